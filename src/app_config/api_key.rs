@@ -0,0 +1,20 @@
+/// 个人 API Key 配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ApiKey {
+    /// 携带 API Key 的请求头
+    pub header: String,
+    /// 每个用户最多可以创建的 API Key 数量
+    pub max_per_user: u64,
+    /// 有效期上限 (天)，0 表示允许创建永不过期的 Key
+    pub max_expire_days: i64,
+}
+impl Default for ApiKey {
+    fn default() -> Self {
+        ApiKey {
+            header: "X-API-Key".to_string(),
+            max_per_user: 20,
+            max_expire_days: 0,
+        }
+    }
+}
@@ -0,0 +1,26 @@
+/// 审计日志配置，审计日志写入文档存储，未启用文档存储时不记录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Audit {
+    /// 审计日志所在的集合
+    pub collection: String,
+    /// 保留天数，通过 TTL 索引自动删除过期记录，0 表示永久保留
+    pub retention_days: u64,
+    /// 内存中等待写入的最大事件数，文档存储不可用时超出的事件会被丢弃
+    pub buffer_size: usize,
+    /// 每批写入的最大事件数
+    pub batch_size: usize,
+    /// 写入间隔 (毫秒)
+    pub flush_interval: u64,
+}
+impl Default for Audit {
+    fn default() -> Self {
+        Audit {
+            collection: "audit_logs".to_string(),
+            retention_days: 180,
+            buffer_size: 10000,
+            batch_size: 100,
+            flush_interval: 1000,
+        }
+    }
+}
@@ -0,0 +1,58 @@
+/// 接口鉴权配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Auth {
+    /// 无需登录即可访问的路径白名单
+    /// - 精确匹配，例如 "/api/users/login"
+    /// - 以 "*" 结尾表示前缀匹配，例如 "/static/*"
+    pub whitelist: Vec<String>,
+    /// 管理员邮箱列表，无需在数据库中授予 admin 角色，用于初始化第一个管理员账号
+    /// 只对已验证邮箱的账号生效
+    pub admins: Vec<String>,
+}
+impl Default for Auth {
+    fn default() -> Self {
+        Auth {
+            whitelist: vec![
+                "/".to_string(),
+                "/health".to_string(),
+                "/ready".to_string(),
+                "/.well-known/jwks.json".to_string(),
+                "/api-docs/openapi.json".to_string(),
+                "/api/users/login".to_string(),
+                "/api/users/login/2fa".to_string(),
+                "/api/users/create".to_string(),
+                "/api/users/verify".to_string(),
+                "/api/users/verify/resend".to_string(),
+                "/api/users/password/forgot".to_string(),
+                "/api/users/password/reset".to_string(),
+                "/api/token/refresh".to_string(),
+                "/api/oidc/login".to_string(),
+                "/api/oidc/callback".to_string(),
+                "/api/oauth/token".to_string(),
+                "/api/oauth/introspect".to_string(),
+                "/api/oauth/revoke".to_string(),
+            ],
+            admins: vec![],
+        }
+    }
+}
+
+impl Auth {
+    /// 判断请求路径是否在白名单中
+    pub fn is_whitelisted(&self, path: &str) -> bool {
+        self.whitelist
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => path.starts_with(prefix),
+                None => path == pattern,
+            })
+    }
+
+    /// 判断邮箱是否为管理员
+    pub fn is_admin(&self, email: &str) -> bool {
+        self.admins
+            .iter()
+            .any(|admin| admin.eq_ignore_ascii_case(email))
+    }
+}
@@ -1,15 +1,19 @@
-use sea_orm::{ConnectOptions, ConnectionTrait, Database, DatabaseConnection};
+use crate::migration::Migrator;
+use sea_orm::{ConnectOptions, ConnectionTrait, Database, DatabaseConnection, DbErr};
+use sea_orm_migration::MigratorTrait;
 
 /// 数据库配置
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default)]
 pub struct Db {
-    /// 数据库路径
+    /// 数据库连接地址，根据协议选择数据库: `sqlite://`、`postgres://`、`mysql://`
     pub url: String,
     /// 最大连接数
     pub max_connections: u32,
     /// 最小连接数
     pub min_connections: u32,
+    /// 启动时自动执行未应用的数据库迁移，关闭后需通过 `migrate up` 命令手动执行
+    pub auto_migrate: bool,
 }
 impl Default for Db {
     fn default() -> Self {
@@ -17,46 +21,57 @@ impl Default for Db {
             url: "sqlite://./data/db.sqlite?mode=rwc".to_string(),
             max_connections: 100,
             min_connections: 3,
+            auto_migrate: true,
         }
     }
 }
 
 impl Db {
-    /// 初始化数据库连接池
-    pub async fn init_db(&self) -> Result<DatabaseConnection, sea_orm::DbErr> {
-        let url = &self.url;
-        let mut opt = ConnectOptions::new(url);
+    /// 连接数据库，不执行迁移
+    pub async fn connect(&self) -> Result<DatabaseConnection, DbErr> {
+        self.check_backend()?;
+        let mut opt = ConnectOptions::new(&self.url);
         opt.max_connections(self.max_connections)
             .min_connections(self.min_connections)
             .sqlx_logging(true);
-        let db = Database::connect(opt).await?;
-        create_db_table(db.clone()).await?; // 确保数据库表存在
-        println!("数据库连接成功: {}", url);
+        Database::connect(opt).await
+    }
+
+    /// 初始化数据库连接池，按配置执行未应用的迁移
+    pub async fn init_db(&self) -> Result<DatabaseConnection, DbErr> {
+        let db = self.connect().await?;
+        if self.auto_migrate {
+            Migrator::up(&db, None).await?;
+        } else {
+            let pending = Migrator::get_pending_migrations(&db).await?;
+            if !pending.is_empty() {
+                println!("数据库有 {} 个迁移未执行，请运行 migrate up", pending.len());
+            }
+        }
+        // 连接地址中可能包含密码，只打印数据库类型
+        println!("数据库连接成功: {:?}", db.get_database_backend());
         Ok(db)
     }
-}
-/// 检查数据库的完整性，不完整的部分给予补充
-async fn create_db_table(pool: DatabaseConnection) -> Result<(), sea_orm::DbErr> {
-    // 新增用户表
-    pool.execute_unprepared("CREATE TABLE IF NOT EXISTS users(
-        id          INTEGER primary key AUTOINCREMENT not null,
-        name        text                              not null,
-        email       char(20) UNIQUE                   not null,
-        pass_word   char(65)                          not null,                                        -- 'passwd hash'
-        create_time datetime                          not null default (datetime('now', 'localtime')), -- 'create datetime'
-        update_time datetime                          not null default (datetime('now', 'localtime')), -- 'update datetime'
-        status      char(10)                          not null default 'normal'                        -- comment 'status: normal, blocked, deleted'
-    )").await.unwrap();
 
-    // 新增设备管理表
-    pool.execute_unprepared("CREATE TABLE IF NOT EXISTS devices(
-        id          INTEGER primary key AUTOINCREMENT not null,           -- 唯一id
-        user_id     INTEGER                           not null,           -- users表中的id
-        token       text                              not null,           -- 设备token
-        create_time datetime                          not null default (datetime('now', 'localtime')), -- 创建时间
-        update_time datetime                          not null default (datetime('now', 'localtime')), -- 更新时间
-        name        text                              null,           -- 设备名称
-        FOREIGN KEY(user_id) REFERENCES users(id)
-    )").await.unwrap();
-    Ok(())
+    /// 检查连接地址对应的数据库是否已在编译时启用
+    fn check_backend(&self) -> Result<(), DbErr> {
+        let scheme = self.url.split_once(':').map(|(scheme, _)| scheme);
+        let (feature, enabled) = match scheme {
+            Some("sqlite") => ("sqlite", cfg!(feature = "sqlite")),
+            Some("postgres" | "postgresql") => ("postgres", cfg!(feature = "postgres")),
+            Some("mysql") => ("mysql", cfg!(feature = "mysql")),
+            _ => {
+                return Err(DbErr::Custom(format!(
+                    "不支持的数据库地址，协议必须是 sqlite、postgres 或 mysql: {}",
+                    scheme.unwrap_or_default()
+                )));
+            }
+        };
+        if !enabled {
+            return Err(DbErr::Custom(format!(
+                "未启用 {feature} 数据库支持，请使用 `--features {feature}` 重新编译"
+            )));
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,51 @@
+use jsonwebtoken::Algorithm;
+
+/// JWT 签名配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Jwt {
+    /// 访问令牌有效期 (秒)
+    pub access_token_expire: i64,
+    /// 刷新令牌有效期 (秒)，每次刷新都会重新计算
+    pub refresh_token_expire: i64,
+    /// 保留用于校验的密钥数量，只有 `keys` 中最新的 N 个密钥签发的 token 才能通过校验
+    pub max_keys: usize,
+    /// 签名密钥列表，按时间顺序排列，最后一个为当前签名密钥
+    /// 未配置任何密钥时会在启动时生成临时 HS256 密钥，重启后已签发的 token 全部失效
+    pub keys: Vec<JwtKey>,
+}
+impl Default for Jwt {
+    fn default() -> Self {
+        Jwt {
+            access_token_expire: 60 * 15,
+            refresh_token_expire: 60 * 60 * 24 * 30,
+            max_keys: 3,
+            keys: vec![],
+        }
+    }
+}
+
+/// 单个 JWT 签名密钥
+#[derive(derive_more::Debug, Serialize, Deserialize, Clone)]
+pub struct JwtKey {
+    /// 密钥 ID，写入 token 头部的 `kid` 字段
+    pub kid: String,
+    /// 签名算法: HS256 | HS384 | HS512 | RS256 | RS384 | RS512 | PS256 | PS384 | PS512 | ES256 | ES384 | EdDSA
+    #[serde(default = "default_algorithm")]
+    pub algorithm: Algorithm,
+    /// HMAC 密钥，仅用于 HS* 算法，优先级最低
+    #[debug("{:?}", secret.as_ref().map(|_| "***"))]
+    pub secret: Option<String>,
+    /// 从文件读取 HMAC 密钥，仅用于 HS* 算法
+    pub secret_file: Option<String>,
+    /// 从环境变量读取 HMAC 密钥，仅用于 HS* 算法，优先级最高
+    pub secret_env: Option<String>,
+    /// PEM 格式私钥路径，用于非对称算法签名，仅保留用于校验的旧密钥可不配置
+    pub private_key_path: Option<String>,
+    /// PEM 格式公钥路径，用于非对称算法校验以及 JWKS 接口
+    pub public_key_path: Option<String>,
+}
+
+fn default_algorithm() -> Algorithm {
+    Algorithm::HS256
+}
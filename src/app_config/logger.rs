@@ -84,12 +84,11 @@ impl Logger {
             .expect("Failed to create file appender")
     }
     pub fn message_time_stamp() -> OffsetTime<Vec<BorrowedFormatItem<'static>>> {
-        let timer = OffsetTime::new(
-            UtcOffset::current_local_offset().unwrap_or_else(|_| UtcOffset::UTC),
+        OffsetTime::new(
+            UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC),
             format_description::parse("[offset_hour sign:mandatory]:[offset_minute] [year]-[month]-[day] [hour]:[minute]:[second]")
                 .expect("Failed to parse time format description"),
-        );
-        timer
+        )
     }
     pub fn max_level(&self) -> tracing::Level {
         match self.max_level.to_uppercase().as_str() {
@@ -0,0 +1,35 @@
+/// 登录防暴力破解配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct LoginGuard {
+    /// 同一账号在统计窗口内允许的连续失败次数，超过后锁定该账号
+    pub max_account_failures: u32,
+    /// 同一 IP 在统计窗口内允许的失败次数，超过后锁定该 IP
+    pub max_ip_failures: u32,
+    /// 失败次数统计窗口 (秒)，窗口内没有新的失败记录时计数清零
+    pub window: i64,
+    /// 锁定时长 (秒)
+    pub lockout: i64,
+    /// 登录失败后的基础延迟 (毫秒)，每多失败一次延迟翻倍
+    pub base_delay_ms: u64,
+    /// 登录失败后的最大延迟 (毫秒)
+    pub max_delay_ms: u64,
+    /// 最多保存的失败记录条数，达到上限后不再记录新的账号或 IP
+    pub max_entries: usize,
+    /// 清理过期失败记录的间隔 (秒)，0 表示不启动清理任务
+    pub prune_interval: u64,
+}
+impl Default for LoginGuard {
+    fn default() -> Self {
+        LoginGuard {
+            max_account_failures: 5,
+            max_ip_failures: 20,
+            window: 60 * 15,
+            lockout: 60 * 15,
+            base_delay_ms: 200,
+            max_delay_ms: 3000,
+            max_entries: 100_000,
+            prune_interval: 60,
+        }
+    }
+}
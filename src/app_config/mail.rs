@@ -0,0 +1,45 @@
+/// 邮件发送配置
+#[derive(derive_more::Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Mail {
+    /// 邮件发送方式
+    /// - "smtp" | "SMTP" 通过 SMTP 服务器发送
+    /// - "file" | "FILE" 写入 `file_dir` 目录下的 .eml 文件，用于开发环境
+    /// - "memory" | "MEMORY" 只保存在内存中，用于测试
+    pub transport: String,
+    /// 发件人，例如 "rust-class-web <noreply@example.com>"
+    pub from: String,
+    /// SMTP 服务器地址
+    pub smtp_host: String,
+    /// SMTP 服务器端口
+    pub smtp_port: u16,
+    /// SMTP 加密方式
+    /// - "starttls" 明文连接后升级为 TLS
+    /// - "tls" 直接使用 TLS 连接
+    /// - "none" 不加密，仅用于本地调试
+    pub smtp_tls: String,
+    /// SMTP 用户名，为空时不进行认证
+    pub smtp_username: String,
+    /// SMTP 密码
+    #[debug("{:?}", smtp_password.as_ref().map(|_| "***"))]
+    pub smtp_password: Option<String>,
+    /// 从环境变量读取 SMTP 密码，优先于 `smtp_password`
+    pub smtp_password_env: Option<String>,
+    /// "file" 方式下邮件的保存目录
+    pub file_dir: String,
+}
+impl Default for Mail {
+    fn default() -> Self {
+        Mail {
+            transport: "file".to_string(),
+            from: "rust-class-web <noreply@localhost>".to_string(),
+            smtp_host: "localhost".to_string(),
+            smtp_port: 587,
+            smtp_tls: "starttls".to_string(),
+            smtp_username: String::new(),
+            smtp_password: None,
+            smtp_password_env: None,
+            file_dir: "./data/mail".to_string(),
+        }
+    }
+}
@@ -1,12 +1,40 @@
+pub mod api_key;
+pub mod audit;
+pub mod auth;
 pub mod db;
+pub mod jwt;
 pub mod logger;
+pub mod login_guard;
+pub mod mail;
 pub mod mongodb;
+pub mod oauth_server;
+pub mod oidc;
+pub mod password;
+pub mod password_reset;
 pub mod server;
+pub mod store;
+pub mod two_factor;
+pub mod user_deletion;
+pub mod verification;
 
+use api_key::ApiKey;
+use audit::Audit;
+use auth::Auth;
 use db::Db;
+use jwt::Jwt;
 use logger::Logger;
+use login_guard::LoginGuard;
+use mail::Mail;
 use mongodb::Mongodb;
+use oauth_server::OAuthServer;
+use oidc::Oidc;
+use password::Password;
+use password_reset::PasswordReset;
 use server::Server;
+use store::Store;
+use two_factor::TwoFactor;
+use user_deletion::UserDeletion;
+use verification::Verification;
 
 #[derive(Serialize, Deserialize, Clone, Default, Debug)]
 #[serde(default)]
@@ -19,6 +47,34 @@ pub struct Config {
     pub logger: Logger,
     /// MongoDB 配置
     pub mongodb: Mongodb,
+    /// 文档存储配置
+    pub store: Store,
+    /// 接口鉴权配置
+    pub auth: Auth,
+    /// 密码哈希配置
+    pub password: Password,
+    /// JWT 签名配置
+    pub jwt: Jwt,
+    /// 登录防暴力破解配置
+    pub login_guard: LoginGuard,
+    /// 邮件发送配置
+    pub mail: Mail,
+    /// 注册邮箱验证配置
+    pub verification: Verification,
+    /// 找回密码配置
+    pub password_reset: PasswordReset,
+    /// 两步验证配置
+    pub two_factor: TwoFactor,
+    /// OIDC 登录配置
+    pub oidc: Oidc,
+    /// OAuth2 授权服务配置
+    pub oauth_server: OAuthServer,
+    /// 个人 API Key 配置
+    pub api_key: ApiKey,
+    /// 审计日志配置
+    pub audit: Audit,
+    /// 删除用户配置
+    pub user_deletion: UserDeletion,
 }
 
 impl Config {
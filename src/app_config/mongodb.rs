@@ -1,37 +1,45 @@
+#[cfg(feature = "mongodb")]
 use mongodb::{
     Client,
-    bson::doc,
     options::{ClientOptions, ServerApi, ServerApiVersion},
 };
+#[cfg(feature = "mongodb")]
+use std::time::Duration;
 
-/// MongoDB 配置
+/// MongoDB 配置，`store.backend` 为 "mongodb" 时使用
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default)]
 pub struct Mongodb {
     /// MongoDB 服务器地址
     pub url: String,
+    /// 文档存储使用的数据库
+    pub database: String,
+    /// 连接超时时间 (秒)
+    pub connect_timeout: u64,
+    /// 连接失败后的重试间隔，也是连接成功后的健康检查间隔 (秒)
+    pub retry_interval: u64,
 }
 impl Default for Mongodb {
     fn default() -> Self {
         Mongodb {
-            url: "mongodb://192.168.64.2:27017".to_string(),
+            url: "mongodb://127.0.0.1:27017".to_string(),
+            database: "rust_class_web".to_string(),
+            connect_timeout: 5,
+            retry_interval: 10,
         }
     }
 }
 
+#[cfg(feature = "mongodb")]
 impl Mongodb {
-    /// 访问 MongoDB 服务器
+    /// 创建 MongoDB 客户端，客户端在首次使用时才会真正建立连接
     pub async fn client(&self) -> Result<Client, mongodb::error::Error> {
-        let url = &self.url;
-        let mut client_options = ClientOptions::parse(url).await?;
+        let mut client_options = ClientOptions::parse(&self.url).await?;
         let server_api = ServerApi::builder().version(ServerApiVersion::V1).build();
         client_options.server_api = Some(server_api);
-        let client = Client::with_options(client_options)?;
-        client
-            .database("admin")
-            .run_command(doc! { "ping": 1 })
-            .await?;
-        println!("MongoDB 连接成功: {url}");
-        Ok(client)
+        let timeout = Duration::from_secs(self.connect_timeout);
+        client_options.connect_timeout = Some(timeout);
+        client_options.server_selection_timeout = Some(timeout);
+        Client::with_options(client_options)
     }
 }
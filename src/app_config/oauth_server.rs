@@ -0,0 +1,17 @@
+/// OAuth2 授权服务配置 (本站作为授权服务器向第三方应用签发令牌)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct OAuthServer {
+    /// 授权码有效期 (秒)，授权码只能使用一次
+    pub code_expire: i64,
+    /// 客户端凭据模式签发的访问令牌有效期 (秒)，该令牌不能刷新
+    pub client_token_expire: i64,
+}
+impl Default for OAuthServer {
+    fn default() -> Self {
+        OAuthServer {
+            code_expire: 60,
+            client_token_expire: 60 * 60,
+        }
+    }
+}
@@ -0,0 +1,68 @@
+/// 外部 OpenID Connect 身份提供方登录配置
+#[derive(derive_more::Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Oidc {
+    /// 是否启用 OIDC 登录
+    pub enabled: bool,
+    /// 身份提供方的 issuer 地址，启动后从 `{issuer}/.well-known/openid-configuration` 加载发现文档
+    pub issuer: String,
+    /// 客户端 ID
+    pub client_id: String,
+    /// 客户端密钥，公开客户端 (仅使用 PKCE) 可不配置
+    #[debug("{:?}", client_secret.as_ref().map(|_| "***"))]
+    pub client_secret: Option<String>,
+    /// 从环境变量读取客户端密钥，优先于 `client_secret`
+    pub client_secret_env: Option<String>,
+    /// 在身份提供方登记的回调地址
+    pub redirect_uri: String,
+    /// 请求的授权范围
+    pub scopes: Vec<String>,
+    /// 首次登录且邮箱未注册时是否自动创建用户
+    pub auto_provision: bool,
+    /// 从跳转到身份提供方到回调完成允许的最长时间 (秒)
+    pub state_expire: i64,
+    /// 发现文档与 JWKS 的缓存时间 (秒)
+    pub metadata_cache: i64,
+    /// 同时等待回调的登录请求上限，达到上限后拒绝新的登录请求
+    pub max_pending: usize,
+    /// 清理超时未回调的登录请求的间隔 (秒)，0 表示不启动清理任务
+    pub prune_interval: u64,
+    /// 请求身份提供方的超时时间 (秒)，包括发现文档、JWKS 与换取令牌
+    pub request_timeout: u64,
+    /// 连接身份提供方的超时时间 (秒)
+    pub connect_timeout: u64,
+}
+impl Default for Oidc {
+    fn default() -> Self {
+        Oidc {
+            enabled: false,
+            issuer: String::new(),
+            client_id: String::new(),
+            client_secret: None,
+            client_secret_env: None,
+            redirect_uri: "https://localhost:8001/api/oidc/callback".to_string(),
+            scopes: vec![
+                "openid".to_string(),
+                "email".to_string(),
+                "profile".to_string(),
+            ],
+            auto_provision: true,
+            state_expire: 60 * 10,
+            metadata_cache: 60 * 60,
+            max_pending: 10_000,
+            prune_interval: 60,
+            request_timeout: 10,
+            connect_timeout: 5,
+        }
+    }
+}
+
+impl Oidc {
+    /// 读取客户端密钥，环境变量优先
+    pub fn client_secret(&self) -> Option<String> {
+        self.client_secret_env
+            .as_ref()
+            .and_then(|name| std::env::var(name).ok())
+            .or_else(|| self.client_secret.clone())
+    }
+}
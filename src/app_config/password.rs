@@ -0,0 +1,35 @@
+/// 密码哈希配置 (Argon2id)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Password {
+    /// 内存开销 (KiB)
+    pub mem_cost: u32,
+    /// 迭代次数
+    pub time_cost: u32,
+    /// 并行度
+    pub lanes: u32,
+}
+impl Default for Password {
+    fn default() -> Self {
+        // OWASP 推荐参数: m=19 MiB, t=2, p=1
+        Password {
+            mem_cost: 19456,
+            time_cost: 2,
+            lanes: 1,
+        }
+    }
+}
+
+impl Password {
+    /// 根据配置生成 argon2 参数
+    pub fn argon2_config(&self) -> argon2::Config<'static> {
+        argon2::Config {
+            variant: argon2::Variant::Argon2id,
+            version: argon2::Version::Version13,
+            mem_cost: self.mem_cost,
+            time_cost: self.time_cost,
+            lanes: self.lanes,
+            ..argon2::Config::default()
+        }
+    }
+}
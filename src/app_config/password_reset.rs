@@ -0,0 +1,20 @@
+/// 找回密码配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PasswordReset {
+    /// 重置令牌有效期 (秒)
+    pub token_expire: i64,
+    /// 重新发送重置邮件的最小间隔 (秒)
+    pub resend_interval: i64,
+    /// 邮件中的重置密码页面地址，令牌以 `?token=` 参数追加在后面
+    pub link_url: String,
+}
+impl Default for PasswordReset {
+    fn default() -> Self {
+        PasswordReset {
+            token_expire: 60 * 30,
+            resend_interval: 60,
+            link_url: "https://localhost:8001/reset-password".to_string(),
+        }
+    }
+}
@@ -2,6 +2,7 @@ use rustls::{
     ServerConfig,
     pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
 };
+use std::net::IpAddr;
 
 /// 服务器启动配置
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -17,6 +18,10 @@ pub struct Server {
     pub tls_cert_path: String,
     /// 密钥路径
     pub tls_key_path: String,
+    /// 在错误响应中返回数据库错误的原始信息，可能包含 SQL 语句，只用于开发调试
+    pub expose_internal_errors: bool,
+    /// 受信任的反向代理，支持单个 IP 与 CIDR，只有来自这些地址的请求才读取 `X-Forwarded-For`
+    pub trusted_proxies: Vec<String>,
 }
 impl Default for Server {
     fn default() -> Self {
@@ -26,6 +31,8 @@ impl Default for Server {
             enabled_tls: false,
             tls_cert_path: "cert.pem".to_string(),
             tls_key_path: "key.pem".to_string(),
+            expose_internal_errors: false,
+            trusted_proxies: Vec::new(),
         }
     }
 }
@@ -34,6 +41,19 @@ impl Server {
     pub fn addr(&self) -> (std::net::IpAddr, u16) {
         (self.host, self.port)
     }
+
+    /// 判断地址是否属于受信任的反向代理
+    pub fn is_trusted_proxy(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies
+            .iter()
+            .any(|proxy| match proxy.split_once('/') {
+                Some((network, prefix)) => match (network.parse(), prefix.parse()) {
+                    (Ok(network), Ok(prefix)) => in_network(ip, network, prefix),
+                    _ => false,
+                },
+                None => proxy.parse() == Ok(ip),
+            })
+    }
     pub fn rustls_config(&self) -> ServerConfig {
         rustls::crypto::aws_lc_rs::default_provider()
             .install_default()
@@ -55,3 +75,18 @@ impl Server {
             .unwrap()
     }
 }
+
+/// 判断 `ip` 是否属于 `network/prefix` 网段，IPv4 与 IPv6 不互相匹配
+fn in_network(ip: IpAddr, network: IpAddr, prefix: u32) -> bool {
+    match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(network)) if prefix <= 32 => {
+            let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(network)) if prefix <= 128 => {
+            let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
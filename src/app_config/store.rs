@@ -0,0 +1,17 @@
+/// 文档存储配置，审计日志等数据保存在文档存储中
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Store {
+    /// 文档存储方式
+    /// - "none" 不启用，依赖文档存储的功能不可用
+    /// - "memory" 保存在内存中，重启后丢失，用于测试与单机部署
+    /// - "mongodb" 保存在 MongoDB 中，需要启用 `mongodb` feature
+    pub backend: String,
+}
+impl Default for Store {
+    fn default() -> Self {
+        Store {
+            backend: "none".to_string(),
+        }
+    }
+}
@@ -0,0 +1,23 @@
+/// 两步验证 (TOTP) 配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct TwoFactor {
+    /// 显示在身份验证器 App 中的发行方名称
+    pub issuer: String,
+    /// 允许的时钟偏差 (时间步数)，1 表示同时接受前后 30 秒的验证码
+    pub skew: i64,
+    /// 登录第二步的挑战令牌有效期 (秒)
+    pub challenge_expire: i64,
+    /// 启用时生成的恢复码数量
+    pub recovery_codes: usize,
+}
+impl Default for TwoFactor {
+    fn default() -> Self {
+        TwoFactor {
+            issuer: "rust-class-web".to_string(),
+            skew: 1,
+            challenge_expire: 60 * 5,
+            recovery_codes: 10,
+        }
+    }
+}
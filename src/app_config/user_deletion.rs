@@ -0,0 +1,17 @@
+/// 删除用户配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct UserDeletion {
+    /// 删除后的保留天数，保留期内管理员可以恢复，过期后由后台任务彻底删除
+    pub retention_days: u64,
+    /// 后台清理任务的执行间隔 (秒)，0 表示不启动清理任务
+    pub purge_interval: u64,
+}
+impl Default for UserDeletion {
+    fn default() -> Self {
+        UserDeletion {
+            retention_days: 30,
+            purge_interval: 3600,
+        }
+    }
+}
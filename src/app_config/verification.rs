@@ -0,0 +1,23 @@
+/// 注册邮箱验证配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Verification {
+    /// 是否允许未验证邮箱的用户登录
+    pub allow_unverified_login: bool,
+    /// 验证令牌有效期 (秒)
+    pub token_expire: i64,
+    /// 重新发送验证邮件的最小间隔 (秒)
+    pub resend_interval: i64,
+    /// 邮件中的验证链接，令牌以 `?token=` 参数追加在后面
+    pub link_url: String,
+}
+impl Default for Verification {
+    fn default() -> Self {
+        Verification {
+            allow_unverified_login: false,
+            token_expire: 60 * 60 * 24,
+            resend_interval: 60,
+            link_url: "https://localhost:8001/api/users/verify".to_string(),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::entity::api_keys;
+use crate::models::api_key::CreatedKey;
+use crate::utils::{serde_timestamp, serde_timestamp_option};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// API Key 信息，不包含 Key 的摘要
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyDto {
+    pub id: i64,
+    pub name: String,
+    /// Key 的开头几位，用于在列表中辨认
+    pub prefix: String,
+    /// 授权范围，空格分隔，为空时拥有用户的全部权限
+    pub scope: Option<String>,
+    /// 过期时间，为空时永不过期
+    #[serde(with = "serde_timestamp_option")]
+    #[schema(value_type = Option<String>, example = "2025-01-01 00:00:00")]
+    pub expire_time: Option<DateTime<Utc>>,
+    #[serde(with = "serde_timestamp_option")]
+    #[schema(value_type = Option<String>, example = "2025-01-01 00:00:00")]
+    pub last_used_time: Option<DateTime<Utc>>,
+    /// 最近一次使用的客户端 IP
+    pub last_used_ip: Option<String>,
+    #[serde(with = "serde_timestamp")]
+    #[schema(value_type = String, example = "2025-01-01 00:00:00")]
+    pub create_time: DateTime<Utc>,
+}
+
+impl From<api_keys::Model> for ApiKeyDto {
+    fn from(api_key: api_keys::Model) -> Self {
+        ApiKeyDto {
+            id: api_key.id,
+            name: api_key.name,
+            prefix: api_key.prefix,
+            scope: api_key.scope,
+            expire_time: api_key.expire_time,
+            last_used_time: api_key.last_used_time,
+            last_used_ip: api_key.last_used_ip,
+            create_time: api_key.create_time,
+        }
+    }
+}
+
+/// 新创建的 API Key，明文只在创建时返回一次
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedKeyDto {
+    /// API Key 明文
+    pub key: String,
+    pub api_key: ApiKeyDto,
+}
+
+impl From<CreatedKey> for CreatedKeyDto {
+    fn from(created: CreatedKey) -> Self {
+        CreatedKeyDto {
+            key: created.key,
+            api_key: created.api_key.into(),
+        }
+    }
+}
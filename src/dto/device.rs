@@ -0,0 +1,38 @@
+use crate::entity::devices;
+use crate::utils::serde_timestamp;
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// 登录设备 (会话) 信息，不包含 token 等敏感字段
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceDto {
+    pub id: i64,
+    pub name: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    #[serde(with = "serde_timestamp")]
+    #[schema(value_type = String, example = "2025-01-01 00:00:00")]
+    pub create_time: DateTime<Utc>,
+    /// 最近活跃时间
+    #[serde(with = "serde_timestamp")]
+    #[schema(value_type = String, example = "2025-01-01 00:00:00")]
+    pub last_active_time: DateTime<Utc>,
+    /// 是否为发起本次请求的会话
+    pub current: bool,
+}
+
+impl DeviceDto {
+    /// `current_device_id` 为发起本次请求的设备
+    pub fn new(device: devices::Model, current_device_id: i64) -> Self {
+        DeviceDto {
+            current: device.id == current_device_id,
+            id: device.id,
+            name: device.name,
+            ip: device.ip,
+            user_agent: device.user_agent,
+            create_time: device.create_time,
+            last_active_time: device.update_time,
+        }
+    }
+}
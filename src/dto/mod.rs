@@ -0,0 +1,75 @@
+//! 接口返回的数据结构
+//!
+//! 接口不直接序列化数据库实体，而是转换为这里的类型后返回，实体新增的字段不会自动出现在响应中，
+//! 避免密码哈希、令牌等敏感字段泄露。OpenAPI 接口文档也由这些类型生成。
+
+pub mod api_key;
+pub mod device;
+pub mod oauth_client;
+pub mod user;
+
+use actix_web::{HttpRequest, HttpResponse, Responder, body::BoxBody};
+use serde::Serialize;
+use std::borrow::Cow;
+use utoipa::ToSchema;
+
+/// 接口统一的响应格式，成功与失败的响应都使用该格式
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiResp<T> {
+    /// 成功时为 200，失败时为 HTTP 状态码
+    pub code: i32,
+    pub data: T,
+    #[schema(value_type = String)]
+    pub message: Cow<'static, str>,
+    /// 错误码，取值见 [`crate::errors::code`]，成功时不返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    pub error_code: Option<&'static str>,
+    /// 参数校验失败的字段，其他情况不返回
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+    /// 请求 ID，出错时返回，用于排查日志
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// 校验失败的字段
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl<T> ApiResp<T> {
+    /// 成功响应，message 为 `ok`
+    pub fn ok(data: T) -> Self {
+        Self::with_message(data, "ok")
+    }
+
+    /// 带提示信息的成功响应
+    pub fn with_message(data: T, message: impl Into<Cow<'static, str>>) -> Self {
+        ApiResp {
+            code: 200,
+            data,
+            message: message.into(),
+            error_code: None,
+            errors: Vec::new(),
+            request_id: None,
+        }
+    }
+}
+
+impl<T: Serialize> Responder for ApiResp<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse {
+        self.into()
+    }
+}
+
+impl<T: Serialize> From<ApiResp<T>> for HttpResponse {
+    fn from(resp: ApiResp<T>) -> Self {
+        HttpResponse::Ok().json(resp)
+    }
+}
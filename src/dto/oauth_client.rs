@@ -0,0 +1,48 @@
+use crate::entity::oauth_clients;
+use crate::utils::serde_timestamp;
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// OAuth2 客户端信息，不包含密钥摘要
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthClientDto {
+    pub id: i64,
+    pub client_id: String,
+    pub name: String,
+    /// 允许的回调地址，空格分隔
+    pub redirect_uris: String,
+    /// 允许申请的授权范围，空格分隔
+    pub scopes: String,
+    /// 允许的授权方式，空格分隔
+    pub grant_types: String,
+    /// 是否为持有密钥的机密客户端
+    pub confidential: bool,
+    #[serde(with = "serde_timestamp")]
+    #[schema(value_type = String, example = "2025-01-01 00:00:00")]
+    pub create_time: DateTime<Utc>,
+}
+
+impl From<oauth_clients::Model> for OAuthClientDto {
+    fn from(client: oauth_clients::Model) -> Self {
+        OAuthClientDto {
+            confidential: client.is_confidential(),
+            id: client.id,
+            client_id: client.client_id,
+            name: client.name,
+            redirect_uris: client.redirect_uris,
+            scopes: client.scopes,
+            grant_types: client.grant_types,
+            create_time: client.create_time,
+        }
+    }
+}
+
+/// 新登记的 OAuth2 客户端，机密客户端的密钥只在登记时返回一次
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedClientDto {
+    pub client: OAuthClientDto,
+    /// 客户端密钥明文，公开客户端为空
+    pub client_secret: Option<String>,
+}
@@ -0,0 +1,70 @@
+use crate::entity::users;
+use crate::models::session::TokenPair;
+use crate::utils::{serde_timestamp, serde_timestamp_option};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+/// 用户信息
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UserDto {
+    pub id: i64,
+    pub name: String,
+    pub email: String,
+    /// 账号状态，`normal` 为正常，`pending` 为邮箱待验证，`deleted` 为已删除
+    pub status: String,
+    #[serde(with = "serde_timestamp")]
+    #[schema(value_type = String, example = "2025-01-01 00:00:00")]
+    pub create_time: DateTime<Utc>,
+    #[serde(with = "serde_timestamp")]
+    #[schema(value_type = String, example = "2025-01-01 00:00:00")]
+    pub update_time: DateTime<Utc>,
+    /// 删除时间，只有已删除的用户返回
+    #[serde(
+        with = "serde_timestamp_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schema(value_type = Option<String>, example = "2025-01-01 00:00:00")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// 待验证的新邮箱，验证后替换 `email`，没有修改邮箱时不返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pending_email: Option<String>,
+}
+
+impl From<users::Model> for UserDto {
+    fn from(user: users::Model) -> Self {
+        UserDto {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            status: user.status,
+            create_time: user.create_time,
+            update_time: user.update_time,
+            deleted_at: user.deleted_at,
+            pending_email: user.pending_email,
+        }
+    }
+}
+
+/// 登录成功返回的用户信息与令牌
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginDto {
+    pub user: UserDto,
+    /// 访问令牌
+    pub token: String,
+    pub refresh_token: String,
+    /// 访问令牌的有效期 (秒)
+    pub expires_in: i64,
+}
+
+impl LoginDto {
+    pub fn new(user: users::Model, tokens: TokenPair) -> Self {
+        LoginDto {
+            user: user.into(),
+            token: tokens.token,
+            refresh_token: tokens.refresh_token,
+            expires_in: tokens.expires_in,
+        }
+    }
+}
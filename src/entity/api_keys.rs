@@ -0,0 +1,55 @@
+use crate::utils::{serde_timestamp, serde_timestamp_option};
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 用户为脚本、CI 等创建的个人 API Key
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "api_keys")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub user_id: i64,
+    pub name: String,
+    /// Key 的开头几位，用于在列表中辨认
+    pub prefix: String,
+    /// Key 的 SHA-256 摘要
+    #[sea_orm(unique)]
+    #[serde(skip)]
+    pub key_hash: String,
+    /// 授权范围，空格分隔，为空时拥有用户的全部权限
+    #[sea_orm(nullable)]
+    pub scope: Option<String>,
+    #[sea_orm(nullable)]
+    #[serde(default, with = "serde_timestamp_option")]
+    pub expire_time: Option<DateTime<Utc>>,
+    #[sea_orm(nullable)]
+    #[serde(default, with = "serde_timestamp_option")]
+    pub last_used_time: Option<DateTime<Utc>>,
+    /// 最近一次使用的客户端 IP
+    #[sea_orm(nullable)]
+    pub last_used_ip: Option<String>,
+    #[serde(with = "serde_timestamp")]
+    pub create_time: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Users,
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Users.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
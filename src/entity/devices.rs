@@ -1,4 +1,4 @@
-use crate::utils::serde_timestamp;
+use crate::utils::{serde_timestamp, serde_timestamp_option};
 use chrono::{DateTime, Utc};
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -10,6 +10,8 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i64,
     pub user_id: i64,
+    /// 访问令牌，不会出现在序列化结果中
+    #[serde(skip_serializing)]
     pub token: String,
     #[serde(with = "serde_timestamp")]
     pub create_time: DateTime<Utc>,
@@ -17,6 +19,29 @@ pub struct Model {
     pub update_time: DateTime<Utc>,
     #[sea_orm(nullable)]
     pub name: Option<String>,
+    /// 刷新令牌链标识，同一设备轮换刷新令牌时保持不变
+    #[sea_orm(nullable, unique)]
+    #[serde(skip)]
+    pub refresh_family: Option<String>,
+    /// 当前有效刷新令牌的 SHA-256 哈希
+    #[sea_orm(nullable)]
+    #[serde(skip)]
+    pub refresh_token_hash: Option<String>,
+    #[sea_orm(nullable)]
+    #[serde(default, with = "serde_timestamp_option")]
+    pub refresh_expire_time: Option<DateTime<Utc>>,
+    /// 最近一次访问的客户端 IP
+    #[sea_orm(nullable)]
+    pub ip: Option<String>,
+    /// 登录时的 User-Agent
+    #[sea_orm(nullable)]
+    pub user_agent: Option<String>,
+    /// 通过 OAuth2 授权给第三方应用时的客户端 ID，本站登录为空
+    #[sea_orm(nullable)]
+    pub client_id: Option<String>,
+    /// OAuth2 授权范围，空格分隔，本站登录为空
+    #[sea_orm(nullable)]
+    pub scope: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
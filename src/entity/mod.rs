@@ -1,2 +1,13 @@
+pub mod api_keys;
 pub mod devices;
+pub mod oauth_clients;
+pub mod oauth_consents;
+pub mod permissions;
+pub mod recovery_codes;
+pub mod role_permissions;
+pub mod roles;
+pub mod user_identities;
+pub mod user_roles;
+pub mod user_tokens;
+pub mod user_totp;
 pub mod users;
@@ -0,0 +1,61 @@
+use crate::utils::serde_timestamp;
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 接入本站账号体系的 OAuth2 客户端 (第三方应用)
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "oauth_clients")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    #[sea_orm(unique)]
+    pub client_id: String,
+    /// 客户端密钥的 SHA-256 摘要，公开客户端 (SPA、移动端) 为空
+    #[sea_orm(nullable)]
+    #[serde(skip)]
+    pub client_secret_hash: Option<String>,
+    pub name: String,
+    /// 允许的回调地址，空格分隔，必须完全匹配
+    pub redirect_uris: String,
+    /// 允许申请的授权范围，空格分隔
+    pub scopes: String,
+    /// 允许的授权方式，空格分隔
+    pub grant_types: String,
+    #[serde(with = "serde_timestamp")]
+    pub create_time: DateTime<Utc>,
+}
+
+impl Model {
+    /// 是否为持有密钥的机密客户端
+    pub fn is_confidential(&self) -> bool {
+        self.client_secret_hash.is_some()
+    }
+
+    pub fn allows_redirect_uri(&self, redirect_uri: &str) -> bool {
+        self.redirect_uris
+            .split_whitespace()
+            .any(|uri| uri == redirect_uri)
+    }
+
+    pub fn allows_grant_type(&self, grant_type: &str) -> bool {
+        self.grant_types
+            .split_whitespace()
+            .any(|grant| grant == grant_type)
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::oauth_consents::Entity")]
+    OauthConsents,
+}
+
+impl Related<super::oauth_consents::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::OauthConsents.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
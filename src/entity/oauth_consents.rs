@@ -0,0 +1,55 @@
+use crate::utils::serde_timestamp;
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 用户对 OAuth2 客户端的授权记录，再次授权相同范围时不需要重复确认
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "oauth_consents")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub user_id: i64,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub client_id: String,
+    /// 用户同意的授权范围，空格分隔
+    pub scope: String,
+    #[serde(with = "serde_timestamp")]
+    pub create_time: DateTime<Utc>,
+    #[serde(with = "serde_timestamp")]
+    pub update_time: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Users,
+    #[sea_orm(
+        belongs_to = "super::oauth_clients::Entity",
+        from = "Column::ClientId",
+        to = "super::oauth_clients::Column::ClientId",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    OauthClients,
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Users.def()
+    }
+}
+
+impl Related<super::oauth_clients::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::OauthClients.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,45 @@
+use crate::utils::serde_timestamp;
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 用户在外部身份提供方的账号，同一个 (issuer, subject) 只能关联一个用户
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "user_identities")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub user_id: i64,
+    /// 身份提供方的 issuer
+    pub issuer: String,
+    /// 身份提供方中的用户标识 (ID Token 的 sub)
+    pub subject: String,
+    /// 最近一次登录时身份提供方返回的邮箱
+    #[sea_orm(nullable)]
+    pub email: Option<String>,
+    #[serde(with = "serde_timestamp")]
+    pub create_time: DateTime<Utc>,
+    #[serde(with = "serde_timestamp")]
+    pub update_time: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Users,
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Users.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,46 @@
+use crate::utils::{serde_timestamp, serde_timestamp_option};
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 用户的 TOTP 两步验证密钥，每个用户最多一条
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "user_totp")]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub user_id: i64,
+    /// Base32 编码的共享密钥
+    #[serde(skip)]
+    pub secret: String,
+    /// 是否已通过首个验证码确认启用
+    pub enabled: bool,
+    /// 最近一次验证通过的时间步，同一时间步内的验证码不能重复使用
+    #[serde(skip)]
+    pub last_used_step: i64,
+    #[serde(with = "serde_timestamp")]
+    pub create_time: DateTime<Utc>,
+    #[sea_orm(nullable)]
+    #[serde(default, with = "serde_timestamp_option")]
+    pub enable_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::UserId",
+        to = "super::users::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Users,
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Users.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
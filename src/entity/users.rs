@@ -1,8 +1,16 @@
-use crate::utils::serde_timestamp;
+use crate::utils::{serde_timestamp, serde_timestamp_option};
 use chrono::{DateTime, Utc};
+use sea_orm::Set;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
+/// 正常状态
+pub const STATUS_NORMAL: &str = "normal";
+/// 已注册但邮箱尚未验证
+pub const STATUS_PENDING: &str = "pending";
+/// 已删除，保留期内可以恢复，之后由后台任务彻底删除
+pub const STATUS_DELETED: &str = "deleted";
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, Validate)]
 #[sea_orm(table_name = "users")]
 #[serde(rename_all = "camelCase")]
@@ -13,15 +21,53 @@ pub struct Model {
     #[sea_orm(unique)]
     #[validate(email)]
     pub email: String,
+    /// 密码哈希，不会出现在序列化结果中
+    #[serde(skip_serializing)]
     pub pass_word: String,
+    /// 账号状态: `normal`, `pending`, `deleted`
     pub status: String,
     #[serde(with = "serde_timestamp")]
     pub create_time: DateTime<Utc>,
     #[serde(with = "serde_timestamp")]
     pub update_time: DateTime<Utc>,
+    /// 删除时间，未删除时为空
+    #[serde(default, with = "serde_timestamp_option")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// 删除前的账号状态，恢复时还原
+    #[serde(skip)]
+    pub status_before_delete: Option<String>,
+    /// 修改邮箱后待验证的新邮箱，验证后替换 `email`
+    #[serde(default)]
+    pub pending_email: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {}
 
-impl ActiveModelBehavior for ActiveModel {}
+impl Entity {
+    /// 查询未删除的用户，需要包含已删除的用户时使用 `find`
+    pub fn find_active() -> Select<Entity> {
+        Self::find().filter(Column::Status.ne(STATUS_DELETED))
+    }
+
+    /// 按 ID 查询未删除的用户
+    pub fn find_active_by_id(id: i64) -> Select<Entity> {
+        Self::find_active().filter(Column::Id.eq(id))
+    }
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    /// 通过 `ActiveModel` 写入时自动维护创建时间与更新时间，`update_many` 不会触发
+    async fn before_save<C>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let now = Utc::now();
+        if insert && self.create_time.is_not_set() {
+            self.create_time = Set(now);
+        }
+        self.update_time = Set(now);
+        Ok(self)
+    }
+}
@@ -1,8 +1,91 @@
+use crate::dto::{ApiResp, FieldError};
+use crate::mw::current_request_id;
 use actix_web::{HttpResponse, ResponseError, http::StatusCode};
 use jsonwebtoken::errors::Error as JwtError;
-use sea_orm::DbErr;
-use serde::Serialize;
+use sea_orm::sqlx::{self, error::ErrorKind};
+use sea_orm::{DbErr, RuntimeErr};
 use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// 错误码，与 HTTP 状态码相互独立，客户端可以据此区分具体的错误原因
+///
+/// 错误码发布后不再修改含义，错误信息 `message` 只用于展示，可能随版本调整
+pub mod code {
+    // 通用错误，未指定错误码时按错误类型返回
+    pub const BAD_REQUEST: &str = "BAD_REQUEST";
+    pub const VALIDATION_FAILED: &str = "VALIDATION_FAILED";
+    pub const UNAUTHORIZED: &str = "UNAUTHORIZED";
+    pub const INVALID_TOKEN: &str = "INVALID_TOKEN";
+    pub const FORBIDDEN: &str = "FORBIDDEN";
+    pub const NOT_FOUND: &str = "NOT_FOUND";
+    pub const CONFLICT: &str = "CONFLICT";
+    pub const REQUEST_TIMEOUT: &str = "REQUEST_TIMEOUT";
+    pub const TOO_MANY_REQUESTS: &str = "TOO_MANY_REQUESTS";
+    pub const SERVICE_UNAVAILABLE: &str = "SERVICE_UNAVAILABLE";
+    pub const INTERNAL_ERROR: &str = "INTERNAL_ERROR";
+    pub const DATABASE_ERROR: &str = "DATABASE_ERROR";
+    /// 违反唯一约束，数据已存在
+    pub const DUPLICATE_ENTRY: &str = "DUPLICATE_ENTRY";
+    /// 违反外键约束，关联的数据不存在或仍被引用
+    pub const INVALID_REFERENCE: &str = "INVALID_REFERENCE";
+    /// 违反非空约束
+    pub const MISSING_FIELD: &str = "MISSING_FIELD";
+    /// 违反检查约束
+    pub const INVALID_VALUE: &str = "INVALID_VALUE";
+
+    // 业务错误
+    /// 请求体不是合法的 JSON 或字段类型不匹配
+    pub const INVALID_BODY: &str = "INVALID_BODY";
+    /// 查询参数格式错误
+    pub const INVALID_QUERY: &str = "INVALID_QUERY";
+    pub const USER_NOT_FOUND: &str = "USER_NOT_FOUND";
+    pub const EMAIL_TAKEN: &str = "EMAIL_TAKEN";
+    /// 邮箱或密码错误
+    pub const LOGIN_FAILED: &str = "LOGIN_FAILED";
+    pub const EMAIL_NOT_VERIFIED: &str = "EMAIL_NOT_VERIFIED";
+    /// 登录失败次数过多，账号或 IP 被暂时锁定
+    pub const ACCOUNT_LOCKED: &str = "ACCOUNT_LOCKED";
+    /// 缺少接口所需的权限
+    pub const PERMISSION_DENIED: &str = "PERMISSION_DENIED";
+    pub const SESSION_NOT_FOUND: &str = "SESSION_NOT_FOUND";
+    /// 两步验证码或恢复码错误
+    pub const INVALID_TWO_FACTOR_CODE: &str = "INVALID_TWO_FACTOR_CODE";
+    /// 已删除的用户超过保留期，无法恢复
+    pub const RESTORE_EXPIRED: &str = "RESTORE_EXPIRED";
+}
+
+/// 是否在响应中返回数据库错误的原始信息，见 `server.expose_internal_errors`
+static EXPOSE_INTERNAL_ERRORS: AtomicBool = AtomicBool::new(false);
+
+/// 设置是否在响应中返回数据库错误的原始信息，只应在开发环境开启
+pub fn set_expose_internal_errors(expose: bool) {
+    EXPOSE_INTERNAL_ERRORS.store(expose, Ordering::Relaxed);
+}
+
+/// 数据库约束的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Constraint {
+    Unique,
+    ForeignKey,
+    NotNull,
+    Check,
+}
+
+/// 判断数据库错误是否由违反约束引起，各数据库的错误码由驱动统一转换
+pub fn constraint_violation(e: &DbErr) -> Option<Constraint> {
+    let (DbErr::Exec(RuntimeErr::SqlxError(sqlx::Error::Database(db)))
+    | DbErr::Query(RuntimeErr::SqlxError(sqlx::Error::Database(db)))) = e
+    else {
+        return None;
+    };
+    match db.kind() {
+        ErrorKind::UniqueViolation => Some(Constraint::Unique),
+        ErrorKind::ForeignKeyViolation => Some(Constraint::ForeignKey),
+        ErrorKind::NotNullViolation => Some(Constraint::NotNull),
+        ErrorKind::CheckViolation => Some(Constraint::Check),
+        _ => None,
+    }
+}
 
 /// 应用程序的统一错误类型
 #[derive(Debug)]
@@ -11,8 +94,8 @@ pub enum AppError {
     DbError(DbErr),
     /// JWT 相关错误
     JwtError(JwtError),
-    /// 校验失败错误，包含错误信息
-    ValidationError(String),
+    /// 校验失败错误，包含各字段的错误信息
+    ValidationError(Vec<FieldError>),
     /// 资源未找到错误，包含错误信息
     NotFound(String),
     /// 未授权访问错误，包含错误信息
@@ -31,13 +114,59 @@ pub enum AppError {
     BadRequest(String),
     /// 服务不可用，包含错误信息
     ServiceUnavailable(String),
+    /// 请求过于频繁，包含错误信息
+    TooManyRequests(String),
+    /// 指定了错误码的错误，HTTP 状态码与错误信息由内部的错误决定
+    Coded(&'static str, Box<AppError>),
 }
 
-#[derive(Serialize)]
-struct ErrorResponse {
-    code: i32,
-    message: String,
-    data: (),
+impl AppError {
+    /// 指定返回给客户端的错误码，取值见 [`code`]
+    pub fn with_code(self, code: &'static str) -> Self {
+        match self {
+            AppError::Coded(_, inner) => AppError::Coded(code, inner),
+            other => AppError::Coded(code, Box::new(other)),
+        }
+    }
+
+    /// 返回给客户端的错误码，未指定时按错误类型返回通用错误码
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Coded(code, _) => code,
+            AppError::DbError(_) => code::DATABASE_ERROR,
+            AppError::JwtError(_) => code::INVALID_TOKEN,
+            AppError::ValidationError(_) => code::VALIDATION_FAILED,
+            AppError::NotFound(_) => code::NOT_FOUND,
+            AppError::Unauthorized(_) => code::UNAUTHORIZED,
+            AppError::InternalError(_) => code::INTERNAL_ERROR,
+            AppError::ParseError(_) | AppError::BadRequest(_) => code::BAD_REQUEST,
+            AppError::Conflict(_) => code::CONFLICT,
+            AppError::Forbidden(_) => code::FORBIDDEN,
+            AppError::Timeout(_) => code::REQUEST_TIMEOUT,
+            AppError::ServiceUnavailable(_) => code::SERVICE_UNAVAILABLE,
+            AppError::TooManyRequests(_) => code::TOO_MANY_REQUESTS,
+        }
+    }
+
+    /// 返回给客户端的错误信息，数据库错误可能包含 SQL 语句，默认不返回原始信息
+    fn message(&self) -> String {
+        match self {
+            AppError::DbError(_) if !EXPOSE_INTERNAL_ERRORS.load(Ordering::Relaxed) => {
+                "数据库操作失败".to_string()
+            }
+            AppError::Coded(_, inner) => inner.message(),
+            other => other.to_string(),
+        }
+    }
+
+    /// 参数校验失败的字段
+    fn fields(&self) -> &[FieldError] {
+        match self {
+            AppError::ValidationError(fields) => fields,
+            AppError::Coded(_, inner) => inner.fields(),
+            _ => &[],
+        }
+    }
 }
 
 impl fmt::Display for AppError {
@@ -45,8 +174,7 @@ impl fmt::Display for AppError {
         match self {
             AppError::DbError(e) => write!(f, "{e}"),
             AppError::JwtError(e) => write!(f, "{e}"),
-            AppError::ValidationError(m)
-            | AppError::NotFound(m)
+            AppError::NotFound(m)
             | AppError::Unauthorized(m)
             | AppError::InternalError(m)
             | AppError::ParseError(m)
@@ -54,7 +182,13 @@ impl fmt::Display for AppError {
             | AppError::Forbidden(m)
             | AppError::Timeout(m)
             | AppError::BadRequest(m)
-            | AppError::ServiceUnavailable(m) => write!(f, "{m}"),
+            | AppError::ServiceUnavailable(m)
+            | AppError::TooManyRequests(m) => write!(f, "{m}"),
+            AppError::ValidationError(fields) => {
+                let messages: Vec<_> = fields.iter().map(|field| field.message.as_str()).collect();
+                write!(f, "{}", messages.join(", "))
+            }
+            AppError::Coded(_, inner) => write!(f, "{inner}"),
         }
     }
 }
@@ -72,23 +206,45 @@ impl ResponseError for AppError {
             AppError::Forbidden(_) => StatusCode::FORBIDDEN,
             AppError::Timeout(_) => StatusCode::REQUEST_TIMEOUT,
             AppError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Coded(_, inner) => inner.status_code(),
         }
     }
     fn error_response(&self) -> HttpResponse {
         let status = self.status_code();
-        let message = self.to_string();
-        let code = status.as_u16() as i32;
-        HttpResponse::build(status).json(ErrorResponse {
-            code,
-            message,
+        HttpResponse::build(status).json(ApiResp {
+            code: status.as_u16() as i32,
             data: (),
+            message: self.message().into(),
+            error_code: Some(self.code()),
+            errors: self.fields().to_vec(),
+            request_id: current_request_id(),
         })
     }
 }
 
+/// 违反约束的错误转换为对应的客户端错误，原始错误只记录到日志
 impl From<DbErr> for AppError {
     fn from(e: DbErr) -> Self {
-        AppError::DbError(e)
+        let Some(constraint) = constraint_violation(&e) else {
+            error!("数据库错误: {e}");
+            return AppError::DbError(e);
+        };
+        warn!("违反数据库 {constraint:?} 约束: {e}");
+        match constraint {
+            Constraint::Unique => {
+                AppError::Conflict("数据已存在".to_string()).with_code(code::DUPLICATE_ENTRY)
+            }
+            Constraint::ForeignKey => {
+                AppError::BadRequest("关联的数据不存在或仍被引用".to_string())
+                    .with_code(code::INVALID_REFERENCE)
+            }
+            Constraint::NotNull => {
+                AppError::BadRequest("缺少必填字段".to_string()).with_code(code::MISSING_FIELD)
+            }
+            Constraint::Check => AppError::BadRequest("字段取值不符合要求".to_string())
+                .with_code(code::INVALID_VALUE),
+        }
     }
 }
 impl From<JwtError> for AppError {
@@ -0,0 +1,78 @@
+use crate::dto::ApiResp;
+use crate::errors::{AppError, code};
+use crate::models::audit::{AuditFilter, AuditRecord};
+use crate::models::rbac::{Permissions, perm};
+use crate::state::AppState;
+use crate::utils::serde_timestamp_option;
+use actix_web::{Result, web};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+/// 每次查询返回的默认条数
+const DEFAULT_LIMIT: u64 = 50;
+/// 每次查询返回的最大条数
+const MAX_LIMIT: u64 = 500;
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+struct AuditQuery {
+    actor_id: Option<i64>,
+    action: Option<String>,
+    /// 起始时间 (包含)，格式 `2025-01-01 00:00:00`
+    #[serde(default, with = "serde_timestamp_option")]
+    #[param(value_type = Option<String>)]
+    from: Option<DateTime<Utc>>,
+    /// 结束时间 (不包含)
+    #[serde(default, with = "serde_timestamp_option")]
+    #[param(value_type = Option<String>)]
+    to: Option<DateTime<Utc>>,
+    #[serde(default)]
+    offset: u64,
+    /// 返回条数，默认 50，最大 500
+    limit: Option<u64>,
+}
+
+/// 查询审计日志，可按操作者、事件类型与时间范围过滤，按时间倒序返回
+#[utoipa::path(
+    tag = "admin",
+    params(AuditQuery),
+    responses((status = 200, body = ApiResp<Vec<AuditRecord>>)),
+    security(("bearer" = []))
+)]
+#[get("/admin/audit-logs")]
+pub async fn list_audit_logs(
+    query: Result<web::Query<AuditQuery>>,
+    permissions: Permissions,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<Vec<AuditRecord>>, AppError> {
+    permissions.require(perm::AUDIT_READ)?;
+    let query = query
+        // 错误信息由 `QueryConfig` 生成，这里只是推迟到权限检查之后返回
+        .map_err(|e| AppError::BadRequest(e.to_string()).with_code(code::INVALID_QUERY))?
+        .into_inner();
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(AppError::BadRequest(format!(
+            "limit 必须在 1 到 {MAX_LIMIT} 之间"
+        )));
+    }
+    if let (Some(from), Some(to)) = (query.from, query.to)
+        && from >= to
+    {
+        return Err(AppError::BadRequest("起始时间必须早于结束时间".to_string()));
+    }
+    let records = app_data
+        .audit
+        .query(&AuditFilter {
+            actor_id: query.actor_id,
+            action: query.action,
+            from: query.from,
+            to: query.to,
+            offset: query.offset,
+            limit,
+        })
+        .await?;
+    Ok(ApiResp::ok(records))
+}
@@ -0,0 +1,47 @@
+use crate::dto::ApiResp;
+use crate::errors::AppError;
+use crate::models::login_guard::{LockoutInfo, LockoutKind};
+use crate::models::rbac::{Permissions, perm};
+use crate::state::AppState;
+use crate::utils::extract_path_param;
+use actix_web::{Result, web};
+
+/// 列出登录失败记录与锁定状态
+#[utoipa::path(
+    tag = "admin",
+    responses((status = 200, body = ApiResp<Vec<LockoutInfo>>)),
+    security(("bearer" = []))
+)]
+#[get("/admin/lockouts")]
+pub async fn list_lockouts(
+    permissions: Permissions,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<Vec<LockoutInfo>>, AppError> {
+    permissions.require(perm::LOGIN_LOCKOUT)?;
+    Ok(ApiResp::ok(app_data.login_guard.list()))
+}
+
+/// 清除某个账号或 IP 的失败记录并解除锁定
+#[utoipa::path(
+    tag = "admin",
+    params(
+        ("kind" = LockoutKind, Path, description = "锁定类型"),
+        ("key" = String, Path, description = "邮箱或 IP"),
+    ),
+    responses((status = 200, body = ApiResp<bool>)),
+    security(("bearer" = []))
+)]
+#[delete("/admin/lockouts/{kind}/{key}")]
+pub async fn clear_lockout(
+    path: Result<web::Path<(LockoutKind, String)>>,
+    permissions: Permissions,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<bool>, AppError> {
+    permissions.require(perm::LOGIN_LOCKOUT)?;
+    let (kind, key) = extract_path_param(path, "锁定类型")?;
+    if !app_data.login_guard.clear(kind, &key) {
+        return Err(AppError::NotFound(format!("{key} 没有失败记录")));
+    }
+    info!("管理员 {} 清除了 {key} 的登录锁定", permissions.user.id);
+    Ok(ApiResp::ok(true))
+}
@@ -0,0 +1,53 @@
+use crate::dto::ApiResp;
+use crate::entity::{devices, users};
+use crate::errors::{AppError, code};
+use crate::models::audit::{AuditEvent, Outcome, action};
+use crate::models::client_info::ClientInfo;
+use crate::models::rbac::{Permissions, perm};
+use crate::state::AppState;
+use crate::utils::extract_path_param;
+use actix_web::{Result, web};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+/// 管理员强制指定用户退出所有会话，返回撤销的会话数量
+#[utoipa::path(
+    tag = "admin",
+    params(("id" = i64, Path, description = "用户ID")),
+    responses((status = 200, body = ApiResp<u64>)),
+    security(("bearer" = []))
+)]
+#[post("/admin/users/{id}/logout")]
+pub async fn force_logout(
+    id: Result<web::Path<i64>>,
+    permissions: Permissions,
+    client: ClientInfo,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<u64>, AppError> {
+    permissions.require(perm::USER_LOGOUT)?;
+    let user_id = extract_path_param(id, "用户ID")?;
+    if users::Entity::find_active_by_id(user_id)
+        .one(&app_data.db_pool)
+        .await?
+        .is_none()
+    {
+        return Err(
+            AppError::NotFound(format!("用户ID {user_id} 不存在")).with_code(code::USER_NOT_FOUND)
+        );
+    }
+    let delete_result = devices::Entity::delete_many()
+        .filter(devices::Column::UserId.eq(user_id))
+        .exec(&app_data.db_pool)
+        .await?;
+    warn!(
+        "管理员 {} 强制用户 {} 退出登录，撤销会话 {} 个",
+        permissions.user.id, user_id, delete_result.rows_affected
+    );
+    app_data.audit.record(
+        AuditEvent::new(action::LOGOUT, Outcome::Success, &client)
+            .actor(permissions.user.id, &permissions.user.email)
+            .target(user_id)
+            .detail("forced"),
+    );
+
+    Ok(ApiResp::ok(delete_result.rows_affected))
+}
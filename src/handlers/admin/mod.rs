@@ -0,0 +1,7 @@
+pub mod audit;
+pub mod lockout;
+pub mod logout;
+pub mod oauth_client;
+pub mod restore;
+pub mod role;
+pub mod two_factor;
@@ -0,0 +1,184 @@
+use crate::dto::ApiResp;
+use crate::dto::oauth_client::{CreatedClientDto, OAuthClientDto};
+use crate::entity::{devices, oauth_clients, oauth_consents};
+use crate::errors::AppError;
+use crate::models::oauth::{PROFILE_SCOPE, grant_type};
+use crate::models::rbac::{Permissions, perm, permission_codes};
+use crate::models::secure_token;
+use crate::state::AppState;
+use crate::utils::{extract_path_param, validate_params};
+use actix_web::{Result, web};
+use chrono::Utc;
+use reqwest::Url;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::NotSet, ColumnTrait, EntityTrait, QueryFilter, Set,
+    TransactionTrait,
+};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
+
+fn default_grant_types() -> Vec<String> {
+    vec![
+        grant_type::AUTHORIZATION_CODE.to_string(),
+        grant_type::REFRESH_TOKEN.to_string(),
+    ]
+}
+
+fn default_confidential() -> bool {
+    true
+}
+
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+struct CreateClientReq {
+    #[validate(length(min = 1, max = 64, message = "客户端名称长度必须在 1 到 64 之间"))]
+    name: String,
+    #[serde(default)]
+    redirect_uris: Vec<String>,
+    /// 允许申请的授权范围，取值为权限编码或 `profile`
+    #[validate(length(min = 1, message = "授权范围不能为空"))]
+    scopes: Vec<String>,
+    #[serde(default = "default_grant_types")]
+    grant_types: Vec<String>,
+    /// 是否为机密客户端，公开客户端不签发密钥，必须使用 PKCE
+    #[serde(default = "default_confidential")]
+    confidential: bool,
+}
+
+impl CreateClientReq {
+    /// 校验回调地址、授权方式与授权范围
+    async fn check(&self, app_data: &AppState) -> Result<(), AppError> {
+        for uri in &self.redirect_uris {
+            let url = Url::parse(uri)
+                .map_err(|_| AppError::BadRequest(format!("无效的回调地址 {uri}")))?;
+            if url.fragment().is_some() {
+                return Err(AppError::BadRequest(format!("回调地址不能包含片段 {uri}")));
+            }
+        }
+        for grant in &self.grant_types {
+            if !grant_type::ALL.contains(&grant.as_str()) {
+                return Err(AppError::BadRequest(format!("不支持的授权方式 {grant}")));
+            }
+        }
+        let has_grant = |grant: &str| self.grant_types.iter().any(|g| g == grant);
+        if has_grant(grant_type::AUTHORIZATION_CODE) && self.redirect_uris.is_empty() {
+            return Err(AppError::BadRequest(
+                "授权码模式至少需要一个回调地址".to_string(),
+            ));
+        }
+        if has_grant(grant_type::CLIENT_CREDENTIALS) && !self.confidential {
+            return Err(AppError::BadRequest(
+                "公开客户端不能使用客户端凭据模式".to_string(),
+            ));
+        }
+        let known = permission_codes(&app_data.db_pool).await?;
+        if let Some(scope) = self
+            .scopes
+            .iter()
+            .find(|scope| *scope != PROFILE_SCOPE && !known.contains(*scope))
+        {
+            return Err(AppError::BadRequest(format!("未知的授权范围 {scope}")));
+        }
+        Ok(())
+    }
+}
+
+/// 登记 OAuth2 客户端，机密客户端的密钥只在此时返回一次
+#[utoipa::path(
+    tag = "admin",
+    request_body = CreateClientReq,
+    responses((status = 200, body = ApiResp<CreatedClientDto>)),
+    security(("bearer" = []))
+)]
+#[post("/admin/oauth/clients")]
+pub async fn create_client(
+    params: web::Json<CreateClientReq>,
+    permissions: Permissions,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<CreatedClientDto>, AppError> {
+    permissions.require(perm::OAUTH_CLIENT)?;
+    validate_params(&*params)?;
+    params.check(&app_data).await?;
+
+    let client_secret = params.confidential.then(secure_token::generate);
+    let client = oauth_clients::ActiveModel {
+        id: NotSet,
+        client_id: Set(uuid::Uuid::new_v4().simple().to_string()),
+        client_secret_hash: Set(client_secret.as_deref().map(secure_token::digest)),
+        name: Set(params.name.clone()),
+        redirect_uris: Set(params.redirect_uris.join(" ")),
+        scopes: Set(params.scopes.join(" ")),
+        grant_types: Set(params.grant_types.join(" ")),
+        create_time: Set(Utc::now()),
+    }
+    .insert(&app_data.db_pool)
+    .await?;
+    info!(
+        "管理员 {} 登记了 OAuth2 客户端 {} ({})",
+        permissions.user.id, client.client_id, client.name
+    );
+    Ok(ApiResp::with_message(
+        CreatedClientDto {
+            client: client.into(),
+            client_secret,
+        },
+        "请妥善保存客户端密钥，之后无法再次查看",
+    ))
+}
+
+/// 列出所有 OAuth2 客户端
+#[utoipa::path(
+    tag = "admin",
+    responses((status = 200, body = ApiResp<Vec<OAuthClientDto>>)),
+    security(("bearer" = []))
+)]
+#[get("/admin/oauth/clients")]
+pub async fn list_clients(
+    permissions: Permissions,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<Vec<OAuthClientDto>>, AppError> {
+    permissions.require(perm::OAUTH_CLIENT)?;
+    let clients = oauth_clients::Entity::find().all(&app_data.db_pool).await?;
+    Ok(ApiResp::ok(
+        clients.into_iter().map(OAuthClientDto::from).collect(),
+    ))
+}
+
+/// 删除 OAuth2 客户端，同时删除用户对它的授权记录与它持有的会话，返回撤销的会话数量
+#[utoipa::path(
+    tag = "admin",
+    params(("client_id" = String, Path, description = "客户端ID")),
+    responses((status = 200, body = ApiResp<u64>)),
+    security(("bearer" = []))
+)]
+#[delete("/admin/oauth/clients/{client_id}")]
+pub async fn delete_client(
+    client_id: Result<web::Path<String>>,
+    permissions: Permissions,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<u64>, AppError> {
+    permissions.require(perm::OAUTH_CLIENT)?;
+    let client_id = extract_path_param(client_id, "客户端ID")?;
+    let txn = app_data.db_pool.begin().await?;
+    let delete_result = oauth_clients::Entity::delete_many()
+        .filter(oauth_clients::Column::ClientId.eq(&client_id))
+        .exec(&txn)
+        .await?;
+    if delete_result.rows_affected == 0 {
+        return Err(AppError::NotFound(format!("客户端 {client_id} 不存在")));
+    }
+    oauth_consents::Entity::delete_many()
+        .filter(oauth_consents::Column::ClientId.eq(&client_id))
+        .exec(&txn)
+        .await?;
+    let revoked = devices::Entity::delete_many()
+        .filter(devices::Column::ClientId.eq(&client_id))
+        .exec(&txn)
+        .await?;
+    txn.commit().await?;
+    warn!(
+        "管理员 {} 删除了 OAuth2 客户端 {client_id}，撤销会话 {} 个",
+        permissions.user.id, revoked.rows_affected
+    );
+    Ok(ApiResp::ok(revoked.rows_affected))
+}
@@ -0,0 +1,36 @@
+use crate::dto::{ApiResp, user::UserDto};
+use crate::errors::AppError;
+use crate::models::audit::{AuditEvent, Outcome, action};
+use crate::models::client_info::ClientInfo;
+use crate::models::rbac::{Permissions, perm};
+use crate::models::user_deletion;
+use crate::state::AppState;
+use crate::utils::extract_path_param;
+use actix_web::{Result, web};
+
+/// 恢复保留期内的已删除用户，与删除用户使用相同的 `user:delete` 权限
+#[utoipa::path(
+    tag = "admin",
+    params(("id" = i64, Path, description = "用户ID")),
+    responses((status = 200, body = ApiResp<UserDto>)),
+    security(("bearer" = []))
+)]
+#[post("/admin/users/{id}/restore")]
+pub async fn restore_user(
+    id: Result<web::Path<i64>>,
+    permissions: Permissions,
+    client: ClientInfo,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<UserDto>, AppError> {
+    permissions.require(perm::USER_DELETE)?;
+    let user_id = extract_path_param(id, "用户ID")?;
+    let user =
+        user_deletion::restore(&app_data.db_pool, user_id, &app_data.config.user_deletion).await?;
+    info!("管理员 {} 恢复了用户 {user_id}", permissions.user.id);
+    app_data.audit.record(
+        AuditEvent::new(action::USER_RESTORE, Outcome::Success, &client)
+            .actor(permissions.user.id, &permissions.user.email)
+            .target(user_id),
+    );
+    Ok(ApiResp::ok(UserDto::from(user)))
+}
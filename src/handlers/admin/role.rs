@@ -0,0 +1,181 @@
+use crate::dto::ApiResp;
+use crate::entity::{permissions, role_permissions, roles, user_roles, users};
+use crate::errors::{AppError, code};
+use crate::models::audit::{AuditEvent, Outcome, action};
+use crate::models::client_info::ClientInfo;
+use crate::models::rbac::{Permissions, assign_role, perm, role};
+use crate::state::AppState;
+use crate::utils::{extract_path_param, validate_params};
+use actix_web::{Result, web};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct RoleItem {
+    name: String,
+    description: String,
+    permissions: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Validate, ToSchema)]
+struct GrantRoleReq {
+    #[validate(length(min = 1, message = "角色名称不能为空"))]
+    role: String,
+}
+
+async fn ensure_user_exists(
+    user_id: i64,
+    db_pool: &sea_orm::DatabaseConnection,
+) -> Result<(), AppError> {
+    users::Entity::find_active_by_id(user_id)
+        .one(db_pool)
+        .await?
+        .map(|_| ())
+        .ok_or_else(|| {
+            AppError::NotFound(format!("用户ID {user_id} 不存在")).with_code(code::USER_NOT_FOUND)
+        })
+}
+
+/// 列出所有角色及其权限
+#[utoipa::path(
+    tag = "admin",
+    responses((status = 200, body = ApiResp<Vec<RoleItem>>)),
+    security(("bearer" = []))
+)]
+#[get("/admin/roles")]
+pub async fn list_roles(
+    permissions: Permissions,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<Vec<RoleItem>>, AppError> {
+    permissions.require(perm::ROLE_MANAGE)?;
+    let role_list = roles::Entity::find()
+        .find_with_related(role_permissions::Entity)
+        .all(&app_data.db_pool)
+        .await?;
+    let permission_list = permissions::Entity::find().all(&app_data.db_pool).await?;
+
+    let data = role_list
+        .into_iter()
+        .map(|(role, links)| RoleItem {
+            name: role.name,
+            description: role.description,
+            permissions: permission_list
+                .iter()
+                .filter(|p| links.iter().any(|link| link.permission_id == p.id))
+                .map(|p| p.code.clone())
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+    Ok(ApiResp::ok(data))
+}
+
+/// 查询用户拥有的角色
+#[utoipa::path(
+    tag = "admin",
+    params(("id" = i64, Path, description = "用户ID")),
+    responses((status = 200, body = ApiResp<Vec<String>>)),
+    security(("bearer" = []))
+)]
+#[get("/admin/users/{id}/roles")]
+pub async fn get_user_roles(
+    id: Result<web::Path<i64>>,
+    permissions: Permissions,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<Vec<String>>, AppError> {
+    permissions.require(perm::ROLE_MANAGE)?;
+    let user_id = extract_path_param(id, "用户ID")?;
+    ensure_user_exists(user_id, &app_data.db_pool).await?;
+    let role_names = roles::Entity::find()
+        .inner_join(user_roles::Entity)
+        .filter(user_roles::Column::UserId.eq(user_id))
+        .all(&app_data.db_pool)
+        .await?
+        .into_iter()
+        .map(|role| role.name)
+        .collect::<Vec<_>>();
+    Ok(ApiResp::ok(role_names))
+}
+
+/// 为用户授予角色
+#[utoipa::path(
+    tag = "admin",
+    params(("id" = i64, Path, description = "用户ID")),
+    request_body = GrantRoleReq,
+    responses((status = 200, body = ApiResp<bool>)),
+    security(("bearer" = []))
+)]
+#[post("/admin/users/{id}/roles")]
+pub async fn grant_role(
+    id: Result<web::Path<i64>>,
+    data: web::Json<GrantRoleReq>,
+    permissions: Permissions,
+    client: ClientInfo,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<bool>, AppError> {
+    permissions.require(perm::ROLE_MANAGE)?;
+    let user_id = extract_path_param(id, "用户ID")?;
+    validate_params(&*data)?;
+    ensure_user_exists(user_id, &app_data.db_pool).await?;
+    assign_role(&app_data.db_pool, user_id, &data.role).await?;
+    info!(
+        "管理员 {} 为用户 {} 授予角色 {}",
+        permissions.user.id, user_id, data.role
+    );
+    app_data.audit.record(
+        AuditEvent::new(action::ROLE_GRANT, Outcome::Success, &client)
+            .actor(permissions.user.id, &permissions.user.email)
+            .target(user_id)
+            .detail(&data.role),
+    );
+    Ok(ApiResp::ok(true))
+}
+
+/// 撤销用户的角色
+#[utoipa::path(
+    tag = "admin",
+    params(
+        ("id" = i64, Path, description = "用户ID"),
+        ("role" = String, Path, description = "角色名称"),
+    ),
+    responses((status = 200, body = ApiResp<bool>)),
+    security(("bearer" = []))
+)]
+#[delete("/admin/users/{id}/roles/{role}")]
+pub async fn revoke_role(
+    path: Result<web::Path<(i64, String)>>,
+    permissions: Permissions,
+    client: ClientInfo,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<bool>, AppError> {
+    permissions.require(perm::ROLE_MANAGE)?;
+    let (user_id, role_name) = extract_path_param(path, "路径参数")?;
+    if user_id == permissions.user.id && role_name == role::ADMIN {
+        return Err(AppError::BadRequest("不能撤销自己的管理员角色".to_string()));
+    }
+    let role_model = roles::Entity::find()
+        .filter(roles::Column::Name.eq(&role_name))
+        .one(&app_data.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("角色 {role_name} 不存在")))?;
+    let delete_result = user_roles::Entity::delete_many()
+        .filter(user_roles::Column::UserId.eq(user_id))
+        .filter(user_roles::Column::RoleId.eq(role_model.id))
+        .exec(&app_data.db_pool)
+        .await?;
+    info!(
+        "管理员 {} 撤销用户 {} 的角色 {}",
+        permissions.user.id, user_id, role_name
+    );
+    if delete_result.rows_affected > 0 {
+        app_data.audit.record(
+            AuditEvent::new(action::ROLE_REVOKE, Outcome::Success, &client)
+                .actor(permissions.user.id, &permissions.user.email)
+                .target(user_id)
+                .detail(role_name),
+        );
+    }
+    Ok(ApiResp::ok(delete_result.rows_affected > 0))
+}
@@ -0,0 +1,44 @@
+use crate::dto::ApiResp;
+use crate::entity::users;
+use crate::errors::{AppError, code};
+use crate::models::rbac::{Permissions, perm};
+use crate::models::two_factor;
+use crate::state::AppState;
+use crate::utils::extract_path_param;
+use actix_web::{Result, web};
+
+/// 管理员重置指定用户的两步验证，用于用户丢失身份验证器且没有恢复码的情况
+#[utoipa::path(
+    tag = "admin",
+    params(("id" = i64, Path, description = "用户ID")),
+    responses((status = 200, body = ApiResp<bool>)),
+    security(("bearer" = []))
+)]
+#[delete("/admin/users/{id}/2fa")]
+pub async fn reset_two_factor(
+    id: Result<web::Path<i64>>,
+    permissions: Permissions,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<bool>, AppError> {
+    permissions.require(perm::USER_RESET_2FA)?;
+    let user_id = extract_path_param(id, "用户ID")?;
+    if users::Entity::find_active_by_id(user_id)
+        .one(&app_data.db_pool)
+        .await?
+        .is_none()
+    {
+        return Err(
+            AppError::NotFound(format!("用户ID {user_id} 不存在")).with_code(code::USER_NOT_FOUND)
+        );
+    }
+    if !two_factor::disable(&app_data.db_pool, user_id).await? {
+        return Err(AppError::NotFound(format!(
+            "用户ID {user_id} 未启用两步验证"
+        )));
+    }
+    warn!(
+        "管理员 {} 重置了用户 {} 的两步验证",
+        permissions.user.id, user_id
+    );
+    Ok(ApiResp::ok(true))
+}
@@ -0,0 +1,48 @@
+use crate::dto::{ApiResp, api_key::CreatedKeyDto};
+use crate::errors::AppError;
+use crate::models::api_key;
+use crate::models::auth::AuthUser;
+use crate::state::AppState;
+use crate::utils::validate_params;
+use actix_web::{Result, web};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
+
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+struct CreateApiKeyReq {
+    #[validate(length(min = 1, max = 64, message = "名称长度必须在 1 到 64 之间"))]
+    name: String,
+    /// 授权范围 (权限编码)，不传时拥有用户的全部权限
+    scopes: Option<Vec<String>>,
+    /// 有效期 (天)，不传时永不过期
+    expire_days: Option<i64>,
+}
+
+/// 创建 API Key，明文只在本次响应中返回
+#[utoipa::path(
+    tag = "api_key",
+    request_body = CreateApiKeyReq,
+    responses((status = 200, body = ApiResp<CreatedKeyDto>)),
+    security(("bearer" = []))
+)]
+#[post("/api-keys")]
+pub async fn create_api_key(
+    params: web::Json<CreateApiKeyReq>,
+    auth_user: AuthUser,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<CreatedKeyDto>, AppError> {
+    validate_params(&*params)?;
+    let created = api_key::create(
+        auth_user.id,
+        &params.name,
+        params.scopes.as_deref(),
+        params.expire_days,
+        &app_data,
+    )
+    .await?;
+    Ok(ApiResp::with_message(
+        CreatedKeyDto::from(created),
+        "请妥善保存 API Key，之后无法再次查看",
+    ))
+}
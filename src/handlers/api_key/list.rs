@@ -0,0 +1,28 @@
+use crate::dto::{ApiResp, api_key::ApiKeyDto};
+use crate::entity::api_keys;
+use crate::errors::AppError;
+use crate::models::auth::AuthUser;
+use crate::state::AppState;
+use actix_web::{Result, web};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+
+/// 列出当前用户的 API Key，包括已过期的
+#[utoipa::path(
+    tag = "api_key",
+    responses((status = 200, body = ApiResp<Vec<ApiKeyDto>>)),
+    security(("bearer" = []))
+)]
+#[get("/api-keys")]
+pub async fn list_api_keys(
+    auth_user: AuthUser,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<Vec<ApiKeyDto>>, AppError> {
+    let key_list = api_keys::Entity::find()
+        .filter(api_keys::Column::UserId.eq(auth_user.id))
+        .order_by_desc(api_keys::Column::CreateTime)
+        .all(&app_data.db_pool)
+        .await?;
+    Ok(ApiResp::ok(
+        key_list.into_iter().map(ApiKeyDto::from).collect(),
+    ))
+}
@@ -0,0 +1,34 @@
+use crate::dto::ApiResp;
+use crate::entity::api_keys;
+use crate::errors::AppError;
+use crate::models::auth::AuthUser;
+use crate::state::AppState;
+use crate::utils::extract_path_param;
+use actix_web::{Result, web};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+/// 撤销当前用户的某个 API Key，撤销后立即失效
+#[utoipa::path(
+    tag = "api_key",
+    params(("id" = i64, Path, description = "API Key ID")),
+    responses((status = 200, body = ApiResp<bool>)),
+    security(("bearer" = []))
+)]
+#[delete("/api-keys/{id}")]
+pub async fn revoke_api_key(
+    id: Result<web::Path<i64>>,
+    auth_user: AuthUser,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<bool>, AppError> {
+    let key_id = extract_path_param(id, "API Key ID")?;
+    let delete_result = api_keys::Entity::delete_many()
+        .filter(api_keys::Column::Id.eq(key_id))
+        .filter(api_keys::Column::UserId.eq(auth_user.id))
+        .exec(&app_data.db_pool)
+        .await?;
+    if delete_result.rows_affected == 0 {
+        return Err(AppError::NotFound(format!("API Key {key_id} 不存在")));
+    }
+    info!("用户 {} 撤销了 API Key {key_id}", auth_user.id);
+    Ok(ApiResp::ok(true))
+}
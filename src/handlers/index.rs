@@ -1,7 +1,49 @@
+use crate::dto::ApiResp;
 use crate::errors::AppError;
-use actix_web::{Result, get};
+use crate::models::document_store::StoreStatus;
+use crate::state::AppState;
+use actix_web::{HttpResponse, Result, get, web};
+use serde::Serialize;
 
 #[get("/")]
 pub async fn index() -> Result<String, AppError> {
     Ok("Hello, world!".into())
 }
+
+#[get("/health")]
+pub async fn health() -> Result<String, AppError> {
+    Ok("ok".into())
+}
+
+/// 各依赖组件的状态
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Components {
+    database: &'static str,
+    document_store: StoreStatus,
+}
+
+/// 就绪检查，数据库不可用时返回 503；文档存储是可选组件，不可用时仍返回 200，message 为 `degraded`
+#[get("/ready")]
+pub async fn ready(app_data: web::Data<AppState>) -> HttpResponse {
+    let database_up = app_data.db_pool.ping().await.is_ok();
+    let document_store = app_data
+        .document_store
+        .as_ref()
+        .map_or(StoreStatus::Disabled, |store| store.status());
+    let data = Components {
+        database: if database_up { "up" } else { "down" },
+        document_store,
+    };
+    if !database_up {
+        return HttpResponse::ServiceUnavailable().json(ApiResp {
+            code: 503,
+            ..ApiResp::with_message(data, "unavailable")
+        });
+    }
+    let message = match document_store {
+        StoreStatus::Disabled | StoreStatus::Up => "ok",
+        StoreStatus::Connecting | StoreStatus::Down => "degraded",
+    };
+    ApiResp::with_message(data, message).into()
+}
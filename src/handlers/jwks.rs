@@ -0,0 +1,9 @@
+use crate::errors::AppError;
+use crate::state::AppState;
+use actix_web::{HttpResponse, Result, web};
+
+/// 公开 JWT 校验公钥 (JWKS)，供其他服务离线校验 token
+#[get("/.well-known/jwks.json")]
+pub async fn jwks(app_data: web::Data<AppState>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(app_data.jwt_keys.jwks()))
+}
@@ -1,14 +1,84 @@
+mod admin;
+mod api_key;
 mod index;
-use actix_web::web::{ServiceConfig, scope};
+mod jwks;
+mod oauth;
+mod oidc;
+pub mod openapi;
+use crate::errors::{AppError, code};
+use actix_web::web::{JsonConfig, QueryConfig, ServiceConfig, scope};
+mod session;
+mod token;
 mod user;
 
 pub fn config(cfg: &mut ServiceConfig) {
-    cfg.service(index::index).service(
-        scope("/api")
-            .service(user::get::get_query_users)
-            .service(user::delete::delete_user)
-            .service(user::login::login)
-            .service(user::logout::logout)
-            .service(user::create::create_user),
-    );
+    // 请求体与查询参数解析失败时同样返回统一的错误格式
+    cfg.app_data(JsonConfig::default().error_handler(|err, _| {
+        AppError::BadRequest(format!("无效的请求体: {err}"))
+            .with_code(code::INVALID_BODY)
+            .into()
+    }))
+    .app_data(QueryConfig::default().error_handler(|err, _| {
+        AppError::BadRequest(format!("无效的查询参数: {err}"))
+            .with_code(code::INVALID_QUERY)
+            .into()
+    }));
+    cfg.service(index::index)
+        .service(index::health)
+        .service(index::ready)
+        .service(jwks::jwks)
+        .service(openapi::api_docs)
+        .service(
+            scope("/api")
+                .service(user::get::get_query_users)
+                .service(user::get::list_users)
+                .service(user::delete::delete_user)
+                .service(user::login::login)
+                .service(user::login::login_two_factor)
+                .service(user::logout::logout)
+                .service(user::create::create_user)
+                .service(user::verify::verify_email)
+                .service(user::verify::resend_verification)
+                .service(user::password::change_password)
+                .service(user::password::forgot_password)
+                .service(user::password::reset_password)
+                .service(user::two_factor::setup_two_factor)
+                .service(user::two_factor::confirm_two_factor)
+                .service(user::two_factor::disable_two_factor)
+                // 固定路径需要先于 `/users/{id}` 注册
+                .service(user::get::get_user)
+                .service(user::update::update_user)
+                .service(user::update::patch_user)
+                .service(token::refresh::refresh_token)
+                .service(oidc::login::oidc_login)
+                .service(oidc::login::oidc_callback)
+                .service(oauth::authorize::authorize)
+                .service(oauth::authorize::confirm_authorize)
+                .service(oauth::token::issue_token)
+                .service(oauth::token::introspect)
+                .service(oauth::token::revoke)
+                .service(oauth::consent::list_consents)
+                .service(oauth::consent::revoke_consent)
+                .service(session::list::list_sessions)
+                .service(session::rename::rename_session)
+                // 固定路径需要先于 `/sessions/{id}` 注册
+                .service(session::revoke::revoke_other_sessions)
+                .service(session::revoke::revoke_session)
+                .service(api_key::create::create_api_key)
+                .service(api_key::list::list_api_keys)
+                .service(api_key::revoke::revoke_api_key)
+                .service(admin::logout::force_logout)
+                .service(admin::restore::restore_user)
+                .service(admin::role::list_roles)
+                .service(admin::role::get_user_roles)
+                .service(admin::role::grant_role)
+                .service(admin::role::revoke_role)
+                .service(admin::lockout::list_lockouts)
+                .service(admin::lockout::clear_lockout)
+                .service(admin::two_factor::reset_two_factor)
+                .service(admin::oauth_client::create_client)
+                .service(admin::oauth_client::list_clients)
+                .service(admin::oauth_client::delete_client)
+                .service(admin::audit::list_audit_logs),
+        );
 }
@@ -0,0 +1,224 @@
+use crate::dto::ApiResp;
+use crate::entity::oauth_clients;
+use crate::errors::AppError;
+use crate::models::auth::AuthUser;
+use crate::models::oauth::{
+    AuthorizationCode, grant_type, has_consent, record_consent, resolve_scope,
+};
+use crate::state::AppState;
+use actix_web::{Result, web};
+use chrono::Utc;
+use reqwest::Url;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Deserialize, Debug, IntoParams, ToSchema)]
+#[into_params(parameter_in = Query)]
+struct AuthorizeReq {
+    /// 固定为 `code`
+    response_type: Option<String>,
+    client_id: String,
+    redirect_uri: String,
+    scope: Option<String>,
+    state: Option<String>,
+    code_challenge: Option<String>,
+    /// 固定为 `S256`
+    code_challenge_method: Option<String>,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+struct ConsentReq {
+    #[serde(flatten)]
+    request: AuthorizeReq,
+    /// 用户是否同意授权
+    approve: bool,
+}
+
+/// 授权接口的响应
+///
+/// 需要用户确认时返回客户端信息，前端据此展示授权确认页；否则返回跳转回客户端的回调地址
+#[derive(Serialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct AuthorizeResp {
+    consent_required: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scope: Option<String>,
+    /// 携带授权码或错误参数的回调地址
+    #[serde(skip_serializing_if = "Option::is_none")]
+    redirect_uri: Option<String>,
+}
+
+/// 校验通过的授权请求
+struct ValidRequest {
+    client: oauth_clients::Model,
+    scope: String,
+}
+
+/// 在回调地址上追加参数
+fn redirect_with(redirect_uri: &str, pairs: &[(&str, &str)], state: Option<&str>) -> String {
+    let mut url = Url::parse(redirect_uri).expect("redirect_uri validated on registration");
+    {
+        let mut query = url.query_pairs_mut();
+        query.extend_pairs(pairs);
+        if let Some(state) = state {
+            query.append_pair("state", state);
+        }
+    }
+    url.into()
+}
+
+/// 校验授权请求
+///
+/// 客户端或回调地址无效时不能跳转，直接返回错误；其余错误按 RFC 6749 跳转回客户端，
+/// 外层 `Err` 为前者，内层 `Err` 为携带错误参数的回调地址
+async fn validate(
+    req: &AuthorizeReq,
+    app_data: &AppState,
+) -> Result<Result<ValidRequest, String>, AppError> {
+    let client = oauth_clients::Entity::find()
+        .filter(oauth_clients::Column::ClientId.eq(&req.client_id))
+        .one(&app_data.db_pool)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("未知的客户端".to_string()))?;
+    if !client.allows_redirect_uri(&req.redirect_uri) {
+        return Err(AppError::BadRequest("回调地址未登记".to_string()));
+    }
+    let fail = |error: &str, description: &str| {
+        Ok(Err(redirect_with(
+            &req.redirect_uri,
+            &[("error", error), ("error_description", description)],
+            req.state.as_deref(),
+        )))
+    };
+
+    if req.response_type.as_deref() != Some("code") {
+        return fail("unsupported_response_type", "只支持授权码模式");
+    }
+    if !client.allows_grant_type(grant_type::AUTHORIZATION_CODE) {
+        return fail("unauthorized_client", "客户端未开通授权码模式");
+    }
+    match (&req.code_challenge, req.code_challenge_method.as_deref()) {
+        (Some(_), Some("S256")) => {}
+        (Some(_), _) => return fail("invalid_request", "code_challenge_method 只支持 S256"),
+        (None, _) if !client.is_confidential() => {
+            return fail("invalid_request", "公开客户端必须使用 PKCE");
+        }
+        (None, _) => {}
+    }
+    let scope = match resolve_scope(req.scope.as_deref(), &client) {
+        Ok(scope) => scope,
+        Err(e) => return fail(e.error, &e.description),
+    };
+    Ok(Ok(ValidRequest { client, scope }))
+}
+
+/// 签发授权码，返回跳转地址
+fn approve(req: &AuthorizeReq, valid: ValidRequest, user_id: i64, app_data: &AppState) -> String {
+    let code = app_data.oauth.issue_code(AuthorizationCode {
+        client_id: valid.client.client_id,
+        user_id,
+        redirect_uri: req.redirect_uri.clone(),
+        scope: valid.scope,
+        code_challenge: req.code_challenge.clone(),
+        create_time: Utc::now(),
+    });
+    redirect_with(&req.redirect_uri, &[("code", &code)], req.state.as_deref())
+}
+
+/// 发起授权，用户已同意过相同范围时直接签发授权码，否则需要前端展示确认页后调用 POST 接口
+#[utoipa::path(
+    tag = "oauth",
+    params(AuthorizeReq),
+    responses((status = 200, body = ApiResp<AuthorizeResp>)),
+    security(("bearer" = []))
+)]
+#[get("/oauth/authorize")]
+pub async fn authorize(
+    query: web::Query<AuthorizeReq>,
+    auth_user: AuthUser,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<AuthorizeResp>, AppError> {
+    let valid = match validate(&query, &app_data).await? {
+        Ok(valid) => valid,
+        Err(redirect_uri) => return Ok(redirect_response(redirect_uri)),
+    };
+    let consented = has_consent(
+        &app_data.db_pool,
+        auth_user.id,
+        &valid.client.client_id,
+        &valid.scope,
+    )
+    .await?;
+    if consented {
+        let redirect_uri = approve(&query, valid, auth_user.id, &app_data);
+        return Ok(redirect_response(redirect_uri));
+    }
+    // 前端据此展示授权确认页
+    Ok(ApiResp::ok(AuthorizeResp {
+        consent_required: true,
+        client_id: Some(valid.client.client_id),
+        client_name: Some(valid.client.name),
+        scope: Some(valid.scope),
+        redirect_uri: None,
+    }))
+}
+
+/// 用户在授权确认页做出选择，同意时记录授权并签发授权码
+#[utoipa::path(
+    tag = "oauth",
+    request_body = ConsentReq,
+    responses((status = 200, body = ApiResp<AuthorizeResp>)),
+    security(("bearer" = []))
+)]
+#[post("/oauth/authorize")]
+pub async fn confirm_authorize(
+    params: web::Json<ConsentReq>,
+    auth_user: AuthUser,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<AuthorizeResp>, AppError> {
+    let req = &params.request;
+    let valid = match validate(req, &app_data).await? {
+        Ok(valid) => valid,
+        Err(redirect_uri) => return Ok(redirect_response(redirect_uri)),
+    };
+    if !params.approve {
+        let redirect_uri = redirect_with(
+            &req.redirect_uri,
+            &[
+                ("error", "access_denied"),
+                ("error_description", "用户拒绝授权"),
+            ],
+            req.state.as_deref(),
+        );
+        return Ok(redirect_response(redirect_uri));
+    }
+    record_consent(
+        &app_data.db_pool,
+        auth_user.id,
+        &valid.client.client_id,
+        &valid.scope,
+    )
+    .await?;
+    info!(
+        "用户 {} 授权客户端 {} 访问 {}",
+        auth_user.id, valid.client.client_id, valid.scope
+    );
+    let redirect_uri = approve(req, valid, auth_user.id, &app_data);
+    Ok(redirect_response(redirect_uri))
+}
+
+/// 授权流程结束，前端跳转回客户端的回调地址
+fn redirect_response(redirect_uri: String) -> ApiResp<AuthorizeResp> {
+    ApiResp::ok(AuthorizeResp {
+        consent_required: false,
+        client_id: None,
+        client_name: None,
+        scope: None,
+        redirect_uri: Some(redirect_uri),
+    })
+}
@@ -0,0 +1,85 @@
+use crate::dto::ApiResp;
+use crate::entity::{devices, oauth_clients, oauth_consents};
+use crate::errors::AppError;
+use crate::models::auth::AuthUser;
+use crate::state::AppState;
+use crate::utils::{extract_path_param, serde_timestamp};
+use actix_web::{Result, web};
+use chrono::{DateTime, Utc};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder, TransactionTrait};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// 当前用户授权过的第三方应用
+#[derive(Serialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ConsentInfo {
+    client_id: String,
+    client_name: Option<String>,
+    scope: String,
+    #[serde(with = "serde_timestamp")]
+    #[schema(value_type = String, example = "2025-01-01 00:00:00")]
+    create_time: DateTime<Utc>,
+    #[serde(with = "serde_timestamp")]
+    #[schema(value_type = String, example = "2025-01-01 00:00:00")]
+    update_time: DateTime<Utc>,
+}
+
+/// 列出当前用户授权过的第三方应用
+#[utoipa::path(
+    tag = "oauth",
+    responses((status = 200, body = ApiResp<Vec<ConsentInfo>>)),
+    security(("bearer" = []))
+)]
+#[get("/oauth/consents")]
+pub async fn list_consents(
+    auth_user: AuthUser,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<Vec<ConsentInfo>>, AppError> {
+    let consents = oauth_consents::Entity::find()
+        .find_also_related(oauth_clients::Entity)
+        .filter(oauth_consents::Column::UserId.eq(auth_user.id))
+        .order_by_desc(oauth_consents::Column::UpdateTime)
+        .all(&app_data.db_pool)
+        .await?
+        .into_iter()
+        .map(|(consent, client)| ConsentInfo {
+            client_id: consent.client_id,
+            client_name: client.map(|client| client.name),
+            scope: consent.scope,
+            create_time: consent.create_time,
+            update_time: consent.update_time,
+        })
+        .collect::<Vec<_>>();
+    Ok(ApiResp::ok(consents))
+}
+
+/// 取消对某个第三方应用的授权，同时撤销该应用持有的会话，返回撤销的会话数量
+#[utoipa::path(
+    tag = "oauth",
+    params(("client_id" = String, Path, description = "客户端ID")),
+    responses((status = 200, body = ApiResp<u64>)),
+    security(("bearer" = []))
+)]
+#[delete("/oauth/consents/{client_id}")]
+pub async fn revoke_consent(
+    client_id: Result<web::Path<String>>,
+    auth_user: AuthUser,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<u64>, AppError> {
+    let client_id = extract_path_param(client_id, "客户端ID")?;
+    let txn = app_data.db_pool.begin().await?;
+    let delete_result = oauth_consents::Entity::delete_by_id((auth_user.id, client_id.clone()))
+        .exec(&txn)
+        .await?;
+    if delete_result.rows_affected == 0 {
+        return Err(AppError::NotFound(format!("未授权过客户端 {client_id}")));
+    }
+    let revoked = devices::Entity::delete_many()
+        .filter(devices::Column::UserId.eq(auth_user.id))
+        .filter(devices::Column::ClientId.eq(&client_id))
+        .exec(&txn)
+        .await?;
+    txn.commit().await?;
+    Ok(ApiResp::ok(revoked.rows_affected))
+}
@@ -0,0 +1,3 @@
+pub mod authorize;
+pub mod consent;
+pub mod token;
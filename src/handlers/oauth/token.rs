@@ -0,0 +1,386 @@
+use crate::entity::{devices, oauth_clients, users};
+use crate::errors::AppError;
+use crate::models::client_info::ClientInfo;
+use crate::models::oauth::{
+    OAuthError, authenticate_client, grant_type, resolve_scope, verify_pkce,
+};
+use crate::models::refresh_token::RefreshToken;
+use crate::models::session::{TokenPair, create_grant, refresh_session};
+use crate::models::token::{generate_scoped_token, verify_token};
+use crate::state::AppState;
+use actix_web::{HttpRequest, HttpResponse, http::header::CACHE_CONTROL, web};
+use chrono::Utc;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Deserialize, Debug, ToSchema)]
+struct TokenReq {
+    grant_type: Option<String>,
+    code: Option<String>,
+    redirect_uri: Option<String>,
+    code_verifier: Option<String>,
+    refresh_token: Option<String>,
+    scope: Option<String>,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+}
+
+/// RFC 6749 第 5.1 节格式的令牌响应
+#[derive(Serialize, Debug, ToSchema)]
+struct TokenResp {
+    access_token: String,
+    #[schema(value_type = String, example = "Bearer")]
+    token_type: &'static str,
+    expires_in: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    refresh_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scope: Option<String>,
+}
+
+impl TokenResp {
+    fn from_pair(tokens: TokenPair, scope: Option<String>) -> Self {
+        Self {
+            access_token: tokens.token,
+            token_type: "Bearer",
+            expires_in: tokens.expires_in,
+            refresh_token: Some(tokens.refresh_token),
+            scope,
+        }
+    }
+}
+
+/// 令牌接口，支持授权码、刷新令牌与客户端凭据三种方式
+///
+/// 请求与响应遵循 RFC 6749，不使用统一的响应格式，客户端可以通过 HTTP Basic 认证传递凭据
+#[utoipa::path(
+    tag = "oauth",
+    request_body(content = TokenReq, content_type = "application/x-www-form-urlencoded"),
+    responses((status = 200, body = TokenResp))
+)]
+#[post("/oauth/token")]
+pub async fn issue_token(
+    req: HttpRequest,
+    params: web::Form<TokenReq>,
+    client: ClientInfo,
+    app_data: web::Data<AppState>,
+) -> Result<HttpResponse, OAuthError> {
+    let oauth_client = authenticate_client(
+        &app_data.db_pool,
+        req.headers(),
+        params.client_id.as_deref(),
+        params.client_secret.as_deref(),
+    )
+    .await?;
+    let grant = params
+        .grant_type
+        .as_deref()
+        .ok_or_else(|| OAuthError::invalid_request("缺少 grant_type"))?;
+    if !grant_type::ALL.contains(&grant) {
+        return Err(OAuthError::new(
+            "unsupported_grant_type",
+            format!("不支持的授权方式 {grant}"),
+        ));
+    }
+    if !oauth_client.allows_grant_type(grant) {
+        return Err(OAuthError::new(
+            "unauthorized_client",
+            format!("客户端未开通 {grant}"),
+        ));
+    }
+    let resp = match grant {
+        grant_type::AUTHORIZATION_CODE => {
+            exchange_code(&params, &oauth_client, &client, &app_data).await?
+        }
+        grant_type::REFRESH_TOKEN => {
+            let refresh_token = params
+                .refresh_token
+                .as_deref()
+                .ok_or_else(|| OAuthError::invalid_request("缺少 refresh_token"))?;
+            let tokens = refresh_session(
+                refresh_token,
+                Some(&oauth_client.client_id),
+                &client,
+                &app_data,
+            )
+            .await
+            .map_err(|e| match e {
+                AppError::Unauthorized(message) => OAuthError::invalid_grant(message),
+                e => e.into(),
+            })?;
+            TokenResp::from_pair(tokens, None)
+        }
+        _ => client_credentials(&params, &oauth_client, &app_data)?,
+    };
+    Ok(HttpResponse::Ok()
+        .insert_header((CACHE_CONTROL, "no-store"))
+        .json(resp))
+}
+
+/// 授权码换取令牌，为用户创建一条属于该客户端的设备会话
+async fn exchange_code(
+    params: &TokenReq,
+    oauth_client: &oauth_clients::Model,
+    client: &ClientInfo,
+    app_data: &AppState,
+) -> Result<TokenResp, OAuthError> {
+    let code = params
+        .code
+        .as_deref()
+        .ok_or_else(|| OAuthError::invalid_request("缺少 code"))?;
+    let invalid = || OAuthError::invalid_grant("授权码无效或已过期");
+    let code = app_data
+        .oauth
+        .take_code(code)
+        .filter(|code| code.client_id == oauth_client.client_id)
+        .ok_or_else(invalid)?;
+    if params.redirect_uri.as_deref() != Some(code.redirect_uri.as_str()) {
+        return Err(OAuthError::invalid_grant("redirect_uri 与授权请求不一致"));
+    }
+    if let Some(challenge) = &code.code_challenge {
+        let verified = params
+            .code_verifier
+            .as_deref()
+            .is_some_and(|verifier| verify_pkce(verifier, challenge));
+        if !verified {
+            return Err(OAuthError::invalid_grant("code_verifier 校验失败"));
+        }
+    }
+    let user = users::Entity::find_active_by_id(code.user_id)
+        .one(&app_data.db_pool)
+        .await?
+        .ok_or_else(invalid)?;
+    let tokens = create_grant(&user, oauth_client, &code.scope, client, app_data).await?;
+    info!(
+        "客户端 {} 获得用户 {} 的授权令牌",
+        oauth_client.client_id, user.id
+    );
+    Ok(TokenResp::from_pair(tokens, Some(code.scope)))
+}
+
+/// 客户端以自身身份获取访问令牌，令牌不关联用户与设备，不能访问本站接口，只用于内省
+fn client_credentials(
+    params: &TokenReq,
+    oauth_client: &oauth_clients::Model,
+    app_data: &AppState,
+) -> Result<TokenResp, OAuthError> {
+    if !oauth_client.is_confidential() {
+        return Err(OAuthError::new(
+            "unauthorized_client",
+            "公开客户端不能使用客户端凭据模式",
+        ));
+    }
+    let scope = resolve_scope(params.scope.as_deref(), oauth_client)?;
+    let expires_in = app_data.config.oauth_server.client_token_expire;
+    let access_token = generate_scoped_token(
+        &app_data.jwt_keys,
+        &oauth_client.client_id,
+        expires_in,
+        Some(&oauth_client.client_id),
+        Some(&scope),
+    )
+    .map_err(AppError::from)?;
+    Ok(TokenResp {
+        access_token,
+        token_type: "Bearer",
+        expires_in,
+        refresh_token: None,
+        scope: Some(scope),
+    })
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+struct TokenParamReq {
+    token: Option<String>,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+}
+
+/// RFC 7662 格式的内省响应，令牌无效时只返回 `active: false`
+#[derive(Serialize, Debug, Default, ToSchema)]
+struct IntrospectResp {
+    active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sub: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exp: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>, example = "access_token")]
+    token_type: Option<&'static str>,
+}
+
+/// 令牌内省，供资源服务器查询令牌是否有效及其授权范围，只允许机密客户端调用
+#[utoipa::path(
+    tag = "oauth",
+    request_body(content = TokenParamReq, content_type = "application/x-www-form-urlencoded"),
+    responses((status = 200, body = IntrospectResp))
+)]
+#[post("/oauth/introspect")]
+pub async fn introspect(
+    req: HttpRequest,
+    params: web::Form<TokenParamReq>,
+    app_data: web::Data<AppState>,
+) -> Result<HttpResponse, OAuthError> {
+    let oauth_client = authenticate_client(
+        &app_data.db_pool,
+        req.headers(),
+        params.client_id.as_deref(),
+        params.client_secret.as_deref(),
+    )
+    .await?;
+    if !oauth_client.is_confidential() {
+        return Err(OAuthError::new(
+            "unauthorized_client",
+            "公开客户端不能调用内省接口",
+        ));
+    }
+    let token = params
+        .token
+        .as_deref()
+        .ok_or_else(|| OAuthError::invalid_request("缺少 token"))?;
+    let resp = introspect_token(token, &app_data)
+        .await?
+        .unwrap_or_default();
+    Ok(HttpResponse::Ok()
+        .insert_header((CACHE_CONTROL, "no-store"))
+        .json(resp))
+}
+
+async fn introspect_token(
+    token: &str,
+    app_data: &AppState,
+) -> Result<Option<IntrospectResp>, OAuthError> {
+    if let Ok(data) = verify_token(&app_data.jwt_keys, token) {
+        let claims = data.claims;
+        // 客户端凭据令牌以客户端 ID 作为 sub，客户端仍然存在即有效
+        if claims.client_id.as_deref() == Some(claims.sub.as_str()) {
+            let exists = oauth_clients::Entity::find()
+                .filter(oauth_clients::Column::ClientId.eq(&claims.sub))
+                .one(&app_data.db_pool)
+                .await?
+                .is_some();
+            return Ok(exists.then(|| IntrospectResp {
+                active: true,
+                scope: claims.scope,
+                client_id: claims.client_id,
+                sub: Some(claims.sub),
+                exp: Some(claims.exp as i64),
+                token_type: Some("access_token"),
+                ..Default::default()
+            }));
+        }
+        let Some(device) = devices::Entity::find()
+            .filter(devices::Column::Token.eq(token))
+            .one(&app_data.db_pool)
+            .await?
+        else {
+            return Ok(None);
+        };
+        return Ok(Some(IntrospectResp {
+            active: true,
+            scope: device.scope,
+            client_id: device.client_id,
+            username: Some(claims.sub),
+            sub: Some(device.user_id.to_string()),
+            exp: Some(claims.exp as i64),
+            token_type: Some("access_token"),
+        }));
+    }
+
+    let Some(device) = find_refresh_device(token, app_data).await? else {
+        return Ok(None);
+    };
+    let Some(user) = users::Entity::find_active_by_id(device.user_id)
+        .one(&app_data.db_pool)
+        .await?
+    else {
+        return Ok(None);
+    };
+    Ok(Some(IntrospectResp {
+        active: true,
+        scope: device.scope,
+        client_id: device.client_id,
+        username: Some(user.email),
+        sub: Some(user.id.to_string()),
+        exp: device
+            .refresh_expire_time
+            .map(|expire_time| expire_time.timestamp()),
+        token_type: Some("refresh_token"),
+    }))
+}
+
+/// 按刷新令牌查找仍然有效的设备会话
+async fn find_refresh_device(
+    token: &str,
+    app_data: &AppState,
+) -> Result<Option<devices::Model>, OAuthError> {
+    let Some(presented) = RefreshToken::parse(token) else {
+        return Ok(None);
+    };
+    let presented_hash = presented.hash();
+    Ok(devices::Entity::find()
+        .filter(devices::Column::RefreshFamily.eq(&presented.family))
+        .one(&app_data.db_pool)
+        .await?
+        .filter(|device| device.refresh_token_hash.as_deref() == Some(presented_hash.as_str()))
+        .filter(|device| {
+            device
+                .refresh_expire_time
+                .is_some_and(|expire_time| expire_time > Utc::now())
+        }))
+}
+
+/// 撤销令牌 (RFC 7009)，访问令牌与刷新令牌都会删除对应的设备会话
+///
+/// 只能撤销签发给调用方客户端的令牌，令牌无效或不属于该客户端时同样返回成功。
+/// 客户端凭据令牌是无状态的，只能等待过期
+#[utoipa::path(
+    tag = "oauth",
+    request_body(content = TokenParamReq, content_type = "application/x-www-form-urlencoded"),
+    responses((status = 200, description = "撤销成功，响应体为空"))
+)]
+#[post("/oauth/revoke")]
+pub async fn revoke(
+    req: HttpRequest,
+    params: web::Form<TokenParamReq>,
+    app_data: web::Data<AppState>,
+) -> Result<HttpResponse, OAuthError> {
+    let oauth_client = authenticate_client(
+        &app_data.db_pool,
+        req.headers(),
+        params.client_id.as_deref(),
+        params.client_secret.as_deref(),
+    )
+    .await?;
+    let token = params
+        .token
+        .as_deref()
+        .ok_or_else(|| OAuthError::invalid_request("缺少 token"))?;
+    let device = match devices::Entity::find()
+        .filter(devices::Column::Token.eq(token))
+        .one(&app_data.db_pool)
+        .await?
+    {
+        Some(device) => Some(device),
+        None => find_refresh_device(token, &app_data).await?,
+    };
+    if let Some(device) =
+        device.filter(|device| device.client_id.as_deref() == Some(&oauth_client.client_id))
+    {
+        devices::Entity::delete_by_id(device.id)
+            .exec(&app_data.db_pool)
+            .await?;
+        info!(
+            "客户端 {} 撤销了用户 {} 的授权会话 {}",
+            oauth_client.client_id, device.user_id, device.id
+        );
+    }
+    Ok(HttpResponse::Ok().finish())
+}
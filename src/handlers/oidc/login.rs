@@ -0,0 +1,91 @@
+use crate::errors::AppError;
+use crate::handlers::user::login::{login_success, two_factor_challenge};
+use crate::models::client_info::ClientInfo;
+use crate::models::oidc::{OidcClient, STATE_COOKIE, sign_in};
+use crate::state::AppState;
+use actix_web::cookie::{Cookie, SameSite, time::Duration};
+use actix_web::{HttpRequest, HttpResponse, Result, http::header::LOCATION, web};
+use serde::Deserialize;
+use std::sync::Arc;
+
+fn oidc_client(app_data: &AppState) -> Result<&Arc<OidcClient>, AppError> {
+    app_data
+        .oidc
+        .as_ref()
+        .ok_or_else(|| AppError::NotFound("未启用 OIDC 登录".to_string()))
+}
+
+/// 跳转到身份提供方的登录页面
+///
+/// `state` 同时写入 Cookie，回调时校验，确保回调与发起登录的是同一个浏览器
+#[get("/oidc/login")]
+pub async fn oidc_login(app_data: web::Data<AppState>) -> Result<HttpResponse, AppError> {
+    let oidc = oidc_client(&app_data)?;
+    let (url, state) = oidc.authorization_url().await?;
+    Ok(HttpResponse::Found()
+        .insert_header((LOCATION, url))
+        .cookie(state_cookie(state, oidc))
+        .finish())
+}
+
+/// 保存 `state` 的 Cookie，身份提供方跳转回来属于顶层导航，`SameSite=Lax` 时仍会携带
+fn state_cookie(value: String, oidc: &OidcClient) -> Cookie<'static> {
+    Cookie::build(STATE_COOKIE, value)
+        .path("/api/oidc")
+        .http_only(true)
+        .secure(oidc.secure_cookie())
+        .same_site(SameSite::Lax)
+        .finish()
+}
+
+#[derive(Deserialize, Debug)]
+struct CallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+/// 身份提供方登录完成后的回调，校验通过后按普通登录创建设备会话，启用了两步验证时返回挑战令牌
+#[get("/oidc/callback")]
+pub async fn oidc_callback(
+    req: HttpRequest,
+    query: web::Query<CallbackQuery>,
+    client: ClientInfo,
+    app_data: web::Data<AppState>,
+) -> Result<HttpResponse, AppError> {
+    let oidc = oidc_client(&app_data)?;
+    if let Some(error) = &query.error {
+        return Err(AppError::Unauthorized(format!(
+            "身份提供方拒绝了登录: {}",
+            query.error_description.as_deref().unwrap_or(error)
+        )));
+    }
+    let (Some(code), Some(state)) = (&query.code, &query.state) else {
+        return Err(AppError::BadRequest("缺少 code 或 state 参数".to_string()));
+    };
+    let cookie = req.cookie(STATE_COOKIE);
+    if cookie.as_ref().map(Cookie::value) != Some(state.as_str()) {
+        return Err(AppError::BadRequest(
+            "登录状态与当前浏览器不匹配，请重新登录".to_string(),
+        ));
+    }
+    let claims = oidc.exchange(code, state).await?;
+    let user = sign_in(oidc.issuer(), &claims, &app_data).await?;
+    let mut response = match two_factor_challenge(user.id, &app_data).await? {
+        Some(challenge) => challenge,
+        None => {
+            let method = format!("oidc {}", oidc.issuer());
+            let response = login_success(&user, &client, &method, &app_data).await?;
+            info!("用户 {} 通过 {} 登录", user.id, oidc.issuer());
+            response.into()
+        }
+    };
+    // `state` 只能使用一次，删除 Cookie
+    let mut expired = state_cookie(String::new(), oidc);
+    expired.set_max_age(Duration::ZERO);
+    response
+        .add_cookie(&expired)
+        .map_err(|e| AppError::InternalError(e.to_string()))?;
+    Ok(response)
+}
@@ -0,0 +1,92 @@
+use super::{admin, api_key, oauth, session, token, user};
+use actix_web::HttpResponse;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+/// OpenAPI 接口文档，接口路径相对于 `/api`
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "rust-class-web"),
+    servers((url = "/api")),
+    paths(
+        user::create::create_user,
+        user::login::login,
+        user::login::login_two_factor,
+        user::logout::logout,
+        user::get::get_query_users,
+        user::get::list_users,
+        user::get::get_user,
+        user::update::update_user,
+        user::update::patch_user,
+        user::delete::delete_user,
+        user::verify::verify_email,
+        user::verify::resend_verification,
+        user::password::change_password,
+        user::password::forgot_password,
+        user::password::reset_password,
+        user::two_factor::setup_two_factor,
+        user::two_factor::confirm_two_factor,
+        user::two_factor::disable_two_factor,
+        token::refresh::refresh_token,
+        session::list::list_sessions,
+        session::rename::rename_session,
+        session::revoke::revoke_other_sessions,
+        session::revoke::revoke_session,
+        api_key::create::create_api_key,
+        api_key::list::list_api_keys,
+        api_key::revoke::revoke_api_key,
+        oauth::authorize::authorize,
+        oauth::authorize::confirm_authorize,
+        oauth::token::issue_token,
+        oauth::token::introspect,
+        oauth::token::revoke,
+        oauth::consent::list_consents,
+        oauth::consent::revoke_consent,
+        admin::logout::force_logout,
+        admin::restore::restore_user,
+        admin::role::list_roles,
+        admin::role::get_user_roles,
+        admin::role::grant_role,
+        admin::role::revoke_role,
+        admin::lockout::list_lockouts,
+        admin::lockout::clear_lockout,
+        admin::two_factor::reset_two_factor,
+        admin::oauth_client::create_client,
+        admin::oauth_client::list_clients,
+        admin::oauth_client::delete_client,
+        admin::audit::list_audit_logs,
+    ),
+    modifiers(&BearerAuth),
+    tags(
+        (name = "user", description = "用户"),
+        (name = "session", description = "登录会话"),
+        (name = "api_key", description = "API Key"),
+        (name = "oauth", description = "OAuth2 授权服务"),
+        (name = "admin", description = "管理员"),
+    )
+)]
+pub struct ApiDoc;
+
+/// 登录后通过 `Authorization: Bearer <token>` 访问
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
+
+/// 返回 OpenAPI 格式的接口文档
+#[get("/api-docs/openapi.json")]
+pub async fn api_docs() -> HttpResponse {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
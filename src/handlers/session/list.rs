@@ -0,0 +1,37 @@
+use crate::dto::{ApiResp, device::DeviceDto};
+use crate::entity::devices;
+use crate::errors::AppError;
+use crate::models::auth::AuthUser;
+use crate::state::AppState;
+use actix_web::{Result, web};
+use chrono::Utc;
+use sea_orm::{ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder};
+
+/// 列出当前用户所有未过期的会话
+#[utoipa::path(
+    tag = "session",
+    responses((status = 200, body = ApiResp<Vec<DeviceDto>>)),
+    security(("bearer" = []))
+)]
+#[get("/sessions")]
+pub async fn list_sessions(
+    auth_user: AuthUser,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<Vec<DeviceDto>>, AppError> {
+    let device_list = devices::Entity::find()
+        .filter(devices::Column::UserId.eq(auth_user.id))
+        .filter(
+            Condition::any()
+                .add(devices::Column::RefreshExpireTime.is_null())
+                .add(devices::Column::RefreshExpireTime.gt(Utc::now())),
+        )
+        .order_by_desc(devices::Column::UpdateTime)
+        .all(&app_data.db_pool)
+        .await?;
+
+    let sessions = device_list
+        .into_iter()
+        .map(|device| DeviceDto::new(device, auth_user.device_id))
+        .collect::<Vec<_>>();
+    Ok(ApiResp::ok(sessions))
+}
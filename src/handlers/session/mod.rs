@@ -0,0 +1,23 @@
+pub mod list;
+pub mod rename;
+pub mod revoke;
+
+use crate::entity::devices;
+use crate::errors::{AppError, code};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+/// 查询属于当前用户的设备，不属于该用户时视为不存在
+async fn find_user_device(
+    user_id: i64,
+    device_id: i64,
+    db_pool: &sea_orm::DatabaseConnection,
+) -> Result<devices::Model, AppError> {
+    devices::Entity::find_by_id(device_id)
+        .filter(devices::Column::UserId.eq(user_id))
+        .one(db_pool)
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound(format!("会话 {device_id} 不存在"))
+                .with_code(code::SESSION_NOT_FOUND)
+        })
+}
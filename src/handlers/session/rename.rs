@@ -0,0 +1,42 @@
+use super::find_user_device;
+use crate::dto::ApiResp;
+use crate::entity::devices;
+use crate::errors::AppError;
+use crate::models::auth::AuthUser;
+use crate::state::AppState;
+use crate::utils::{extract_path_param, validate_params};
+use actix_web::{Result, web};
+use sea_orm::{ActiveModelTrait, Set};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+#[derive(Deserialize, Serialize, Debug, Validate, ToSchema)]
+struct RenameReq {
+    #[validate(length(min = 1, max = 64, message = "设备名称长度需为 1-64 个字符"))]
+    name: String,
+}
+
+/// 修改当前用户某个会话的设备名称
+#[utoipa::path(
+    tag = "session",
+    params(("id" = i64, Path, description = "会话ID")),
+    request_body = RenameReq,
+    responses((status = 200, body = ApiResp<bool>)),
+    security(("bearer" = []))
+)]
+#[put("/sessions/{id}")]
+pub async fn rename_session(
+    id: Result<web::Path<i64>>,
+    data: web::Json<RenameReq>,
+    auth_user: AuthUser,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<bool>, AppError> {
+    let device_id = extract_path_param(id, "会话ID")?;
+    validate_params(&*data)?;
+    let device = find_user_device(auth_user.id, device_id, &app_data.db_pool).await?;
+    let mut active: devices::ActiveModel = device.into();
+    active.name = Set(Some(data.name.trim().to_string()));
+    active.update(&app_data.db_pool).await?;
+    Ok(ApiResp::ok(true))
+}
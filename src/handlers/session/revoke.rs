@@ -0,0 +1,63 @@
+use super::find_user_device;
+use crate::dto::ApiResp;
+use crate::entity::devices;
+use crate::errors::AppError;
+use crate::models::audit::{AuditEvent, Outcome, action};
+use crate::models::auth::AuthUser;
+use crate::models::client_info::ClientInfo;
+use crate::state::AppState;
+use crate::utils::extract_path_param;
+use actix_web::{Result, web};
+use sea_orm::{ColumnTrait, EntityTrait, ModelTrait, QueryFilter};
+
+/// 撤销当前用户除本次请求所用会话以外的所有会话，返回撤销数量
+#[utoipa::path(
+    tag = "session",
+    responses((status = 200, body = ApiResp<u64>)),
+    security(("bearer" = []))
+)]
+#[delete("/sessions/others")]
+pub async fn revoke_other_sessions(
+    auth_user: AuthUser,
+    client: ClientInfo,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<u64>, AppError> {
+    let delete_result = devices::Entity::delete_many()
+        .filter(devices::Column::UserId.eq(auth_user.id))
+        .filter(devices::Column::Id.ne(auth_user.device_id))
+        .exec(&app_data.db_pool)
+        .await?;
+    app_data.audit.record(
+        AuditEvent::new(action::SESSION_REVOKE, Outcome::Success, &client)
+            .actor(auth_user.id, &auth_user.email)
+            .detail("others"),
+    );
+    Ok(ApiResp::ok(delete_result.rows_affected))
+}
+
+/// 撤销当前用户的某个会话
+#[utoipa::path(
+    tag = "session",
+    params(("id" = i64, Path, description = "会话ID")),
+    responses((status = 200, body = ApiResp<bool>)),
+    security(("bearer" = []))
+)]
+#[delete("/sessions/{id}")]
+pub async fn revoke_session(
+    id: Result<web::Path<i64>>,
+    auth_user: AuthUser,
+    client: ClientInfo,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<bool>, AppError> {
+    let device_id = extract_path_param(id, "会话ID")?;
+    let device = find_user_device(auth_user.id, device_id, &app_data.db_pool).await?;
+    let delete_result = device.delete(&app_data.db_pool).await?;
+    if delete_result.rows_affected > 0 {
+        app_data.audit.record(
+            AuditEvent::new(action::SESSION_REVOKE, Outcome::Success, &client)
+                .actor(auth_user.id, &auth_user.email)
+                .detail(device_id.to_string()),
+        );
+    }
+    Ok(ApiResp::ok(delete_result.rows_affected > 0))
+}
@@ -0,0 +1,30 @@
+use crate::dto::ApiResp;
+use crate::errors::AppError;
+use crate::models::client_info::ClientInfo;
+use crate::models::session::{TokenPair, refresh_session};
+use crate::state::AppState;
+use actix_web::{Result, web};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct RefreshReq {
+    refresh_token: String,
+}
+
+/// 使用刷新令牌换取新的访问令牌与刷新令牌
+#[utoipa::path(
+    tag = "session",
+    request_body = RefreshReq,
+    responses((status = 200, body = ApiResp<TokenPair>))
+)]
+#[post("/token/refresh")]
+pub async fn refresh_token(
+    data: web::Json<RefreshReq>,
+    client: ClientInfo,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<TokenPair>, AppError> {
+    let tokens = refresh_session(&data.refresh_token, None, &client, &app_data).await?;
+    Ok(ApiResp::ok(tokens))
+}
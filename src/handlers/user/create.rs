@@ -1,49 +1,45 @@
+use crate::dto::{ApiResp, user::UserDto};
 use crate::entity::users;
-use crate::errors::AppError;
-use crate::state::{ARGON2_SALT, AppState};
-use actix_web::{HttpResponse, Result, web};
+use crate::errors::{AppError, Constraint, code, constraint_violation};
+use crate::models::audit::{AuditEvent, Outcome, action};
+use crate::models::client_info::ClientInfo;
+use crate::models::password::hash_password;
+use crate::models::rbac::{DEFAULT_ROLE, assign_role};
+use crate::models::verification::send_verification_email;
+use crate::state::AppState;
+use crate::utils::validate_params;
+use actix_web::{Result, web};
 use chrono::Utc;
-use sea_orm::{ActiveModelTrait, ActiveValue::NotSet, Set};
+use sea_orm::{ActiveModelTrait, ActiveValue::NotSet, Set, TransactionTrait};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use validator::Validate;
 
-#[derive(Deserialize, Serialize)]
-pub struct PostReqJson<T> {
-    code: i32,
-    data: T,
-    message: &'static str,
-}
-
-#[derive(Deserialize, Serialize, Debug, Validate)]
+#[derive(Deserialize, Serialize, Debug, Validate, ToSchema)]
 struct CreateUser {
+    #[validate(length(min = 1, max = 64, message = "用户名长度需为 1-64 个字符"))]
     name: String,
     #[validate(email(message = "无效的邮箱地址"))]
     email: String,
+    #[validate(length(min = 8, max = 128, message = "密码长度需为 8-128 个字符"))]
     pass_word: String,
 }
+/// 注册用户，注册后需要验证邮箱
+#[utoipa::path(
+    tag = "user",
+    request_body = CreateUser,
+    responses((status = 200, body = ApiResp<UserDto>))
+)]
 #[post("/users/create")]
 pub async fn create_user(
     params: web::Json<CreateUser>,
+    client: ClientInfo,
     app_data: web::Data<AppState>,
-) -> Result<HttpResponse, AppError> {
-    println!("{:#?}", params);
+) -> Result<ApiResp<UserDto>, AppError> {
     // 参数验证
-    if let Err(e) = params.validate() {
-        // 提取所有错误消息，只保留自定义内容
-        let msg = e
-            .field_errors()
-            .values()
-            .flat_map(|errs| errs.iter().filter_map(|err| err.message.as_ref()))
-            .map(|msg| msg.to_string())
-            .collect::<Vec<_>>()
-            .join(", ");
-        return Err(AppError::ValidationError(msg));
-    }
+    validate_params(&*params)?;
 
-    let argon2_config = argon2::Config::default();
-    let hashed_password =
-        argon2::hash_encoded(params.pass_word.as_bytes(), ARGON2_SALT, &argon2_config)
-            .map_err(|e| AppError::InternalError(format!("{e}")))?;
+    let hashed_password = hash_password(&params.pass_word, &app_data.config.password)?;
 
     // 使用 sea-orm 创建用户
     let user = users::ActiveModel {
@@ -51,17 +47,39 @@ pub async fn create_user(
         name: Set(params.name.to_string()),
         email: Set(params.email.to_string()),
         pass_word: Set(hashed_password),
-        status: Set("normal".to_string()),
+        status: Set(users::STATUS_PENDING.to_string()),
         create_time: Set(Utc::now()),
         update_time: Set(Utc::now()),
+        deleted_at: Set(None),
+        status_before_delete: Set(None),
+        pending_email: Set(None),
     };
 
-    let insert_result = user.insert(&app_data.db_pool).await?;
-    println!("插入结果: {:?}", insert_result);
+    // 创建用户与授予默认角色在同一事务中完成
+    let txn = app_data.db_pool.begin().await?;
+    // 邮箱已被注册时由数据库的唯一约束拦截，并发注册同一邮箱时也不会重复
+    let insert_result = user
+        .insert(&txn)
+        .await
+        .map_err(|e| match constraint_violation(&e) {
+            Some(Constraint::Unique) => {
+                AppError::Conflict(format!("邮箱 {} 已被注册", params.email))
+                    .with_code(code::EMAIL_TAKEN)
+            }
+            _ => e.into(),
+        })?;
+    assign_role(&txn, insert_result.id, DEFAULT_ROLE).await?;
+    txn.commit().await?;
+    app_data.audit.record(
+        AuditEvent::new(action::USER_CREATE, Outcome::Success, &client)
+            .actor(insert_result.id, &insert_result.email)
+            .target(insert_result.id),
+    );
+
+    // 邮件发送失败不影响注册结果，用户可以通过重新发送接口再次获取验证邮件
+    if let Err(e) = send_verification_email(&insert_result, &app_data).await {
+        warn!("用户 {} 的验证邮件发送失败: {e}", insert_result.id);
+    }
 
-    Ok(HttpResponse::Ok().json(PostReqJson {
-        code: 200,
-        data: insert_result, // sea-orm 插入成功返回记录而不是影响行数，这里简单返回1
-        message: "ok",
-    }))
+    Ok(ApiResp::ok(UserDto::from(insert_result)))
 }
@@ -1,57 +1,41 @@
-use crate::entity::{devices, users};
-use crate::errors::AppError;
+use crate::dto::ApiResp;
+use crate::errors::{AppError, code};
+use crate::models::audit::{AuditEvent, Outcome, action};
+use crate::models::client_info::ClientInfo;
+use crate::models::rbac::{Permissions, perm};
+use crate::models::user_deletion;
 use crate::state::AppState;
 use crate::utils::extract_path_param;
-use actix_web::{HttpResponse, Result, web};
-use sea_orm::{ActiveModelTrait, ColumnTrait, DeleteResult, EntityTrait, QueryFilter};
-
-#[derive(Deserialize, Serialize)]
-pub struct PostReqJson<T> {
-    code: i32,
-    data: T,
-    message: &'static str,
-}
-
-async fn find_user_active_model(
-    user_id: i64,
-    db_pool: &sea_orm::DatabaseConnection,
-) -> Result<users::ActiveModel, AppError> {
-    let model = users::Entity::find_by_id(user_id).one(db_pool).await?;
-    if model.is_none() {
-        return Err(AppError::NotFound(format!("用户ID {user_id} 不存在")));
-    }
-    Ok(model.unwrap().into())
-}
-
-async fn delete_user_devices(
-    user_id: i64,
-    db_pool: &sea_orm::DatabaseConnection,
-) -> Result<DeleteResult, AppError> {
-    let device_delete_result = devices::Entity::delete_many()
-        .filter(crate::entity::devices::Column::UserId.eq(user_id))
-        .exec(db_pool)
-        .await?;
-    println!("删除设备结果: {:?}", device_delete_result);
-    Ok(device_delete_result)
-}
+use actix_web::{Result, web};
 
+/// 删除用户，标记为已删除并撤销该用户的所有会话
+///
+/// 保留期 `user_deletion.retention_days` 内管理员可以恢复，过期后由后台任务彻底删除
+#[utoipa::path(
+    tag = "user",
+    params(("id" = i64, Path, description = "用户ID")),
+    responses((status = 200, body = ApiResp<bool>)),
+    security(("bearer" = []))
+)]
 #[delete("/users/delete/{id}")]
 pub async fn delete_user(
     id: Result<web::Path<String>>,
+    permissions: Permissions,
+    client: ClientInfo,
     app_data: web::Data<AppState>,
-) -> Result<HttpResponse, AppError> {
+) -> Result<ApiResp<bool>, AppError> {
+    permissions.require(perm::USER_DELETE)?;
     let user_id = extract_path_param(id, "无效的用户ID")?.parse::<i64>()?;
-    // 使用通用函数删除该用户下的所有设备
-    delete_user_devices(user_id, &app_data.db_pool).await?;
-
-    // 使用通用函数查找用户 ActiveModel
-    let user = find_user_active_model(user_id, &app_data.db_pool).await?;
-
-    let delete_result = user.delete(&app_data.db_pool).await?;
-    println!("删除结果: {:?}", delete_result);
-    Ok(HttpResponse::Ok().json(PostReqJson {
-        code: 200,
-        data: delete_result.rows_affected > 0, // sea-orm 删除成功返回影响行数
-        message: "ok",
-    }))
+    if !user_deletion::soft_delete(&app_data.db_pool, user_id).await? {
+        return Err(
+            AppError::NotFound(format!("用户ID {user_id} 不存在")).with_code(code::USER_NOT_FOUND)
+        );
+    }
+    info!("管理员 {} 删除了用户 {user_id}", permissions.user.id);
+    app_data.audit.record(
+        AuditEvent::new(action::USER_DELETE, Outcome::Success, &client)
+            .actor(permissions.user.id, &permissions.user.email)
+            .target(user_id),
+    );
+    Ok(ApiResp::ok(true))
 }
@@ -1,39 +1,161 @@
+use crate::dto::{ApiResp, user::UserDto};
 use crate::entity::users;
-use crate::errors::AppError;
+use crate::errors::{AppError, code};
+use crate::models::pagination::{Page, PageParams, Sort, paginate};
+use crate::models::rbac::{Permissions, perm};
 use crate::state::AppState;
-use actix_web::{HttpResponse, Result, web};
+use crate::utils::{contains_ignore_case, extract_path_param, serde_timestamp_option};
+use actix_web::{Result, web};
+use chrono::{DateTime, Utc};
 use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Deserialize, Serialize, Debug)]
+/// 用户列表允许排序的字段，第一个为默认排序
+const SORT_FIELDS: [(&str, users::Column); 6] = [
+    ("id", users::Column::Id),
+    ("name", users::Column::Name),
+    ("email", users::Column::Email),
+    ("status", users::Column::Status),
+    ("createTime", users::Column::CreateTime),
+    ("updateTime", users::Column::UpdateTime),
+];
+
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
 struct Info {
     name: String,
+    page: Option<u64>,
+    size: Option<u64>,
+    cursor: Option<String>,
 }
 
-#[derive(Deserialize, Serialize)]
-pub struct PostReqJson<T> {
-    code: i32,
-    data: T,
-    message: &'static str,
-}
-
+/// 按用户名模糊查询，按 ID 排序分页返回
+///
+/// 已废弃，请使用支持排序与更多过滤条件的 `GET /users`
+#[utoipa::path(
+    tag = "user",
+    request_body = Info,
+    responses((status = 200, body = ApiResp<Page<UserDto>>)),
+    security(("bearer" = []))
+)]
 #[post("/users/getQueryUsers")]
 pub async fn get_query_users(
     info: web::Json<Info>,
+    permissions: Permissions,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<Page<UserDto>>, AppError> {
+    permissions.require(perm::USER_READ)?;
+
+    let info = info.into_inner();
+    let select =
+        users::Entity::find_active().filter(contains_ignore_case(users::Column::Name, &info.name));
+    let sort = Sort::parse(None, &SORT_FIELDS)?;
+    let params = PageParams {
+        page: info.page,
+        size: info.size,
+        cursor: info.cursor,
+    };
+    let page = paginate(&app_data.db_pool, select, users::Column::Id, &sort, &params).await?;
+    Ok(ApiResp::ok(page.map(UserDto::from)))
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+struct ListQuery {
+    page: Option<u64>,
+    size: Option<u64>,
+    cursor: Option<String>,
+    /// 排序字段，前面加 `-` 表示倒序，例如 `-createTime`
+    sort: Option<String>,
+    /// 用户名包含的关键字，不区分大小写
+    name: Option<String>,
+    /// 邮箱包含的关键字，不区分大小写
+    email: Option<String>,
+    /// 账号状态，默认不包含已删除的用户，传入 `deleted` 时只查询已删除的用户
+    status: Option<String>,
+    /// 注册时间起始 (包含)，格式 `2025-01-01 00:00:00`
+    #[serde(default, with = "serde_timestamp_option")]
+    #[param(value_type = Option<String>)]
+    created_from: Option<DateTime<Utc>>,
+    /// 注册时间结束 (不包含)
+    #[serde(default, with = "serde_timestamp_option")]
+    #[param(value_type = Option<String>)]
+    created_to: Option<DateTime<Utc>>,
+}
+
+/// 分页查询用户，支持按用户名、邮箱、状态与注册时间过滤
+#[utoipa::path(
+    tag = "user",
+    params(ListQuery),
+    responses((status = 200, body = ApiResp<Page<UserDto>>)),
+    security(("bearer" = []))
+)]
+#[get("/users")]
+pub async fn list_users(
+    query: Result<web::Query<ListQuery>>,
+    permissions: Permissions,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<Page<UserDto>>, AppError> {
+    permissions.require(perm::USER_READ)?;
+    let query = query
+        // 错误信息由 `QueryConfig` 生成，这里只是推迟到权限检查之后返回
+        .map_err(|e| AppError::BadRequest(e.to_string()).with_code(code::INVALID_QUERY))?
+        .into_inner();
+    let sort = Sort::parse(query.sort.as_deref(), &SORT_FIELDS)?;
+
+    // 默认不包含已删除的用户，按状态 `deleted` 过滤时查询已删除的用户
+    let mut select = match query.status.as_deref() {
+        Some(users::STATUS_DELETED) => users::Entity::find(),
+        _ => users::Entity::find_active(),
+    };
+    if let Some(name) = &query.name {
+        select = select.filter(contains_ignore_case(users::Column::Name, name));
+    }
+    if let Some(email) = &query.email {
+        select = select.filter(contains_ignore_case(users::Column::Email, email));
+    }
+    if let Some(status) = &query.status {
+        select = select.filter(users::Column::Status.eq(status));
+    }
+    if let Some(from) = query.created_from {
+        select = select.filter(users::Column::CreateTime.gte(from));
+    }
+    if let Some(to) = query.created_to {
+        select = select.filter(users::Column::CreateTime.lt(to));
+    }
+
+    let params = PageParams {
+        page: query.page,
+        size: query.size,
+        cursor: query.cursor,
+    };
+    let page = paginate(&app_data.db_pool, select, users::Column::Id, &sort, &params).await?;
+    Ok(ApiResp::ok(page.map(UserDto::from)))
+}
+
+/// 查询单个用户，只能查询自己，拥有 `user:read` 权限时可以查询任意用户
+#[utoipa::path(
+    tag = "user",
+    params(("id" = i64, Path, description = "用户ID")),
+    responses((status = 200, body = ApiResp<UserDto>)),
+    security(("bearer" = []))
+)]
+#[get("/users/{id}")]
+pub async fn get_user(
+    id: Result<web::Path<i64>>,
+    permissions: Permissions,
     app_data: web::Data<AppState>,
-) -> Result<HttpResponse, AppError> {
-    println!("{:#?}", info.name);
-
-    // 使用 sea-orm 进行查询
-    let user_list = users::Entity::find()
-        .filter(crate::entity::users::Column::Name.contains(&info.name))
-        .all(&app_data.db_pool)
-        .await?;
-
-    println!("{:#?}", user_list);
-    Ok(HttpResponse::Ok().json(PostReqJson {
-        code: 200,
-        data: user_list,
-        message: "ok",
-    }))
+) -> Result<ApiResp<UserDto>, AppError> {
+    let user_id = extract_path_param(id, "用户ID")?;
+    if user_id != permissions.user.id {
+        permissions.require(perm::USER_READ)?;
+    }
+    let user = users::Entity::find_active_by_id(user_id)
+        .one(&app_data.db_pool)
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound(format!("用户ID {user_id} 不存在")).with_code(code::USER_NOT_FOUND)
+        })?;
+    Ok(ApiResp::ok(UserDto::from(user)))
 }
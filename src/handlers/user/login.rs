@@ -1,66 +1,224 @@
-use crate::entity::{devices, users};
-use crate::errors::AppError;
-use crate::models::token::generate_token;
+use crate::dto::{ApiResp, user::LoginDto};
+use crate::entity::users;
+use crate::errors::{AppError, code};
+use crate::models::audit::{AuditEvent, Outcome, action};
+use crate::models::client_info::ClientInfo;
+use crate::models::password::{dummy_verify, hash_password, needs_rehash, verify_password};
+use crate::models::session::create_session;
+use crate::models::two_factor;
+use crate::models::user_token::{self, purpose};
 use crate::state::AppState;
 use actix_web::{HttpResponse, Result, web};
-use sea_orm::{ActiveModelTrait, ActiveValue::NotSet, ColumnTrait, EntityTrait, QueryFilter, Set};
+use sea_orm::{ActiveModelTrait, ColumnTrait, QueryFilter, Set};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use validator::Validate;
 
-#[derive(Deserialize, Serialize, Debug, Validate)]
+#[derive(Deserialize, Serialize, Debug, Validate, ToSchema)]
 struct LoginReq {
     #[validate(email)]
     email: String,
     pass_word: String,
 }
-#[derive(Deserialize, Serialize)]
-struct LoginResp<T> {
-    code: i32,
-    data: T,
-    message: &'static str,
-}
 
+/// 登录失败时统一返回的错误信息，不区分用户不存在与密码错误
+const LOGIN_FAILED: &str = "邮箱或密码错误";
+
+/// 邮箱密码登录，启用了两步验证时返回挑战令牌，需要再调用 `/users/login/2fa`
+#[utoipa::path(
+    tag = "user",
+    request_body = LoginReq,
+    responses((status = 200, body = ApiResp<LoginDto>))
+)]
 #[post("/users/login")]
 pub async fn login(
     data: web::Json<LoginReq>,
+    client: ClientInfo,
     app_data: web::Data<AppState>,
 ) -> Result<HttpResponse, AppError> {
-    let user_opt = users::Entity::find()
+    let ip = client.ip.as_deref();
+    app_data
+        .login_guard
+        .check(&data.email, ip)
+        .inspect_err(|_| audit_failure(&data.email, None, "已锁定", &client, &app_data))?;
+
+    let user_opt = users::Entity::find_active()
         .filter(users::Column::Email.eq(&data.email))
         .one(&app_data.db_pool)
         .await?;
-
-    if let Some(user) = user_opt {
-        if argon2::verify_encoded(&user.pass_word, data.pass_word.as_bytes())
-            .map_err(|e| AppError::InternalError(format!("Password verification error: {}", e)))?
-        {
-            let token = generate_token(&user.email);
-            let device = devices::ActiveModel {
-                id: NotSet,
-                user_id: Set(user.id),
-                token: Set(token.clone()),
-                ..Default::default()
-            };
-            if let Err(e) = device.insert(&app_data.db_pool).await {
-                return Err(AppError::InternalError(format!("设备写入失败: {}", e)));
-            }
-            return Ok(HttpResponse::Ok().json(LoginResp {
-                code: 200,
-                data: json!({ "user": user, "token": token }),
-                message: "Login successful",
-            }));
-        } else {
-            return Ok(HttpResponse::Unauthorized().json(LoginResp::<()> {
-                code: 401,
-                data: (),
-                message: "Invalid password",
-            }));
+    let verified = match &user_opt {
+        Some(user) => verify_password(&user.pass_word, &data.pass_word)?,
+        None => {
+            dummy_verify(&data.pass_word, &app_data.config.password);
+            false
         }
+    };
+    let user_id = user_opt.as_ref().map(|user| user.id);
+    let Some(user) = user_opt.filter(|_| verified) else {
+        audit_failure(&data.email, user_id, "邮箱或密码错误", &client, &app_data);
+        let delay = app_data.login_guard.record_failure(&data.email, ip);
+        info!(email = %data.email, ip = ?ip, "登录失败");
+        // 逐步增加失败响应的等待时间，降低暴力破解的速度
+        tokio::time::sleep(delay).await;
+        return Err(AppError::Unauthorized(LOGIN_FAILED.to_string()).with_code(code::LOGIN_FAILED));
+    };
+    app_data.login_guard.record_success(&data.email);
+
+    if user.status == users::STATUS_PENDING && !app_data.config.verification.allow_unverified_login
+    {
+        audit_failure(&user.email, Some(user.id), "邮箱未验证", &client, &app_data);
+        return Err(
+            AppError::Forbidden("邮箱尚未验证，请先完成邮箱验证".to_string())
+                .with_code(code::EMAIL_NOT_VERIFIED),
+        );
+    }
+
+    if needs_rehash(&user.pass_word, &app_data.config.password) {
+        rehash_password(&user, &data.pass_word, &app_data).await;
     }
 
-    Ok(HttpResponse::Unauthorized().json(LoginResp::<()> {
-        code: 401,
-        data: (),
-        message: "User not found",
-    }))
+    if let Some(challenge) = two_factor_challenge(user.id, &app_data).await? {
+        return Ok(challenge);
+    }
+    login_success(&user, &client, "password", &app_data)
+        .await
+        .map(HttpResponse::from)
+}
+
+/// 启用了两步验证时返回挑战令牌，提交验证码后才签发访问令牌，未启用时返回 `None`
+pub(crate) async fn two_factor_challenge(
+    user_id: i64,
+    app_data: &AppState,
+) -> Result<Option<HttpResponse>, AppError> {
+    if !two_factor::is_enabled(&app_data.db_pool, user_id).await? {
+        return Ok(None);
+    }
+    let expire = app_data.config.two_factor.challenge_expire;
+    let challenge_token =
+        user_token::issue(app_data, user_id, purpose::TWO_FACTOR_CHALLENGE, expire).await?;
+    Ok(Some(
+        ApiResp::with_message(
+            json!({
+                "twoFactorRequired": true,
+                "challengeToken": challenge_token,
+                "expiresIn": expire,
+            }),
+            "Two-factor authentication required",
+        )
+        .into(),
+    ))
+}
+
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
+struct TwoFactorLoginReq {
+    challenge_token: String,
+    /// TOTP 验证码或恢复码
+    code: String,
+}
+
+/// 登录第二步: 提交密码登录返回的挑战令牌与两步验证码
+#[utoipa::path(
+    tag = "user",
+    request_body = TwoFactorLoginReq,
+    responses((status = 200, body = ApiResp<LoginDto>))
+)]
+#[post("/users/login/2fa")]
+pub async fn login_two_factor(
+    data: web::Json<TwoFactorLoginReq>,
+    client: ClientInfo,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<LoginDto>, AppError> {
+    let invalid = || AppError::Unauthorized("登录已过期，请重新输入密码".to_string());
+    let user_id = user_token::peek(
+        &app_data,
+        &data.challenge_token,
+        purpose::TWO_FACTOR_CHALLENGE,
+    )
+    .await?
+    .ok_or_else(invalid)?;
+    let user = users::Entity::find_active_by_id(user_id)
+        .one(&app_data.db_pool)
+        .await?
+        .ok_or_else(invalid)?;
+
+    // 验证码同样计入登录失败次数，防止暴力尝试
+    let ip = client.ip.as_deref();
+    app_data
+        .login_guard
+        .check(&user.email, ip)
+        .inspect_err(|_| audit_failure(&user.email, Some(user.id), "已锁定", &client, &app_data))?;
+    if !two_factor::verify_code(user.id, &data.code, &app_data).await? {
+        audit_failure(
+            &user.email,
+            Some(user.id),
+            "两步验证码错误",
+            &client,
+            &app_data,
+        );
+        let delay = app_data.login_guard.record_failure(&user.email, ip);
+        info!(email = %user.email, ip = ?ip, "两步验证失败");
+        tokio::time::sleep(delay).await;
+        return Err(AppError::Unauthorized("验证码错误".to_string())
+            .with_code(code::INVALID_TWO_FACTOR_CODE));
+    }
+    // 挑战令牌只能使用一次
+    user_token::consume(
+        &app_data,
+        &data.challenge_token,
+        purpose::TWO_FACTOR_CHALLENGE,
+    )
+    .await?
+    .ok_or_else(invalid)?;
+    app_data.login_guard.record_success(&user.email);
+    login_success(&user, &client, "2fa", &app_data).await
+}
+
+/// 记录登录失败的审计日志，`user_id` 为邮箱对应的用户，用户不存在时为空
+fn audit_failure(
+    email: &str,
+    user_id: Option<i64>,
+    reason: &str,
+    client: &ClientInfo,
+    app_data: &AppState,
+) {
+    let mut event = AuditEvent::new(action::LOGIN, Outcome::Failure, client)
+        .actor_email(email)
+        .detail(reason);
+    event.actor_id = user_id;
+    app_data.audit.record(event);
+}
+
+/// 创建设备会话并返回登录成功的响应，`method` 为登录方式，记录在审计日志中
+pub(crate) async fn login_success(
+    user: &users::Model,
+    client: &ClientInfo,
+    method: &str,
+    app_data: &AppState,
+) -> Result<ApiResp<LoginDto>, AppError> {
+    let tokens = create_session(user, client, app_data).await?;
+    app_data.audit.record(
+        AuditEvent::new(action::LOGIN, Outcome::Success, client)
+            .actor(user.id, &user.email)
+            .detail(method),
+    );
+    Ok(ApiResp::with_message(
+        LoginDto::new(user.clone(), tokens),
+        "Login successful",
+    ))
+}
+
+/// 使用当前配置与随机盐值重新计算密码哈希，失败时仅记录日志，不影响本次登录
+async fn rehash_password(user: &users::Model, pass_word: &str, app_data: &AppState) {
+    let result = async {
+        let hashed_password = hash_password(pass_word, &app_data.config.password)?;
+        let mut active: users::ActiveModel = user.clone().into();
+        active.pass_word = Set(hashed_password);
+        active.update(&app_data.db_pool).await?;
+        Ok::<_, AppError>(())
+    }
+    .await;
+    match result {
+        Ok(()) => info!("用户 {} 的密码哈希已按当前参数重新计算", user.id),
+        Err(e) => warn!("用户 {} 的密码哈希重新计算失败: {e}", user.id),
+    }
 }
@@ -1,41 +1,61 @@
+use crate::dto::ApiResp;
 use crate::entity::devices;
 use crate::errors::AppError;
+use crate::models::audit::{AuditEvent, Outcome, action};
+use crate::models::auth::AuthUser;
+use crate::models::client_info::ClientInfo;
 use crate::state::AppState;
-use actix_web::{HttpResponse, Result, web};
+use actix_web::{Result, web};
 use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 /// 用户登出请求的结构体
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Default, ToSchema)]
 struct LogoutReq {
-    id: i64,
-    token: Option<String>,
-}
-#[derive(Deserialize, Serialize)]
-struct LoginResp<T> {
-    code: i32,
-    data: T,
-    message: &'static str,
+    /// 是否退出该用户的所有会话，默认只退出当前会话
+    #[serde(default)]
+    all: bool,
 }
 
-/// 处理用户登出请求
+/// 处理用户登出请求，只作用于调用者自己的会话，返回撤销的会话数量
+#[utoipa::path(
+    tag = "user",
+    request_body(content = Option<LogoutReq>),
+    responses((status = 200, body = ApiResp<u64>)),
+    security(("bearer" = []))
+)]
 #[post("/logout")]
 pub async fn logout(
-    data: web::Json<LogoutReq>,
+    auth_user: AuthUser,
+    data: Option<web::Json<LogoutReq>>,
+    client: ClientInfo,
     app_data: web::Data<AppState>,
-) -> Result<HttpResponse, AppError> {
-    let mut query = devices::Entity::delete_many().filter(devices::Column::UserId.eq(data.id));
-    // token 可传可不传，传空字符串也视为删除所有
-    if let Some(token) = &data.token {
-        if !token.is_empty() {
-            query = query.filter(devices::Column::Token.eq(token));
-        }
+) -> Result<ApiResp<u64>, AppError> {
+    let data = data.map(web::Json::into_inner).unwrap_or_default();
+    let mut query = devices::Entity::delete_many().filter(devices::Column::UserId.eq(auth_user.id));
+    if !data.all {
+        query = query.filter(devices::Column::Id.eq(auth_user.device_id));
     }
-    let _ = query.exec(&app_data.db_pool).await;
+    let delete_result = query.exec(&app_data.db_pool).await?;
+    info!(
+        "用户 {} 登出{}，撤销会话 {} 个",
+        auth_user.id,
+        if data.all {
+            "所有设备"
+        } else {
+            "当前设备"
+        },
+        delete_result.rows_affected
+    );
+    app_data.audit.record(
+        AuditEvent::new(action::LOGOUT, Outcome::Success, &client)
+            .actor(auth_user.id, &auth_user.email)
+            .detail(if data.all { "all" } else { "current" }),
+    );
 
-    Ok(HttpResponse::Ok().json(LoginResp {
-        code: 200,
-        data: true,
-        message: "Logout successful",
-    }))
+    Ok(ApiResp::with_message(
+        delete_result.rows_affected,
+        "Logout successful",
+    ))
 }
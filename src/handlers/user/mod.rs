@@ -3,3 +3,7 @@ pub mod delete;
 pub mod get;
 pub mod login;
 pub mod logout;
+pub mod password;
+pub mod two_factor;
+pub mod update;
+pub mod verify;
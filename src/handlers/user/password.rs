@@ -0,0 +1,130 @@
+use crate::dto::ApiResp;
+use crate::entity::users;
+use crate::errors::{AppError, code};
+use crate::models::auth::AuthUser;
+use crate::models::client_info::ClientInfo;
+use crate::models::password::verify_password;
+use crate::models::password_reset::{send_reset_email, update_password};
+use crate::models::user_token::{self, purpose};
+use crate::state::AppState;
+use crate::utils::validate_params;
+use actix_web::{Result, web};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
+
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+struct ChangePasswordReq {
+    old_pass_word: String,
+    #[validate(length(min = 8, max = 128, message = "密码长度需为 8-128 个字符"))]
+    new_pass_word: String,
+}
+
+/// 修改当前用户的密码，成功后所有设备 (包括当前设备) 都需要重新登录
+///
+/// 原密码错误与登录失败一样计入失败次数，防止通过该接口暴力尝试密码
+#[utoipa::path(
+    tag = "user",
+    request_body = ChangePasswordReq,
+    responses((status = 200, body = ApiResp<u64>)),
+    security(("bearer" = []))
+)]
+#[post("/users/password/change")]
+pub async fn change_password(
+    auth_user: AuthUser,
+    params: web::Json<ChangePasswordReq>,
+    client: ClientInfo,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<u64>, AppError> {
+    validate_params(&*params)?;
+    let user = users::Entity::find_active_by_id(auth_user.id)
+        .one(&app_data.db_pool)
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound("用户不存在".to_string()).with_code(code::USER_NOT_FOUND)
+        })?;
+    let ip = client.ip.as_deref();
+    app_data.login_guard.check(&user.email, ip)?;
+    if !verify_password(&user.pass_word, &params.old_pass_word)? {
+        let delay = app_data.login_guard.record_failure(&user.email, ip);
+        info!(email = %user.email, ip = ?ip, "修改密码时原密码错误");
+        tokio::time::sleep(delay).await;
+        return Err(AppError::BadRequest("原密码错误".to_string()));
+    }
+    app_data.login_guard.record_success(&user.email);
+    let revoked = update_password(user.id, &params.new_pass_word, &app_data).await?;
+    Ok(ApiResp::with_message(revoked, "密码已修改，请重新登录"))
+}
+
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+struct ForgotPasswordReq {
+    #[validate(email(message = "无效的邮箱地址"))]
+    email: String,
+}
+
+/// 发送找回密码邮件
+///
+/// 无论邮箱是否存在都返回相同的结果，邮件在后台发送，响应时间同样不会泄露注册信息
+#[utoipa::path(
+    tag = "user",
+    request_body = ForgotPasswordReq,
+    responses((status = 200, body = ApiResp<bool>))
+)]
+#[post("/users/password/forgot")]
+pub async fn forgot_password(
+    params: web::Json<ForgotPasswordReq>,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<bool>, AppError> {
+    validate_params(&*params)?;
+    let user = users::Entity::find_active()
+        .filter(users::Column::Email.eq(&params.email))
+        .one(&app_data.db_pool)
+        .await?;
+    // 发送过于频繁或邮件发送失败时只记录日志，同样返回成功
+    if let Some(user) = user {
+        let app_data = app_data.clone();
+        actix_web::rt::spawn(async move {
+            if let Err(e) = send_reset_email(&user, &app_data).await {
+                warn!("用户 {} 的重置密码邮件未发送: {e}", user.id);
+            }
+        });
+    }
+    Ok(ApiResp::with_message(
+        true,
+        "如果该邮箱已注册，重置密码邮件已发送",
+    ))
+}
+
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+struct ResetPasswordReq {
+    token: String,
+    #[validate(length(min = 8, max = 128, message = "密码长度需为 8-128 个字符"))]
+    new_pass_word: String,
+}
+
+/// 使用邮件中的重置令牌设置新密码，令牌只能使用一次
+#[utoipa::path(
+    tag = "user",
+    request_body = ResetPasswordReq,
+    responses((status = 200, body = ApiResp<u64>))
+)]
+#[post("/users/password/reset")]
+pub async fn reset_password(
+    params: web::Json<ResetPasswordReq>,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<u64>, AppError> {
+    validate_params(&*params)?;
+    let user_id = user_token::consume(&app_data, &params.token, purpose::RESET_PASSWORD)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("重置链接无效或已过期".to_string()))?;
+    let revoked = update_password(user_id, &params.new_pass_word, &app_data).await?;
+    // 能收到重置邮件说明邮箱属于该用户，尚未验证的账号一并标记为已验证
+    users::Entity::update_many()
+        .col_expr(users::Column::Status, users::STATUS_NORMAL.into())
+        .filter(users::Column::Id.eq(user_id))
+        .filter(users::Column::Status.eq(users::STATUS_PENDING))
+        .exec(&app_data.db_pool)
+        .await?;
+    Ok(ApiResp::with_message(revoked, "密码已重置，请重新登录"))
+}
@@ -0,0 +1,79 @@
+use crate::dto::ApiResp;
+use crate::entity::users;
+use crate::errors::{AppError, code};
+use crate::models::auth::AuthUser;
+use crate::models::two_factor;
+use crate::state::AppState;
+use actix_web::{Result, web};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+#[derive(Deserialize, Debug, ToSchema)]
+struct CodeReq {
+    /// TOTP 验证码或恢复码
+    code: String,
+}
+
+/// 生成两步验证密钥，返回密钥与 otpauth URI，需要再调用确认接口才会启用
+#[utoipa::path(
+    tag = "user",
+    responses((status = 200, body = ApiResp<two_factor::TotpSetup>)),
+    security(("bearer" = []))
+)]
+#[post("/users/2fa/setup")]
+pub async fn setup_two_factor(
+    auth_user: AuthUser,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<two_factor::TotpSetup>, AppError> {
+    let user = users::Entity::find_active_by_id(auth_user.id)
+        .one(&app_data.db_pool)
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound("用户不存在".to_string()).with_code(code::USER_NOT_FOUND)
+        })?;
+    let setup = two_factor::begin_setup(&user, &app_data).await?;
+    Ok(ApiResp::ok(setup))
+}
+
+/// 提交身份验证器中的首个验证码确认启用，返回只显示一次的恢复码
+#[utoipa::path(
+    tag = "user",
+    request_body = CodeReq,
+    responses((status = 200, body = ApiResp<Vec<String>>)),
+    security(("bearer" = []))
+)]
+#[post("/users/2fa/confirm")]
+pub async fn confirm_two_factor(
+    auth_user: AuthUser,
+    params: web::Json<CodeReq>,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<Vec<String>>, AppError> {
+    let recovery_codes = two_factor::confirm_setup(auth_user.id, &params.code, &app_data).await?;
+    Ok(ApiResp::with_message(
+        recovery_codes,
+        "两步验证已启用，请妥善保存恢复码",
+    ))
+}
+
+/// 关闭两步验证，需要提交当前验证码或恢复码
+#[utoipa::path(
+    tag = "user",
+    request_body = CodeReq,
+    responses((status = 200, body = ApiResp<bool>)),
+    security(("bearer" = []))
+)]
+#[post("/users/2fa/disable")]
+pub async fn disable_two_factor(
+    auth_user: AuthUser,
+    params: web::Json<CodeReq>,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<bool>, AppError> {
+    if !two_factor::verify_code(auth_user.id, &params.code, &app_data).await? {
+        return Err(
+            AppError::BadRequest("验证码错误".to_string()).with_code(code::INVALID_TWO_FACTOR_CODE)
+        );
+    }
+    two_factor::disable(&app_data.db_pool, auth_user.id).await?;
+    info!("用户 {} 关闭了两步验证", auth_user.id);
+    Ok(ApiResp::with_message(true, "两步验证已关闭"))
+}
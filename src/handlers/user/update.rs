@@ -0,0 +1,163 @@
+use crate::dto::{ApiResp, user::UserDto};
+use crate::entity::users;
+use crate::errors::{AppError, code};
+use crate::models::audit::{AuditEvent, Outcome, action};
+use crate::models::client_info::ClientInfo;
+use crate::models::rbac::{Permissions, perm};
+use crate::models::verification::send_email_change_email;
+use crate::state::AppState;
+use crate::utils::{extract_path_param, validate_params};
+use actix_web::{Result, web};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+#[derive(Deserialize, Serialize, Debug, Validate, ToSchema)]
+struct UpdateUser {
+    #[validate(length(min = 1, max = 64, message = "用户名长度需为 1-64 个字符"))]
+    name: String,
+    #[validate(email(message = "无效的邮箱地址"))]
+    email: String,
+}
+
+/// 只修改提交的字段
+#[derive(Deserialize, Serialize, Debug, Validate, ToSchema)]
+struct PatchUser {
+    #[validate(length(min = 1, max = 64, message = "用户名长度需为 1-64 个字符"))]
+    name: Option<String>,
+    #[validate(email(message = "无效的邮箱地址"))]
+    email: Option<String>,
+}
+
+/// 修改用户资料，需要提交全部字段
+#[utoipa::path(
+    tag = "user",
+    params(("id" = i64, Path, description = "用户ID")),
+    request_body = UpdateUser,
+    responses((status = 200, body = ApiResp<UserDto>)),
+    security(("bearer" = []))
+)]
+#[put("/users/{id}")]
+pub async fn update_user(
+    id: Result<web::Path<i64>>,
+    params: web::Json<UpdateUser>,
+    permissions: Permissions,
+    client: ClientInfo,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<UserDto>, AppError> {
+    let user_id = extract_path_param(id, "用户ID")?;
+    validate_params(&*params)?;
+    let params = params.into_inner();
+    let user = apply_update(
+        user_id,
+        Some(params.name),
+        Some(params.email),
+        &permissions,
+        &client,
+        &app_data,
+    )
+    .await?;
+    Ok(ApiResp::ok(UserDto::from(user)))
+}
+
+/// 修改用户资料，只修改提交的字段
+#[utoipa::path(
+    tag = "user",
+    params(("id" = i64, Path, description = "用户ID")),
+    request_body = PatchUser,
+    responses((status = 200, body = ApiResp<UserDto>)),
+    security(("bearer" = []))
+)]
+#[patch("/users/{id}")]
+pub async fn patch_user(
+    id: Result<web::Path<i64>>,
+    params: web::Json<PatchUser>,
+    permissions: Permissions,
+    client: ClientInfo,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<UserDto>, AppError> {
+    let user_id = extract_path_param(id, "用户ID")?;
+    validate_params(&*params)?;
+    let params = params.into_inner();
+    let user = apply_update(
+        user_id,
+        params.name,
+        params.email,
+        &permissions,
+        &client,
+        &app_data,
+    )
+    .await?;
+    Ok(ApiResp::ok(UserDto::from(user)))
+}
+
+/// 本站登录的用户可以修改自己的资料，修改其他用户或通过第三方应用、API Key 修改时需要 `user:update` 权限
+fn ensure_can_edit(permissions: &Permissions, user_id: i64) -> Result<(), AppError> {
+    let caller = &permissions.user;
+    if caller.id == user_id && caller.client_id.is_none() && caller.api_key_id.is_none() {
+        return Ok(());
+    }
+    permissions.require(perm::USER_UPDATE)
+}
+
+/// 修改用户名与邮箱
+///
+/// 新邮箱先保存为待验证邮箱并发送验证邮件，验证前原邮箱、账号状态与会话都保持不变
+async fn apply_update(
+    user_id: i64,
+    name: Option<String>,
+    email: Option<String>,
+    permissions: &Permissions,
+    client: &ClientInfo,
+    app_data: &AppState,
+) -> Result<users::Model, AppError> {
+    ensure_can_edit(permissions, user_id)?;
+    let user = users::Entity::find_active_by_id(user_id)
+        .one(&app_data.db_pool)
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound(format!("用户ID {user_id} 不存在")).with_code(code::USER_NOT_FOUND)
+        })?;
+    let email = email.filter(|email| *email != user.email);
+    let mut changed = Vec::new();
+    let mut active: users::ActiveModel = user.into();
+    if let Some(name) = name {
+        active.name = Set(name);
+        changed.push("name");
+    }
+    if let Some(email) = &email {
+        // `auth.admins` 中的邮箱视为管理员，不能通过修改邮箱获得管理员权限
+        if app_data.config.auth.is_admin(email) && !permissions.has(perm::USER_UPDATE) {
+            return Err(AppError::Forbidden(format!("不能使用邮箱 {email}")));
+        }
+        // 已删除的用户在彻底删除前仍占用邮箱，以便恢复
+        let taken = users::Entity::find()
+            .filter(users::Column::Email.eq(email))
+            .one(&app_data.db_pool)
+            .await?;
+        if taken.is_some() {
+            return Err(
+                AppError::Conflict(format!("邮箱 {email} 已被注册")).with_code(code::EMAIL_TAKEN)
+            );
+        }
+        active.pending_email = Set(Some(email.clone()));
+        changed.push("pending_email");
+    }
+
+    let user = active.update(&app_data.db_pool).await?;
+    app_data.audit.record(
+        AuditEvent::new(action::USER_UPDATE, Outcome::Success, client)
+            .actor(permissions.user.id, &permissions.user.email)
+            .target(user_id)
+            .detail(changed.join(",")),
+    );
+
+    // 邮件发送失败不影响修改结果，用户可以再次提交修改重新获取验证邮件
+    if let Some(email) = &email
+        && let Err(e) = send_email_change_email(&user, email, app_data).await
+    {
+        warn!("用户 {user_id} 的新邮箱验证邮件发送失败: {e}");
+    }
+    Ok(user)
+}
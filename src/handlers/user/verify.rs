@@ -0,0 +1,96 @@
+use crate::dto::ApiResp;
+use crate::entity::users;
+use crate::errors::AppError;
+use crate::models::user_token::{self, purpose};
+use crate::models::verification::{confirm_email_change, resend_verification_email};
+use crate::state::AppState;
+use crate::utils::validate_params;
+use actix_web::{Result, web};
+use chrono::Utc;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct VerifyQuery {
+    /// 邮件链接中的验证令牌
+    token: String,
+}
+
+/// 打开验证邮件中的链接完成邮箱验证，令牌只能使用一次
+///
+/// 注册与修改邮箱的验证邮件使用同一个链接，按令牌的用途区分
+#[utoipa::path(
+    tag = "user",
+    params(VerifyQuery),
+    responses((status = 200, body = ApiResp<bool>))
+)]
+#[get("/users/verify")]
+pub async fn verify_email(
+    query: web::Query<VerifyQuery>,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<bool>, AppError> {
+    if let Some(user_id) =
+        user_token::consume(&app_data, &query.token, purpose::CHANGE_EMAIL).await?
+    {
+        confirm_email_change(user_id, &app_data).await?;
+        return Ok(ApiResp::with_message(
+            true,
+            "邮箱修改成功，请使用新邮箱重新登录",
+        ));
+    }
+    let user_id = user_token::consume(&app_data, &query.token, purpose::VERIFY_EMAIL)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("验证链接无效或已过期".to_string()))?;
+    users::Entity::update_many()
+        .col_expr(users::Column::Status, users::STATUS_NORMAL.into())
+        .col_expr(users::Column::UpdateTime, Utc::now().into())
+        .filter(users::Column::Id.eq(user_id))
+        .filter(users::Column::Status.eq(users::STATUS_PENDING))
+        .exec(&app_data.db_pool)
+        .await?;
+    info!("用户 {user_id} 完成邮箱验证");
+    Ok(ApiResp::with_message(true, "邮箱验证成功"))
+}
+
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+struct ResendReq {
+    #[validate(email(message = "无效的邮箱地址"))]
+    email: String,
+}
+
+/// 重新发送验证邮件
+///
+/// 无论邮箱是否存在、是否已经验证都返回相同的结果，邮件在后台发送，响应时间同样不会泄露注册信息
+#[utoipa::path(
+    tag = "user",
+    request_body = ResendReq,
+    responses((status = 200, body = ApiResp<bool>))
+)]
+#[post("/users/verify/resend")]
+pub async fn resend_verification(
+    params: web::Json<ResendReq>,
+    app_data: web::Data<AppState>,
+) -> Result<ApiResp<bool>, AppError> {
+    validate_params(&*params)?;
+    let user = users::Entity::find_active()
+        .filter(users::Column::Email.eq(&params.email))
+        .filter(users::Column::Status.eq(users::STATUS_PENDING))
+        .one(&app_data.db_pool)
+        .await?;
+    // 发送过于频繁或邮件发送失败时只记录日志，同样返回成功
+    if let Some(user) = user {
+        let app_data = app_data.clone();
+        actix_web::rt::spawn(async move {
+            if let Err(e) = resend_verification_email(&user, &app_data).await {
+                warn!("用户 {} 的验证邮件未发送: {e}", user.id);
+            }
+        });
+    }
+    Ok(ApiResp::with_message(
+        true,
+        "如果该邮箱已注册且尚未验证，验证邮件已重新发送",
+    ))
+}
@@ -9,10 +9,15 @@ extern crate tracing;
 #[macro_use]
 extern crate validator;
 
+#[cfg(not(any(feature = "sqlite", feature = "postgres", feature = "mysql")))]
+compile_error!("至少需要启用 sqlite、postgres、mysql 中的一个数据库 feature");
+
 pub mod app_config;
+pub mod dto;
 pub mod entity;
 pub mod errors;
 pub mod handlers;
+pub mod migration;
 pub mod mw;
 pub mod models;
 pub mod state;
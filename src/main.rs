@@ -3,7 +3,7 @@ use rust_class_web::*;
 use actix_cors::Cors;
 use actix_web::{
     App, HttpServer,
-    http::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE},
+    http::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, HeaderName},
     middleware,
     web::Data,
 };
@@ -14,23 +14,49 @@ use tracing_actix_web::TracingLogger;
 async fn main() -> std::io::Result<()> {
     println!("服务启动中...");
     let app_config = app_config::Config::new();
+    // 数据库迁移命令: migrate [up [N] | down [N] | status]
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("migrate") {
+        let db = app_config
+            .db
+            .connect()
+            .await
+            .map_err(std::io::Error::other)?;
+        if let Err(e) = migration::run_cli(&db, &args[2..]).await {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
     let app_state = AppState::new(&app_config)
         .await
         .map_err(std::io::Error::other)?;
     let _ = app_config.logger.tracing_init().await;
+    // 允许跨域请求携带 API Key 请求头
+    let api_key_header =
+        HeaderName::try_from(app_config.api_key.header.as_str()).map_err(std::io::Error::other)?;
+    let audit = app_state.audit.clone();
 
     let mut http_server = HttpServer::new(move || {
         App::new()
+            // 鉴权中间件放在最内层，确保拿到的是规范化后的路径，且 CORS 预检请求不会被拦截
+            .wrap(middleware::from_fn(mw::auth))
             .wrap(middleware::NormalizePath::trim())
+            // 放在 TracingLogger 内层，与日志使用相同的请求 ID
+            .wrap(middleware::from_fn(mw::request_id))
             .wrap(middleware::Compat::new(TracingLogger::default()))
             .wrap(
                 Cors::default()
                     .allow_any_origin()
                     .allowed_methods(vec!["GET", "POST", "DELETE", "PUT", "PATCH"])
-                    .allowed_headers(vec![CONTENT_TYPE, AUTHORIZATION, ACCEPT])
+                    .allowed_headers(vec![
+                        CONTENT_TYPE,
+                        AUTHORIZATION,
+                        ACCEPT,
+                        api_key_header.clone(),
+                    ])
                     .supports_credentials(),
             )
-            .wrap(middleware::from_fn(mw::auth))
             .wrap(middleware::Compress::default())
             .wrap(middleware::DefaultHeaders::new().add(("X-Version", CARGO_PKG_VERSION)))
             .app_data(Data::new(app_state.clone()))
@@ -47,5 +73,8 @@ async fn main() -> std::io::Result<()> {
         println!("{CARGO_PKG_NAME} v{CARGO_PKG_VERSION} 服务启动成功！");
         println!("➜ Local:   http://127.0.0.1:{}", addr.1);
     }
-    http_server.run().await
+    http_server.run().await?;
+    // 服务已停止接收请求，写入队列中剩余的审计日志后再退出
+    audit.shutdown().await;
+    Ok(())
 }
@@ -0,0 +1,654 @@
+use sea_orm_migration::prelude::*;
+
+/// 基线迁移: 创建引入迁移之前 `create_db_table` 维护的全部数据表
+///
+/// 使用 `IF NOT EXISTS` 创建，旧版本创建的数据库可以直接接管，并补齐旧版本设备表缺少的字段
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// 自增主键
+fn id<T: IntoIden>(col: T) -> ColumnDef {
+    ColumnDef::new(col)
+        .big_integer()
+        .not_null()
+        .auto_increment()
+        .primary_key()
+        .to_owned()
+}
+
+/// 创建时间、更新时间等带默认值的时间字段
+fn timestamp<T: IntoIden>(col: T) -> ColumnDef {
+    ColumnDef::new(col)
+        .timestamp_with_time_zone()
+        .not_null()
+        .default(Expr::current_timestamp())
+        .to_owned()
+}
+
+/// 指向 `users.id` 的外键，删除用户时一并删除
+fn user_fk<T: IntoIden + 'static>(table: T) -> ForeignKeyCreateStatement {
+    ForeignKey::create()
+        .from_col(Alias::new("user_id"))
+        .from_tbl(table)
+        .to(Users::Table, Users::Id)
+        .on_delete(ForeignKeyAction::Cascade)
+        .to_owned()
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Users::Table)
+                    .if_not_exists()
+                    .col(id(Users::Id))
+                    .col(ColumnDef::new(Users::Name).string().not_null())
+                    .col(
+                        ColumnDef::new(Users::Email)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Users::PassWord).string().not_null())
+                    .col(timestamp(Users::CreateTime))
+                    .col(timestamp(Users::UpdateTime))
+                    .col(
+                        ColumnDef::new(Users::Status)
+                            .string_len(16)
+                            .not_null()
+                            .default("normal"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Devices::Table)
+                    .if_not_exists()
+                    .col(id(Devices::Id))
+                    .col(ColumnDef::new(Devices::UserId).big_integer().not_null())
+                    .col(ColumnDef::new(Devices::Token).text().not_null())
+                    .col(timestamp(Devices::CreateTime))
+                    .col(timestamp(Devices::UpdateTime))
+                    .col(ColumnDef::new(Devices::Name).string().null())
+                    .col(ColumnDef::new(Devices::RefreshFamily).string_len(64).null())
+                    .col(
+                        ColumnDef::new(Devices::RefreshTokenHash)
+                            .string_len(64)
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(Devices::RefreshExpireTime)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(ColumnDef::new(Devices::Ip).string().null())
+                    .col(ColumnDef::new(Devices::UserAgent).text().null())
+                    .col(ColumnDef::new(Devices::ClientId).string_len(64).null())
+                    .col(ColumnDef::new(Devices::Scope).text().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Devices::Table, Devices::UserId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        // 旧版本创建的设备表缺少的字段
+        let legacy_columns = [
+            (
+                Devices::RefreshFamily,
+                ColumnDef::new(Devices::RefreshFamily)
+                    .string_len(64)
+                    .null()
+                    .to_owned(),
+            ),
+            (
+                Devices::RefreshTokenHash,
+                ColumnDef::new(Devices::RefreshTokenHash)
+                    .string_len(64)
+                    .null()
+                    .to_owned(),
+            ),
+            (
+                Devices::RefreshExpireTime,
+                ColumnDef::new(Devices::RefreshExpireTime)
+                    .timestamp_with_time_zone()
+                    .null()
+                    .to_owned(),
+            ),
+            (
+                Devices::Ip,
+                ColumnDef::new(Devices::Ip).string().null().to_owned(),
+            ),
+            (
+                Devices::UserAgent,
+                ColumnDef::new(Devices::UserAgent).text().null().to_owned(),
+            ),
+            (
+                Devices::ClientId,
+                ColumnDef::new(Devices::ClientId)
+                    .string_len(64)
+                    .null()
+                    .to_owned(),
+            ),
+            (
+                Devices::Scope,
+                ColumnDef::new(Devices::Scope).text().null().to_owned(),
+            ),
+        ];
+        for (column, mut definition) in legacy_columns {
+            if !manager.has_column("devices", &column.to_string()).await? {
+                manager
+                    .alter_table(
+                        Table::alter()
+                            .table(Devices::Table)
+                            .add_column(&mut definition)
+                            .to_owned(),
+                    )
+                    .await?;
+            }
+        }
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_devices_refresh_family")
+                    .table(Devices::Table)
+                    .col(Devices::RefreshFamily)
+                    .unique()
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Roles::Table)
+                    .if_not_exists()
+                    .col(id(Roles::Id))
+                    .col(
+                        ColumnDef::new(Roles::Name)
+                            .string_len(32)
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Roles::Description)
+                            .string()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(timestamp(Roles::CreateTime))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Permissions::Table)
+                    .if_not_exists()
+                    .col(id(Permissions::Id))
+                    .col(
+                        ColumnDef::new(Permissions::Code)
+                            .string_len(64)
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Permissions::Description)
+                            .string()
+                            .not_null()
+                            .default(""),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(RolePermissions::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RolePermissions::RoleId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RolePermissions::PermissionId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(RolePermissions::RoleId)
+                            .col(RolePermissions::PermissionId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(RolePermissions::Table, RolePermissions::RoleId)
+                            .to(Roles::Table, Roles::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(RolePermissions::Table, RolePermissions::PermissionId)
+                            .to(Permissions::Table, Permissions::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserRoles::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(UserRoles::UserId).big_integer().not_null())
+                    .col(ColumnDef::new(UserRoles::RoleId).big_integer().not_null())
+                    .col(timestamp(UserRoles::CreateTime))
+                    .primary_key(
+                        Index::create()
+                            .col(UserRoles::UserId)
+                            .col(UserRoles::RoleId),
+                    )
+                    .foreign_key(&mut user_fk(UserRoles::Table))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(UserRoles::Table, UserRoles::RoleId)
+                            .to(Roles::Table, Roles::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserTokens::Table)
+                    .if_not_exists()
+                    .col(id(UserTokens::Id))
+                    .col(ColumnDef::new(UserTokens::UserId).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(UserTokens::Purpose)
+                            .string_len(32)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(UserTokens::TokenHash)
+                            .string_len(64)
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(UserTokens::ExpireTime)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(timestamp(UserTokens::CreateTime))
+                    .foreign_key(&mut user_fk(UserTokens::Table))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserTotp::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UserTotp::UserId)
+                            .big_integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(UserTotp::Secret).string().not_null())
+                    .col(
+                        ColumnDef::new(UserTotp::Enabled)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(UserTotp::LastUsedStep)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(timestamp(UserTotp::CreateTime))
+                    .col(
+                        ColumnDef::new(UserTotp::EnableTime)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .foreign_key(&mut user_fk(UserTotp::Table))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(RecoveryCodes::Table)
+                    .if_not_exists()
+                    .col(id(RecoveryCodes::Id))
+                    .col(
+                        ColumnDef::new(RecoveryCodes::UserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RecoveryCodes::CodeHash)
+                            .string_len(64)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RecoveryCodes::UsedTime)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(timestamp(RecoveryCodes::CreateTime))
+                    .foreign_key(&mut user_fk(RecoveryCodes::Table))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserIdentities::Table)
+                    .if_not_exists()
+                    .col(id(UserIdentities::Id))
+                    .col(
+                        ColumnDef::new(UserIdentities::UserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(UserIdentities::Issuer).string().not_null())
+                    .col(ColumnDef::new(UserIdentities::Subject).string().not_null())
+                    .col(ColumnDef::new(UserIdentities::Email).string().null())
+                    .col(timestamp(UserIdentities::CreateTime))
+                    .col(timestamp(UserIdentities::UpdateTime))
+                    .index(
+                        Index::create()
+                            .col(UserIdentities::Issuer)
+                            .col(UserIdentities::Subject)
+                            .unique(),
+                    )
+                    .foreign_key(&mut user_fk(UserIdentities::Table))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(OauthClients::Table)
+                    .if_not_exists()
+                    .col(id(OauthClients::Id))
+                    .col(
+                        ColumnDef::new(OauthClients::ClientId)
+                            .string_len(64)
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(OauthClients::ClientSecretHash)
+                            .string_len(64)
+                            .null(),
+                    )
+                    .col(ColumnDef::new(OauthClients::Name).string().not_null())
+                    .col(ColumnDef::new(OauthClients::RedirectUris).text().not_null())
+                    .col(ColumnDef::new(OauthClients::Scopes).text().not_null())
+                    .col(ColumnDef::new(OauthClients::GrantTypes).string().not_null())
+                    .col(timestamp(OauthClients::CreateTime))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(OauthConsents::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(OauthConsents::UserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(OauthConsents::ClientId)
+                            .string_len(64)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(OauthConsents::Scope).text().not_null())
+                    .col(timestamp(OauthConsents::CreateTime))
+                    .col(timestamp(OauthConsents::UpdateTime))
+                    .primary_key(
+                        Index::create()
+                            .col(OauthConsents::UserId)
+                            .col(OauthConsents::ClientId),
+                    )
+                    .foreign_key(&mut user_fk(OauthConsents::Table))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(OauthConsents::Table, OauthConsents::ClientId)
+                            .to(OauthClients::Table, OauthClients::ClientId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ApiKeys::Table)
+                    .if_not_exists()
+                    .col(id(ApiKeys::Id))
+                    .col(ColumnDef::new(ApiKeys::UserId).big_integer().not_null())
+                    .col(ColumnDef::new(ApiKeys::Name).string().not_null())
+                    .col(ColumnDef::new(ApiKeys::Prefix).string_len(16).not_null())
+                    .col(
+                        ColumnDef::new(ApiKeys::KeyHash)
+                            .string_len(64)
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(ApiKeys::Scope).text().null())
+                    .col(
+                        ColumnDef::new(ApiKeys::ExpireTime)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(ApiKeys::LastUsedTime)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(ColumnDef::new(ApiKeys::LastUsedIp).string().null())
+                    .col(timestamp(ApiKeys::CreateTime))
+                    .foreign_key(&mut user_fk(ApiKeys::Table))
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 先删除引用其它表的表
+        let tables = [
+            ApiKeys::Table.into_iden(),
+            OauthConsents::Table.into_iden(),
+            OauthClients::Table.into_iden(),
+            UserIdentities::Table.into_iden(),
+            RecoveryCodes::Table.into_iden(),
+            UserTotp::Table.into_iden(),
+            UserTokens::Table.into_iden(),
+            UserRoles::Table.into_iden(),
+            RolePermissions::Table.into_iden(),
+            Permissions::Table.into_iden(),
+            Roles::Table.into_iden(),
+            Devices::Table.into_iden(),
+            Users::Table.into_iden(),
+        ];
+        for table in tables {
+            manager
+                .drop_table(Table::drop().table(table).if_exists().to_owned())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+    Name,
+    Email,
+    PassWord,
+    CreateTime,
+    UpdateTime,
+    Status,
+}
+
+#[derive(DeriveIden)]
+enum Devices {
+    Table,
+    Id,
+    UserId,
+    Token,
+    CreateTime,
+    UpdateTime,
+    Name,
+    RefreshFamily,
+    RefreshTokenHash,
+    RefreshExpireTime,
+    Ip,
+    UserAgent,
+    ClientId,
+    Scope,
+}
+
+#[derive(DeriveIden)]
+enum Roles {
+    Table,
+    Id,
+    Name,
+    Description,
+    CreateTime,
+}
+
+#[derive(DeriveIden)]
+enum Permissions {
+    Table,
+    Id,
+    Code,
+    Description,
+}
+
+#[derive(DeriveIden)]
+enum RolePermissions {
+    Table,
+    RoleId,
+    PermissionId,
+}
+
+#[derive(DeriveIden)]
+enum UserRoles {
+    Table,
+    UserId,
+    RoleId,
+    CreateTime,
+}
+
+#[derive(DeriveIden)]
+enum UserTokens {
+    Table,
+    Id,
+    UserId,
+    Purpose,
+    TokenHash,
+    ExpireTime,
+    CreateTime,
+}
+
+#[derive(DeriveIden)]
+enum UserTotp {
+    Table,
+    UserId,
+    Secret,
+    Enabled,
+    LastUsedStep,
+    CreateTime,
+    EnableTime,
+}
+
+#[derive(DeriveIden)]
+enum RecoveryCodes {
+    Table,
+    Id,
+    UserId,
+    CodeHash,
+    UsedTime,
+    CreateTime,
+}
+
+#[derive(DeriveIden)]
+enum UserIdentities {
+    Table,
+    Id,
+    UserId,
+    Issuer,
+    Subject,
+    Email,
+    CreateTime,
+    UpdateTime,
+}
+
+#[derive(DeriveIden)]
+enum OauthClients {
+    Table,
+    Id,
+    ClientId,
+    ClientSecretHash,
+    Name,
+    RedirectUris,
+    Scopes,
+    GrantTypes,
+    CreateTime,
+}
+
+#[derive(DeriveIden)]
+enum OauthConsents {
+    Table,
+    UserId,
+    ClientId,
+    Scope,
+    CreateTime,
+    UpdateTime,
+}
+
+#[derive(DeriveIden)]
+enum ApiKeys {
+    Table,
+    Id,
+    UserId,
+    Name,
+    Prefix,
+    KeyHash,
+    Scope,
+    ExpireTime,
+    LastUsedTime,
+    LastUsedIp,
+    CreateTime,
+}
@@ -0,0 +1,75 @@
+use crate::models::rbac::seed;
+use sea_orm_migration::prelude::*;
+
+/// 内置角色: (名称, 描述)
+const ROLES: [(&str, &str); 3] = [
+    ("admin", "管理员"),
+    ("teacher", "教师"),
+    ("student", "学生"),
+];
+
+/// 内置权限: (编码, 描述)
+const PERMISSIONS: [(&str, &str); 7] = [
+    ("user:read", "查询用户"),
+    ("user:delete", "删除用户"),
+    ("user:logout", "强制用户退出登录"),
+    ("role:manage", "授予与撤销角色"),
+    ("login:lockout", "查看与清除登录锁定"),
+    ("user:reset_2fa", "重置用户的两步验证"),
+    ("oauth:client", "管理 OAuth2 客户端"),
+];
+
+/// 内置角色拥有的权限: (角色, 权限)
+const ROLE_PERMISSIONS: [(&str, &str); 8] = [
+    ("admin", "user:read"),
+    ("admin", "user:delete"),
+    ("admin", "user:logout"),
+    ("admin", "role:manage"),
+    ("admin", "login:lockout"),
+    ("admin", "user:reset_2fa"),
+    ("admin", "oauth:client"),
+    ("teacher", "user:read"),
+];
+
+/// 写入内置角色与权限
+///
+/// 内置角色或权限有变化时，新增一个迁移写入新增的数据，已存在的记录不会被修改
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        seed(
+            manager.get_connection(),
+            &ROLES,
+            &PERMISSIONS,
+            &ROLE_PERMISSIONS,
+        )
+        .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 角色与权限的关联随外键级联删除
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+        db.execute(
+            backend.build(
+                Query::delete()
+                    .from_table(Alias::new("permissions"))
+                    .and_where(
+                        Expr::col(Alias::new("code"))
+                            .is_in(PERMISSIONS.iter().map(|(code, _)| *code)),
+                    ),
+            ),
+        )
+        .await?;
+        db.execute(backend.build(
+            Query::delete().from_table(Alias::new("roles")).and_where(
+                Expr::col(Alias::new("name")).is_in(ROLES.iter().map(|(name, _)| *name)),
+            ),
+        ))
+        .await?;
+        Ok(())
+    }
+}
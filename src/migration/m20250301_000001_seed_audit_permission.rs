@@ -0,0 +1,32 @@
+use crate::models::rbac::seed;
+use sea_orm_migration::prelude::*;
+
+/// 新增内置权限 `audit:read` 并授予管理员
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        seed(
+            manager.get_connection(),
+            &[],
+            &[("audit:read", "查询审计日志")],
+            &[("admin", "audit:read")],
+        )
+        .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute(
+            manager.get_database_backend().build(
+                Query::delete()
+                    .from_table(Alias::new("permissions"))
+                    .and_where(Expr::col(Alias::new("code")).eq("audit:read")),
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+use sea_orm::DatabaseConnection;
+use sea_orm_migration::prelude::*;
+
+mod m20250101_000001_baseline;
+mod m20250101_000002_seed_rbac;
+mod m20250301_000001_seed_audit_permission;
+mod m20250401_000001_seed_user_update_permission;
+mod m20250501_000001_user_deleted_at;
+mod m20250601_000001_user_pending_email;
+
+/// 数据库迁移，按文件名中的时间顺序执行，已执行的版本记录在 `seaql_migrations` 表中
+///
+/// 新增迁移时在 `migration` 目录下新建 `mYYYYMMDD_HHMMSS_<名称>.rs` 并追加到列表末尾，已发布的迁移不能修改
+pub struct Migrator;
+
+#[async_trait::async_trait]
+impl MigratorTrait for Migrator {
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        vec![
+            Box::new(m20250101_000001_baseline::Migration),
+            Box::new(m20250101_000002_seed_rbac::Migration),
+            Box::new(m20250301_000001_seed_audit_permission::Migration),
+            Box::new(m20250401_000001_seed_user_update_permission::Migration),
+            Box::new(m20250501_000001_user_deleted_at::Migration),
+            Box::new(m20250601_000001_user_pending_email::Migration),
+        ]
+    }
+}
+
+const USAGE: &str = "用法: rust-class-web migrate <up [N] | down [N] | status>
+  up [N]    执行未执行的迁移，N 为最多执行的数量，默认全部
+  down [N]  回滚最近执行的 N 个迁移，默认 1 个
+  status    查看所有迁移的执行状态";
+
+/// 解析可选的数量参数
+fn parse_steps(arg: Option<&String>) -> Result<Option<u32>, DbErr> {
+    arg.map(|steps| {
+        steps
+            .parse()
+            .map_err(|_| DbErr::Custom(format!("无效的数量: {steps}\n{USAGE}")))
+    })
+    .transpose()
+}
+
+/// 执行 `migrate` 子命令
+pub async fn run_cli(db: &DatabaseConnection, args: &[String]) -> Result<(), DbErr> {
+    match args.first().map(String::as_str) {
+        Some("up") => {
+            Migrator::up(db, parse_steps(args.get(1))?).await?;
+            println!("迁移执行完成");
+        }
+        Some("down") => {
+            Migrator::down(db, Some(parse_steps(args.get(1))?.unwrap_or(1))).await?;
+            println!("迁移回滚完成");
+        }
+        Some("status") => {
+            for migration in Migrator::get_migration_with_status(db).await? {
+                println!("{:<40} {}", migration.name(), migration.status());
+            }
+        }
+        _ => return Err(DbErr::Custom(USAGE.to_string())),
+    }
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use crate::entity::{api_keys, users};
+use crate::errors::AppError;
+use crate::models::client_info::ClientInfo;
+use crate::models::rbac::permission_codes;
+use crate::models::secure_token;
+use crate::state::AppState;
+use chrono::{Duration, Utc};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::NotSet, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter,
+    Set,
+};
+
+/// API Key 的固定前缀，便于在日志与代码仓库中识别泄露的 Key
+pub const KEY_PREFIX: &str = "rcw_";
+/// 列表中展示的 Key 开头长度
+const DISPLAY_LEN: usize = 12;
+/// 最近使用时间的更新间隔 (秒)
+const LAST_USED_INTERVAL: i64 = 60;
+
+/// 创建成功后返回的 Key，明文只在此时返回一次
+#[derive(Debug)]
+pub struct CreatedKey {
+    pub key: String,
+    pub api_key: api_keys::Model,
+}
+
+/// 为用户创建 API Key
+///
+/// - `scopes` 为空时拥有用户的全部权限，否则只保留其中的权限
+/// - `expire_days` 为空时永不过期，配置了 `max_expire_days` 时必须指定且不能超过上限
+pub async fn create(
+    user_id: i64,
+    name: &str,
+    scopes: Option<&[String]>,
+    expire_days: Option<i64>,
+    app_data: &AppState,
+) -> Result<CreatedKey, AppError> {
+    let config = &app_data.config.api_key;
+    let count = api_keys::Entity::find()
+        .filter(api_keys::Column::UserId.eq(user_id))
+        .count(&app_data.db_pool)
+        .await?;
+    if count >= config.max_per_user {
+        return Err(AppError::Conflict(format!(
+            "最多只能创建 {} 个 API Key",
+            config.max_per_user
+        )));
+    }
+
+    match expire_days {
+        Some(days) if days <= 0 => {
+            return Err(AppError::BadRequest("有效期必须大于 0 天".to_string()));
+        }
+        Some(days) if config.max_expire_days > 0 && days > config.max_expire_days => {
+            return Err(AppError::BadRequest(format!(
+                "有效期不能超过 {} 天",
+                config.max_expire_days
+            )));
+        }
+        None if config.max_expire_days > 0 => {
+            return Err(AppError::BadRequest("必须指定有效期".to_string()));
+        }
+        _ => {}
+    }
+
+    let scope = match scopes {
+        Some(scopes) => {
+            if scopes.is_empty() {
+                return Err(AppError::BadRequest("授权范围不能为空".to_string()));
+            }
+            let known = permission_codes(&app_data.db_pool).await?;
+            if let Some(scope) = scopes.iter().find(|scope| !known.contains(*scope)) {
+                return Err(AppError::BadRequest(format!("未知的授权范围 {scope}")));
+            }
+            Some(scopes.join(" "))
+        }
+        None => None,
+    };
+
+    let key = format!("{KEY_PREFIX}{}", secure_token::generate());
+    let api_key = api_keys::ActiveModel {
+        id: NotSet,
+        user_id: Set(user_id),
+        name: Set(name.to_string()),
+        prefix: Set(key[..DISPLAY_LEN].to_string()),
+        key_hash: Set(secure_token::digest(&key)),
+        scope: Set(scope),
+        expire_time: Set(expire_days.map(|days| Utc::now() + Duration::days(days))),
+        last_used_time: Set(None),
+        last_used_ip: Set(None),
+        create_time: Set(Utc::now()),
+    }
+    .insert(&app_data.db_pool)
+    .await?;
+    info!("用户 {user_id} 创建了 API Key {}", api_key.id);
+    Ok(CreatedKey { key, api_key })
+}
+
+/// 校验请求携带的 API Key，返回 Key 与所属用户，并记录最近使用时间与 IP
+pub async fn authenticate(
+    key: &str,
+    client: &ClientInfo,
+    app_data: &AppState,
+) -> Result<(api_keys::Model, users::Model), AppError> {
+    let invalid = || AppError::Unauthorized("API Key 无效".to_string());
+    if !key.starts_with(KEY_PREFIX) {
+        return Err(invalid());
+    }
+    let api_key = api_keys::Entity::find()
+        .filter(api_keys::Column::KeyHash.eq(secure_token::digest(key)))
+        .one(&app_data.db_pool)
+        .await?
+        .ok_or_else(invalid)?;
+    if api_key
+        .expire_time
+        .is_some_and(|expire_time| expire_time <= Utc::now())
+    {
+        return Err(AppError::Unauthorized("API Key 已过期".to_string()));
+    }
+    let user = users::Entity::find_active_by_id(api_key.user_id)
+        .one(&app_data.db_pool)
+        .await?
+        .ok_or_else(invalid)?;
+
+    // 同一个 Key 一分钟内只写一次，IP 变化时立即更新
+    let stale = api_key
+        .last_used_time
+        .is_none_or(|last_used| Utc::now() - last_used > Duration::seconds(LAST_USED_INTERVAL));
+    if stale || api_key.last_used_ip != client.ip {
+        api_keys::Entity::update_many()
+            .col_expr(api_keys::Column::LastUsedTime, Some(Utc::now()).into())
+            .col_expr(api_keys::Column::LastUsedIp, client.ip.clone().into())
+            .filter(api_keys::Column::Id.eq(api_key.id))
+            .exec(&app_data.db_pool)
+            .await?;
+    }
+    Ok((api_key, user))
+}
@@ -0,0 +1,341 @@
+use crate::app_config::audit::Audit;
+use crate::errors::AppError;
+use crate::models::client_info::ClientInfo;
+use crate::models::document_store::{
+    self, DocumentStore, Filter, FindOptions, Index, SortOrder, StoreStatus, serde_date,
+};
+use crate::utils::serde_timestamp;
+use chrono::{DateTime, Utc};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use utoipa::ToSchema;
+
+/// 审计事件类型
+pub mod action {
+    /// 登录，包括密码登录、两步验证与 OIDC 登录
+    pub const LOGIN: &str = "login";
+    /// 登出
+    pub const LOGOUT: &str = "logout";
+    /// 用户撤销自己的某个会话或其他所有会话
+    pub const SESSION_REVOKE: &str = "session.revoke";
+    /// 注册用户
+    pub const USER_CREATE: &str = "user.create";
+    /// 修改用户资料
+    pub const USER_UPDATE: &str = "user.update";
+    /// 删除用户
+    pub const USER_DELETE: &str = "user.delete";
+    /// 恢复已删除的用户
+    pub const USER_RESTORE: &str = "user.restore";
+    /// 授予角色
+    pub const ROLE_GRANT: &str = "role.grant";
+    /// 撤销角色
+    pub const ROLE_REVOKE: &str = "role.revoke";
+}
+
+/// 操作结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Success,
+    Failure,
+}
+
+/// 一条审计事件
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEvent {
+    pub action: String,
+    pub outcome: Outcome,
+    /// 操作者的用户 ID，登录失败等无法确定用户时为空
+    pub actor_id: Option<i64>,
+    /// 操作者的邮箱，登录失败时为尝试登录的邮箱
+    pub actor_email: Option<String>,
+    /// 被操作的用户 ID
+    pub target_id: Option<i64>,
+    /// 补充说明，例如失败原因、授予的角色
+    pub detail: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+}
+
+impl AuditEvent {
+    pub fn new(action: &str, outcome: Outcome, client: &ClientInfo) -> Self {
+        Self {
+            action: action.to_string(),
+            outcome,
+            actor_id: None,
+            actor_email: None,
+            target_id: None,
+            detail: None,
+            ip: client.ip.clone(),
+            user_agent: client.user_agent.clone(),
+            request_id: client.request_id.clone(),
+        }
+    }
+
+    pub fn actor(mut self, id: i64, email: &str) -> Self {
+        self.actor_id = Some(id);
+        self.actor_email = Some(email.to_string());
+        self
+    }
+
+    pub fn actor_email(mut self, email: &str) -> Self {
+        self.actor_email = Some(email.to_string());
+        self
+    }
+
+    pub fn target(mut self, id: i64) -> Self {
+        self.target_id = Some(id);
+        self
+    }
+
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+/// 查询接口返回的审计记录
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditRecord {
+    #[serde(flatten)]
+    pub event: AuditEvent,
+    #[serde(with = "serde_timestamp")]
+    #[schema(value_type = String, example = "2025-01-01 00:00:00")]
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 审计记录查询条件
+#[derive(Debug, Default)]
+pub struct AuditFilter {
+    pub actor_id: Option<i64>,
+    pub action: Option<String>,
+    /// 起始时间 (包含)
+    pub from: Option<DateTime<Utc>>,
+    /// 结束时间 (不包含)
+    pub to: Option<DateTime<Utc>>,
+    pub offset: u64,
+    pub limit: u64,
+}
+
+/// 文档存储中保存的文档
+#[derive(Debug, Serialize, Deserialize)]
+struct AuditDocument {
+    #[serde(flatten)]
+    event: AuditEvent,
+    #[serde(with = "serde_date")]
+    timestamp: DateTime<Utc>,
+}
+
+/// 审计日志，事件先写入内存队列，由后台任务批量写入文档存储，不阻塞请求
+#[derive(Debug)]
+pub struct AuditLog {
+    config: Audit,
+    store: Option<Arc<dyn DocumentStore>>,
+    sender: Option<mpsc::Sender<AuditDocument>>,
+    /// 后台写入任务与通知其退出的信号，关闭时取出
+    writer: Mutex<Option<(oneshot::Sender<()>, JoinHandle<()>)>>,
+}
+
+impl AuditLog {
+    /// 创建审计日志，启用文档存储时启动后台写入任务
+    pub fn new(config: &Audit, store: Option<Arc<dyn DocumentStore>>) -> Self {
+        let (sender, writer) = store
+            .clone()
+            .map(|store| {
+                let (sender, receiver) = mpsc::channel(config.buffer_size.max(1));
+                let (stop, stopped) = oneshot::channel();
+                let task = tokio::spawn(write_loop(receiver, stopped, config.clone(), store));
+                (sender, (stop, task))
+            })
+            .unzip();
+        Self {
+            config: config.clone(),
+            store,
+            sender,
+            writer: Mutex::new(writer),
+        }
+    }
+
+    /// 服务退出前调用，停止接收新事件并等待后台任务写入队列中剩余的事件
+    pub async fn shutdown(&self) {
+        let writer = self.writer.lock().unwrap().take();
+        if let Some((stop, task)) = writer {
+            let _ = stop.send(());
+            if let Err(e) = task.await {
+                warn!("审计日志写入任务异常退出: {e}");
+            }
+        }
+    }
+
+    /// 记录审计事件，队列已满或未启用文档存储时丢弃
+    pub fn record(&self, event: AuditEvent) {
+        if let Some(sender) = &self.sender {
+            let document = AuditDocument {
+                event,
+                timestamp: Utc::now(),
+            };
+            if let Err(e) = sender.try_send(document) {
+                warn!("审计日志队列已满，丢弃事件: {:?}", e.into_inner().event);
+            }
+        }
+    }
+
+    /// 按条件查询审计记录，按时间倒序
+    pub async fn query(&self, filter: &AuditFilter) -> Result<Vec<AuditRecord>, AppError> {
+        let store = self
+            .store
+            .as_ref()
+            .ok_or_else(|| AppError::ServiceUnavailable("文档存储未启用".to_string()))?;
+        let mut query = Filter::new();
+        if let Some(actor_id) = filter.actor_id {
+            query = query.eq("actorId", actor_id);
+        }
+        if let Some(action) = &filter.action {
+            query = query.eq("action", action.as_str());
+        }
+        if let Some(from) = filter.from {
+            query = query.gte("timestamp", document_store::date(from));
+        }
+        if let Some(to) = filter.to {
+            query = query.lt("timestamp", document_store::date(to));
+        }
+        let options = FindOptions {
+            sort: vec![("timestamp".to_string(), SortOrder::Desc)],
+            skip: filter.offset,
+            limit: Some(filter.limit),
+        };
+        store
+            .find(&self.config.collection, &query, &options)
+            .await?
+            .into_iter()
+            .map(|document| {
+                let document: AuditDocument = serde_json::from_value(document.into())
+                    .map_err(|e| AppError::InternalError(format!("审计日志格式错误: {e}")))?;
+                Ok(AuditRecord {
+                    event: document.event,
+                    timestamp: document.timestamp,
+                })
+            })
+            .collect()
+    }
+}
+
+/// 后台写入任务，攒够一批或到达写入间隔时批量写入，文档存储不可用时保留未写入的事件稍后重试
+async fn write_loop(
+    mut receiver: mpsc::Receiver<AuditDocument>,
+    mut stopped: oneshot::Receiver<()>,
+    config: Audit,
+    store: Arc<dyn DocumentStore>,
+) {
+    let batch_size = config.batch_size.max(1);
+    let mut ticker = tokio::time::interval(Duration::from_millis(config.flush_interval.max(1)));
+    let mut pending = Vec::new();
+    let mut indexes_ready = false;
+    let mut stopping = false;
+    let mut closed = false;
+    while !closed {
+        tokio::select! {
+            // 不再接收新事件，队列中已有的事件仍会依次取出，取完后按关闭处理
+            _ = &mut stopped, if !stopping => {
+                stopping = true;
+                receiver.close();
+                continue;
+            }
+            document = receiver.recv() => match document {
+                Some(document) => {
+                    pending.push(document);
+                    if pending.len() < batch_size {
+                        continue;
+                    }
+                }
+                None if pending.is_empty() => return,
+                // 服务退出前最后写入一次
+                None => closed = true,
+            },
+            _ = ticker.tick() => {
+                if pending.is_empty() {
+                    continue;
+                }
+            }
+        }
+
+        if store.status() != StoreStatus::Up {
+            drop_overflow(&mut pending, config.buffer_size);
+            continue;
+        }
+        if !indexes_ready {
+            create_indexes(store.as_ref(), &config).await;
+            indexes_ready = true;
+        }
+        while !pending.is_empty() {
+            let batch_len = pending.len().min(batch_size);
+            let documents = pending[..batch_len]
+                .iter()
+                .filter_map(|document| match serde_json::to_value(document) {
+                    Ok(serde_json::Value::Object(document)) => Some(document),
+                    _ => None,
+                })
+                .collect();
+            match store.insert_many(&config.collection, documents).await {
+                Ok(()) => {
+                    pending.drain(..batch_len);
+                }
+                Err(e) => {
+                    warn!("审计日志写入失败，稍后重试: {e}");
+                    drop_overflow(&mut pending, config.buffer_size);
+                    break;
+                }
+            }
+        }
+    }
+    if !pending.is_empty() {
+        warn!(
+            "服务退出时文档存储不可用，{} 条审计日志未写入",
+            pending.len()
+        );
+    }
+}
+
+/// 等待写入的事件超过上限时丢弃最早的事件
+fn drop_overflow(pending: &mut Vec<AuditDocument>, buffer_size: usize) {
+    if pending.len() > buffer_size {
+        let dropped = pending.len() - buffer_size;
+        pending.drain(..dropped);
+        warn!("文档存储不可用，丢弃 {dropped} 条审计日志");
+    }
+}
+
+/// 创建 TTL 索引与查询用的索引，修改保留天数后需要手动删除旧的 TTL 索引
+async fn create_indexes(store: &dyn DocumentStore, config: &Audit) {
+    let indexes = [
+        Index {
+            keys: vec![("timestamp".to_string(), SortOrder::Asc)],
+            expire_after: (config.retention_days > 0)
+                .then(|| Duration::from_secs(config.retention_days * 24 * 3600)),
+        },
+        Index {
+            keys: vec![
+                ("actorId".to_string(), SortOrder::Asc),
+                ("timestamp".to_string(), SortOrder::Desc),
+            ],
+            expire_after: None,
+        },
+        Index {
+            keys: vec![
+                ("action".to_string(), SortOrder::Asc),
+                ("timestamp".to_string(), SortOrder::Desc),
+            ],
+            expire_after: None,
+        },
+    ];
+    for index in &indexes {
+        if let Err(e) = store.create_index(&config.collection, index).await {
+            warn!("审计日志索引创建失败: {e}");
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use crate::errors::AppError;
+use actix_web::{FromRequest, HttpMessage, HttpRequest, dev::Payload};
+use futures::future::{Ready, ready};
+
+/// 当前登录的用户信息
+///
+/// 由 `mw::auth` 中间件校验 token 后写入请求扩展，处理函数通过提取器获取调用者身份
+#[derive(Debug, Clone)]
+pub struct AuthUser {
+    /// 用户 ID
+    pub id: i64,
+    /// 用户邮箱
+    pub email: String,
+    /// 用户状态，见 `users::STATUS_*`
+    pub status: String,
+    /// 本次请求所使用的设备 ID，使用 API Key 访问时为 0
+    pub device_id: i64,
+    /// 本次请求所使用的 token 或 API Key
+    pub token: String,
+    /// 通过 OAuth2 授权访问时的客户端 ID
+    pub client_id: Option<String>,
+    /// OAuth2 授权或 API Key 的授权范围，空格分隔，权限检查只认可范围内的权限
+    pub scope: Option<String>,
+    /// 使用 API Key 访问时的 Key ID
+    pub api_key_id: Option<i64>,
+}
+
+impl AuthUser {
+    /// 读取中间件写入的调用者身份，不区分本站登录与 OAuth2 授权
+    pub(crate) fn from_extensions(req: &HttpRequest) -> Result<Self, AppError> {
+        req.extensions()
+            .get::<AuthUser>()
+            .cloned()
+            .ok_or_else(|| AppError::Unauthorized("未登录或登录已失效".to_string()))
+    }
+}
+
+/// 直接提取 `AuthUser` 的接口只接受本站登录签发的 token
+///
+/// 第三方应用的 token 与 API Key 只能访问通过 `Permissions` 检查授权范围的接口，不能修改密码、管理会话等
+impl FromRequest for AuthUser {
+    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(AuthUser::from_extensions(req).and_then(|user| {
+            if user.client_id.is_some() {
+                Err(AppError::Forbidden(
+                    "第三方应用的令牌无权访问该接口".to_string(),
+                ))
+            } else if user.api_key_id.is_some() {
+                Err(AppError::Forbidden("API Key 无权访问该接口".to_string()))
+            } else {
+                Ok(user)
+            }
+        }))
+    }
+}
@@ -0,0 +1,111 @@
+use crate::state::AppState;
+use actix_web::{
+    FromRequest, HttpMessage, HttpRequest, dev::Payload, http::header::USER_AGENT, web,
+};
+use futures::future::{Ready, ready};
+use std::convert::Infallible;
+use std::net::IpAddr;
+use tracing_actix_web::RequestId;
+
+/// 发起请求的客户端信息
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    /// 客户端 IP，直连地址是受信任的反向代理时取 `X-Forwarded-For` 请求头
+    pub ip: Option<String>,
+    /// `User-Agent` 请求头
+    pub user_agent: Option<String>,
+    /// 请求 ID，由 `TracingLogger` 中间件生成
+    pub request_id: Option<String>,
+}
+
+impl ClientInfo {
+    pub fn new(req: &HttpRequest) -> Self {
+        Self {
+            ip: client_ip(req).map(|ip| ip.to_string()),
+            user_agent: req
+                .headers()
+                .get(USER_AGENT)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string),
+            request_id: req
+                .extensions()
+                .get::<RequestId>()
+                .map(RequestId::to_string),
+        }
+    }
+
+    /// 根据 User-Agent 生成可读的设备名称，例如 "Chrome on Windows"
+    pub fn device_name(&self) -> Option<String> {
+        let ua = self.user_agent.as_deref()?;
+        let browser = [
+            ("Edg/", "Edge"),
+            ("OPR/", "Opera"),
+            ("Firefox/", "Firefox"),
+            ("Chrome/", "Chrome"),
+            ("Safari/", "Safari"),
+            ("curl/", "curl"),
+            ("PostmanRuntime/", "Postman"),
+            ("okhttp/", "OkHttp"),
+        ]
+        .into_iter()
+        .find(|(pattern, _)| ua.contains(pattern))
+        .map(|(_, name)| name);
+        let os = [
+            ("Windows", "Windows"),
+            ("iPhone", "iOS"),
+            ("iPad", "iPadOS"),
+            ("Android", "Android"),
+            ("Mac OS X", "macOS"),
+            ("CrOS", "ChromeOS"),
+            ("Linux", "Linux"),
+        ]
+        .into_iter()
+        .find(|(pattern, _)| ua.contains(pattern))
+        .map(|(_, name)| name);
+        match (browser, os) {
+            (Some(browser), Some(os)) => Some(format!("{browser} on {os}")),
+            (Some(name), None) | (None, Some(name)) => Some(name.to_string()),
+            (None, None) => Some(ua.chars().take(64).collect()),
+        }
+    }
+}
+
+/// 取客户端 IP，请求头可以被客户端任意伪造，只有直连地址属于 `server.trusted_proxies` 时才读取
+///
+/// 从右向左跳过 `X-Forwarded-For` 中受信任的代理，第一个不受信任的地址即为客户端
+fn client_ip(req: &HttpRequest) -> Option<IpAddr> {
+    let peer = req.peer_addr()?.ip();
+    let Some(app_data) = req.app_data::<web::Data<AppState>>() else {
+        return Some(peer);
+    };
+    let server = &app_data.config.server;
+    if !server.is_trusted_proxy(peer) {
+        return Some(peer);
+    }
+    let mut client = peer;
+    let forwarded = req.headers().get_all("x-forwarded-for");
+    let hops: Vec<_> = forwarded
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .collect();
+    for hop in hops.into_iter().rev() {
+        // 无法解析的地址视为不可信，停止向前查找
+        let Ok(ip) = hop.trim().parse::<IpAddr>() else {
+            break;
+        };
+        client = ip;
+        if !server.is_trusted_proxy(ip) {
+            break;
+        }
+    }
+    Some(client)
+}
+
+impl FromRequest for ClientInfo {
+    type Error = Infallible;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Ok(ClientInfo::new(req)))
+    }
+}
@@ -0,0 +1,205 @@
+use super::{
+    Condition, Document, DocumentStore, Filter, FindOptions, Index, Op, SortOrder, StoreStatus,
+    date_millis,
+};
+use crate::errors::AppError;
+use chrono::Utc;
+use futures::future::{self, BoxFuture};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::time::Duration;
+
+#[derive(Debug, Default)]
+struct Collection {
+    documents: Vec<Document>,
+    /// TTL 索引的字段与过期时长
+    ttl: Vec<(String, Duration)>,
+}
+
+impl Collection {
+    /// 删除已过期的文档，每次读写集合前调用
+    fn expire(&mut self) {
+        if self.ttl.is_empty() {
+            return;
+        }
+        let now = Utc::now().timestamp_millis();
+        let ttl = &self.ttl;
+        self.documents.retain(|document| {
+            ttl.iter().all(|(field, expire_after)| {
+                match document.get(field).and_then(date_millis) {
+                    Some(millis) => millis + expire_after.as_millis() as i64 > now,
+                    None => true,
+                }
+            })
+        });
+    }
+}
+
+/// 保存在进程内存中的文档存储，重启后数据丢失，用于测试与单机部署
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    collections: Mutex<HashMap<String, Collection>>,
+    next_id: AtomicU64,
+}
+
+impl MemoryStore {
+    /// 对指定集合执行操作
+    fn with_collection<T>(&self, name: &str, f: impl FnOnce(&mut Collection) -> T) -> T {
+        let mut collections = self.collections.lock().unwrap();
+        let collection = collections.entry(name.to_string()).or_default();
+        collection.expire();
+        f(collection)
+    }
+}
+
+impl DocumentStore for MemoryStore {
+    fn status(&self) -> StoreStatus {
+        StoreStatus::Up
+    }
+
+    fn insert_many<'a>(
+        &'a self,
+        collection: &'a str,
+        documents: Vec<Document>,
+    ) -> BoxFuture<'a, Result<(), AppError>> {
+        self.with_collection(collection, |collection| {
+            for mut document in documents {
+                if !document.contains_key("_id") {
+                    let id = self.next_id.fetch_add(1, AtomicOrdering::Relaxed);
+                    document.insert("_id".to_string(), format!("{id:024x}").into());
+                }
+                collection.documents.push(document);
+            }
+        });
+        Box::pin(future::ready(Ok(())))
+    }
+
+    fn find<'a>(
+        &'a self,
+        collection: &'a str,
+        filter: &'a Filter,
+        options: &'a FindOptions,
+    ) -> BoxFuture<'a, Result<Vec<Document>, AppError>> {
+        let mut documents: Vec<Document> = self.with_collection(collection, |collection| {
+            collection
+                .documents
+                .iter()
+                .filter(|document| matches(document, filter))
+                .cloned()
+                .collect()
+        });
+        documents.sort_by(|a, b| {
+            options
+                .sort
+                .iter()
+                .map(|(field, order)| {
+                    let ordering = compare(
+                        a.get(field).unwrap_or(&Value::Null),
+                        b.get(field).unwrap_or(&Value::Null),
+                    )
+                    .unwrap_or(Ordering::Equal);
+                    match order {
+                        SortOrder::Asc => ordering,
+                        SortOrder::Desc => ordering.reverse(),
+                    }
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+        let documents = documents
+            .into_iter()
+            .skip(usize::try_from(options.skip).unwrap_or(usize::MAX))
+            .take(options.limit.map_or(usize::MAX, |limit| {
+                usize::try_from(limit).unwrap_or(usize::MAX)
+            }))
+            .collect();
+        Box::pin(future::ready(Ok(documents)))
+    }
+
+    fn update_many<'a>(
+        &'a self,
+        collection: &'a str,
+        filter: &'a Filter,
+        set: Document,
+    ) -> BoxFuture<'a, Result<u64, AppError>> {
+        let updated = self.with_collection(collection, |collection| {
+            let mut updated = 0;
+            for document in collection.documents.iter_mut() {
+                if matches(document, filter) {
+                    document.extend(set.clone());
+                    updated += 1;
+                }
+            }
+            updated
+        });
+        Box::pin(future::ready(Ok(updated)))
+    }
+
+    fn delete_many<'a>(
+        &'a self,
+        collection: &'a str,
+        filter: &'a Filter,
+    ) -> BoxFuture<'a, Result<u64, AppError>> {
+        let deleted = self.with_collection(collection, |collection| {
+            let before = collection.documents.len();
+            collection
+                .documents
+                .retain(|document| !matches(document, filter));
+            (before - collection.documents.len()) as u64
+        });
+        Box::pin(future::ready(Ok(deleted)))
+    }
+
+    fn create_index<'a>(
+        &'a self,
+        collection: &'a str,
+        index: &'a Index,
+    ) -> BoxFuture<'a, Result<(), AppError>> {
+        if let (Some(expire_after), Some((field, _))) = (index.expire_after, index.keys.first()) {
+            self.with_collection(collection, |collection| {
+                if !collection.ttl.iter().any(|(f, _)| f == field) {
+                    collection.ttl.push((field.clone(), expire_after));
+                    collection.expire();
+                }
+            });
+        }
+        Box::pin(future::ready(Ok(())))
+    }
+}
+
+fn matches(document: &Document, filter: &Filter) -> bool {
+    filter
+        .conditions
+        .iter()
+        .all(|Condition { field, op, value }| {
+            // 与 MongoDB 一致，缺少的字段视为 null，不同类型的值之间只能判断相等
+            let ordering = compare(document.get(field).unwrap_or(&Value::Null), value);
+            match op {
+                Op::Eq => ordering == Some(Ordering::Equal),
+                Op::Gt => ordering == Some(Ordering::Greater),
+                Op::Gte => ordering.is_some_and(Ordering::is_ge),
+                Op::Lt => ordering == Some(Ordering::Less),
+                Op::Lte => ordering.is_some_and(Ordering::is_le),
+            }
+        })
+}
+
+/// 比较两个值，类型不同时无法比较
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    if let (Some(a), Some(b)) = (date_millis(a), date_millis(b)) {
+        return Some(a.cmp(&b));
+    }
+    match (a, b) {
+        (Value::Null, Value::Null) => Some(Ordering::Equal),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (Value::Number(a), Value::Number(b)) => match (a.as_i64(), b.as_i64()) {
+            (Some(a), Some(b)) => Some(a.cmp(&b)),
+            _ => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        },
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => (a == b).then_some(Ordering::Equal),
+    }
+}
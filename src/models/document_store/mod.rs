@@ -0,0 +1,218 @@
+//! 文档存储
+//!
+//! 审计日志等不适合放在关系数据库中的数据统一通过 [`DocumentStore`] 读写，生产环境使用 MongoDB，
+//! 测试与单机部署可以使用内存实现。文档使用 JSON 表示，时间字段使用 [`date`] 生成的 `{"$date": 毫秒时间戳}`，
+//! 以便 MongoDB 保存为日期类型并支持 TTL 索引。字段名只支持顶层字段。
+
+pub mod memory;
+#[cfg(feature = "mongodb")]
+pub mod mongo;
+
+use crate::app_config::mongodb::Mongodb;
+use crate::app_config::store::Store;
+use crate::errors::AppError;
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use serde_json::Value;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 一条文档
+pub type Document = serde_json::Map<String, Value>;
+
+/// 文档存储的连接状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StoreStatus {
+    /// 未启用
+    Disabled,
+    /// 正在进行首次连接
+    Connecting,
+    /// 最近一次健康检查成功
+    Up,
+    /// 最近一次健康检查失败，后台会持续重试
+    Down,
+}
+
+/// 比较方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+/// 单个字段的查询条件
+#[derive(Debug, Clone)]
+pub struct Condition {
+    pub field: String,
+    pub op: Op,
+    pub value: Value,
+}
+
+/// 查询条件，所有条件同时满足时匹配，没有条件时匹配全部文档
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    pub conditions: Vec<Condition>,
+}
+
+impl Filter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn eq(self, field: &str, value: impl Into<Value>) -> Self {
+        self.push(field, Op::Eq, value.into())
+    }
+
+    pub fn gt(self, field: &str, value: impl Into<Value>) -> Self {
+        self.push(field, Op::Gt, value.into())
+    }
+
+    pub fn gte(self, field: &str, value: impl Into<Value>) -> Self {
+        self.push(field, Op::Gte, value.into())
+    }
+
+    pub fn lt(self, field: &str, value: impl Into<Value>) -> Self {
+        self.push(field, Op::Lt, value.into())
+    }
+
+    pub fn lte(self, field: &str, value: impl Into<Value>) -> Self {
+        self.push(field, Op::Lte, value.into())
+    }
+
+    fn push(mut self, field: &str, op: Op, value: Value) -> Self {
+        self.conditions.push(Condition {
+            field: field.to_string(),
+            op,
+            value,
+        });
+        self
+    }
+}
+
+/// 排序方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// 查询选项
+#[derive(Debug, Clone, Default)]
+pub struct FindOptions {
+    /// 按顺序依次比较的排序字段
+    pub sort: Vec<(String, SortOrder)>,
+    pub skip: u64,
+    /// 为空时不限制条数
+    pub limit: Option<u64>,
+}
+
+/// 索引定义
+#[derive(Debug, Clone)]
+pub struct Index {
+    pub keys: Vec<(String, SortOrder)>,
+    /// 设置后为 TTL 索引，第一个字段的时间早于当前时间减去该时长的文档会被自动删除，只支持单字段索引
+    pub expire_after: Option<Duration>,
+}
+
+/// 文档存储接口，不同的存储方式实现该 trait
+pub trait DocumentStore: Send + Sync + fmt::Debug {
+    /// 当前的连接状态
+    fn status(&self) -> StoreStatus;
+
+    /// 批量写入文档
+    fn insert_many<'a>(
+        &'a self,
+        collection: &'a str,
+        documents: Vec<Document>,
+    ) -> BoxFuture<'a, Result<(), AppError>>;
+
+    /// 查询文档
+    fn find<'a>(
+        &'a self,
+        collection: &'a str,
+        filter: &'a Filter,
+        options: &'a FindOptions,
+    ) -> BoxFuture<'a, Result<Vec<Document>, AppError>>;
+
+    /// 更新匹配的文档，`set` 中的字段覆盖原有字段，返回更新的文档数
+    fn update_many<'a>(
+        &'a self,
+        collection: &'a str,
+        filter: &'a Filter,
+        set: Document,
+    ) -> BoxFuture<'a, Result<u64, AppError>>;
+
+    /// 删除匹配的文档，返回删除的文档数
+    fn delete_many<'a>(
+        &'a self,
+        collection: &'a str,
+        filter: &'a Filter,
+    ) -> BoxFuture<'a, Result<u64, AppError>>;
+
+    /// 创建索引，索引已存在时不做任何事
+    fn create_index<'a>(
+        &'a self,
+        collection: &'a str,
+        index: &'a Index,
+    ) -> BoxFuture<'a, Result<(), AppError>>;
+}
+
+/// 根据配置创建文档存储，未启用时返回 `None`
+pub fn from_config(config: &Store, mongodb: &Mongodb) -> Result<Option<Arc<dyn DocumentStore>>> {
+    let backend = config.backend.to_lowercase();
+    let store: Arc<dyn DocumentStore> = match backend.as_str() {
+        "none" => return Ok(None),
+        "memory" => Arc::new(memory::MemoryStore::default()),
+        #[cfg(feature = "mongodb")]
+        "mongodb" => mongo::MongoStore::start(mongodb),
+        #[cfg(not(feature = "mongodb"))]
+        "mongodb" => {
+            let _ = mongodb;
+            bail!("使用 MongoDB 文档存储需要启用 mongodb feature")
+        }
+        _ => bail!("不支持的文档存储: {}", config.backend),
+    };
+    Ok(Some(store))
+}
+
+/// 生成时间字段的值
+pub fn date(value: DateTime<Utc>) -> Value {
+    json!({ "$date": value.timestamp_millis() })
+}
+
+/// 读取 [`date`] 生成的时间字段，返回毫秒时间戳
+pub fn date_millis(value: &Value) -> Option<i64> {
+    match value {
+        Value::Object(object) if object.len() == 1 => object.get("$date")?.as_i64(),
+        _ => None,
+    }
+}
+
+/// 以 [`date`] 的格式序列化 `DateTime<Utc>`
+pub mod serde_date {
+    use chrono::{DateTime, Utc};
+    use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S>(date: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        super::date(*date).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        super::date_millis(&value)
+            .and_then(DateTime::from_timestamp_millis)
+            .ok_or_else(|| serde::de::Error::custom("无效的时间字段"))
+    }
+}
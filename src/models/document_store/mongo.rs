@@ -0,0 +1,300 @@
+use super::{
+    Document, DocumentStore, Filter, FindOptions, Index, Op, SortOrder, StoreStatus, date,
+};
+use crate::app_config::mongodb::Mongodb;
+use crate::errors::AppError;
+use futures::TryStreamExt;
+use futures::future::BoxFuture;
+use mongodb::{
+    Client, Database, IndexModel,
+    bson::{self, Bson, doc},
+    options::IndexOptions,
+};
+use serde_json::Value;
+use std::sync::{Arc, RwLock};
+
+#[derive(Debug)]
+struct Inner {
+    client: Option<Client>,
+    status: StoreStatus,
+}
+
+/// 基于 MongoDB 的文档存储
+///
+/// 启动时不等待连接，由后台任务负责连接与定期健康检查，不可用时读写返回 `ServiceUnavailable`，其余接口不受影响
+#[derive(Debug)]
+pub struct MongoStore {
+    config: Mongodb,
+    inner: RwLock<Inner>,
+}
+
+impl MongoStore {
+    /// 创建文档存储并启动后台连接与健康检查任务
+    pub fn start(config: &Mongodb) -> Arc<Self> {
+        let store = Arc::new(MongoStore {
+            config: config.clone(),
+            inner: RwLock::new(Inner {
+                client: None,
+                status: StoreStatus::Connecting,
+            }),
+        });
+        let monitor = store.clone();
+        tokio::spawn(async move { monitor.monitor().await });
+        store
+    }
+
+    /// 获取可用的数据库，不可用时返回 `ServiceUnavailable`
+    fn database(&self) -> Result<Database, AppError> {
+        let inner = self.inner.read().unwrap();
+        match (&inner.client, inner.status) {
+            (Some(client), StoreStatus::Up) => Ok(client.database(&self.config.database)),
+            _ => Err(AppError::ServiceUnavailable(
+                "MongoDB 暂时不可用".to_string(),
+            )),
+        }
+    }
+
+    async fn monitor(&self) {
+        let interval = std::time::Duration::from_secs(self.config.retry_interval.max(1));
+        loop {
+            let client = self.inner.read().unwrap().client.clone();
+            let result = match client {
+                Some(client) => ping(&client).await,
+                None => match self.config.client().await {
+                    Ok(client) => {
+                        // 客户端内部会自动重连，创建成功后一直复用
+                        self.inner.write().unwrap().client = Some(client.clone());
+                        ping(&client).await
+                    }
+                    Err(e) => Err(e),
+                },
+            };
+            self.record(result, interval);
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    /// 记录健康检查结果，状态变化时输出日志
+    fn record(&self, result: Result<(), mongodb::error::Error>, interval: std::time::Duration) {
+        let mut inner = self.inner.write().unwrap();
+        match result {
+            Ok(()) => {
+                if inner.status != StoreStatus::Up {
+                    info!("MongoDB 连接成功");
+                }
+                inner.status = StoreStatus::Up;
+            }
+            Err(e) => {
+                if inner.status != StoreStatus::Down {
+                    warn!("MongoDB 不可用，{}秒后重试: {e}", interval.as_secs());
+                }
+                inner.status = StoreStatus::Down;
+            }
+        }
+    }
+}
+
+impl DocumentStore for MongoStore {
+    fn status(&self) -> StoreStatus {
+        self.inner.read().unwrap().status
+    }
+
+    fn insert_many<'a>(
+        &'a self,
+        collection: &'a str,
+        documents: Vec<Document>,
+    ) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            if documents.is_empty() {
+                return Ok(());
+            }
+            self.database()?
+                .collection::<bson::Document>(collection)
+                .insert_many(documents.into_iter().map(to_document))
+                .await
+                .map_err(mongo_error)?;
+            Ok(())
+        })
+    }
+
+    fn find<'a>(
+        &'a self,
+        collection: &'a str,
+        filter: &'a Filter,
+        options: &'a FindOptions,
+    ) -> BoxFuture<'a, Result<Vec<Document>, AppError>> {
+        Box::pin(async move {
+            let mut sort = bson::Document::new();
+            for (field, order) in &options.sort {
+                sort.insert(field, sort_value(*order));
+            }
+            let documents: Vec<bson::Document> = self
+                .database()?
+                .collection::<bson::Document>(collection)
+                .find(to_query(filter))
+                .sort(sort)
+                .skip(options.skip)
+                .limit(
+                    options
+                        .limit
+                        .map_or(0, |limit| i64::try_from(limit).unwrap_or(i64::MAX)),
+                )
+                .await
+                .map_err(mongo_error)?
+                .try_collect()
+                .await
+                .map_err(mongo_error)?;
+            Ok(documents.into_iter().map(from_document).collect())
+        })
+    }
+
+    fn update_many<'a>(
+        &'a self,
+        collection: &'a str,
+        filter: &'a Filter,
+        set: Document,
+    ) -> BoxFuture<'a, Result<u64, AppError>> {
+        Box::pin(async move {
+            let result = self
+                .database()?
+                .collection::<bson::Document>(collection)
+                .update_many(to_query(filter), doc! { "$set": to_document(set) })
+                .await
+                .map_err(mongo_error)?;
+            Ok(result.matched_count)
+        })
+    }
+
+    fn delete_many<'a>(
+        &'a self,
+        collection: &'a str,
+        filter: &'a Filter,
+    ) -> BoxFuture<'a, Result<u64, AppError>> {
+        Box::pin(async move {
+            let result = self
+                .database()?
+                .collection::<bson::Document>(collection)
+                .delete_many(to_query(filter))
+                .await
+                .map_err(mongo_error)?;
+            Ok(result.deleted_count)
+        })
+    }
+
+    fn create_index<'a>(
+        &'a self,
+        collection: &'a str,
+        index: &'a Index,
+    ) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let mut keys = bson::Document::new();
+            for (field, order) in &index.keys {
+                keys.insert(field, sort_value(*order));
+            }
+            let options = index
+                .expire_after
+                .map(|expire_after| IndexOptions::builder().expire_after(expire_after).build());
+            self.database()?
+                .collection::<bson::Document>(collection)
+                .create_index(IndexModel::builder().keys(keys).options(options).build())
+                .await
+                .map_err(mongo_error)?;
+            Ok(())
+        })
+    }
+}
+
+fn mongo_error(e: mongodb::error::Error) -> AppError {
+    AppError::ServiceUnavailable(format!("MongoDB 操作失败: {e}"))
+}
+
+async fn ping(client: &Client) -> Result<(), mongodb::error::Error> {
+    client
+        .database("admin")
+        .run_command(doc! { "ping": 1 })
+        .await
+        .map(|_| ())
+}
+
+fn sort_value(order: SortOrder) -> i32 {
+    match order {
+        SortOrder::Asc => 1,
+        SortOrder::Desc => -1,
+    }
+}
+
+/// 转换为 MongoDB 查询，同一字段的多个条件合并为一个子文档
+fn to_query(filter: &Filter) -> bson::Document {
+    let mut query = bson::Document::new();
+    for condition in &filter.conditions {
+        let op = match condition.op {
+            Op::Eq => "$eq",
+            Op::Gt => "$gt",
+            Op::Gte => "$gte",
+            Op::Lt => "$lt",
+            Op::Lte => "$lte",
+        };
+        if !query.contains_key(&condition.field) {
+            query.insert(&condition.field, bson::Document::new());
+        }
+        if let Ok(operators) = query.get_document_mut(&condition.field) {
+            operators.insert(op, to_bson(condition.value.clone()));
+        }
+    }
+    query
+}
+
+fn to_document(document: Document) -> bson::Document {
+    document
+        .into_iter()
+        .map(|(key, value)| (key, to_bson(value)))
+        .collect()
+}
+
+fn to_bson(value: Value) -> Bson {
+    match value {
+        Value::Null => Bson::Null,
+        Value::Bool(value) => Bson::Boolean(value),
+        Value::Number(number) => match number.as_i64() {
+            Some(value) => Bson::Int64(value),
+            None => Bson::Double(number.as_f64().unwrap_or_default()),
+        },
+        Value::String(value) => Bson::String(value),
+        Value::Array(values) => Bson::Array(values.into_iter().map(to_bson).collect()),
+        // 与 `date` 的格式一致
+        Value::Object(object) => match object.get("$date").and_then(Value::as_i64) {
+            Some(millis) if object.len() == 1 => {
+                Bson::DateTime(bson::DateTime::from_millis(millis))
+            }
+            _ => Bson::Document(to_document(object)),
+        },
+    }
+}
+
+fn from_document(document: bson::Document) -> Document {
+    document
+        .into_iter()
+        .map(|(key, value)| (key, from_bson(value)))
+        .collect()
+}
+
+fn from_bson(value: Bson) -> Value {
+    match value {
+        Bson::Null | Bson::Undefined => Value::Null,
+        Bson::Boolean(value) => Value::Bool(value),
+        Bson::Int32(value) => value.into(),
+        Bson::Int64(value) => value.into(),
+        Bson::Double(value) => value.into(),
+        Bson::String(value) => Value::String(value),
+        Bson::Array(values) => Value::Array(values.into_iter().map(from_bson).collect()),
+        Bson::Document(document) => Value::Object(from_document(document)),
+        Bson::DateTime(value) => {
+            match chrono::DateTime::from_timestamp_millis(value.timestamp_millis()) {
+                Some(value) => date(value),
+                None => Value::Null,
+            }
+        }
+        Bson::ObjectId(id) => Value::String(id.to_hex()),
+        other => other.into_relaxed_extjson(),
+    }
+}
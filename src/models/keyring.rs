@@ -0,0 +1,261 @@
+use crate::app_config::jwt::{Jwt, JwtKey};
+use anyhow::{Context, Result, anyhow, bail};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey, Header, TokenData, Validation, decode, decode_header,
+    encode,
+    errors::{ErrorKind, Result as JwtResult},
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, EllipticCurveKeyParameters,
+        EllipticCurveKeyType, Jwk, JwkSet, KeyAlgorithm, OctetKeyPairParameters, OctetKeyPairType,
+        PublicKeyUse, RSAKeyParameters, RSAKeyType,
+    },
+};
+use rsa::{
+    RsaPublicKey, pkcs1::DecodeRsaPublicKey, pkcs8::DecodePublicKey, traits::PublicKeyParts,
+};
+use serde::{Serialize, de::DeserializeOwned};
+use std::fmt;
+use std::str::FromStr;
+
+/// 已加载的签名密钥
+struct SigningKey {
+    kid: String,
+    algorithm: Algorithm,
+    /// 签名密钥，仅保留用于校验的旧密钥可以为空
+    encoding: Option<EncodingKey>,
+    decoding: DecodingKey,
+    /// 对外公开的公钥，HMAC 密钥不公开
+    jwk: Option<Jwk>,
+}
+
+/// JWT 密钥环
+///
+/// 只使用最新的密钥签名，校验时根据 token 头部的 `kid` 在最近 N 个密钥中查找
+pub struct KeyRing {
+    /// 按时间顺序排列，最后一个为当前签名密钥
+    keys: Vec<SigningKey>,
+}
+
+impl fmt::Debug for KeyRing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.keys.iter().map(|key| (&key.kid, key.algorithm)))
+            .finish()
+    }
+}
+
+impl KeyRing {
+    /// 根据配置加载密钥
+    pub fn from_config(config: &Jwt) -> Result<Self> {
+        let skip = config.keys.len().saturating_sub(config.max_keys.max(1));
+        let keys = config.keys[skip..]
+            .iter()
+            .map(|key| load_key(key).with_context(|| format!("加载 JWT 密钥 {} 失败", key.kid)))
+            .collect::<Result<Vec<_>>>()?;
+        match keys.last() {
+            None => {
+                warn!("未配置 JWT 签名密钥，已生成临时密钥，重启后已签发的 token 将全部失效");
+                Ok(Self::ephemeral())
+            }
+            Some(newest) if newest.encoding.is_none() => {
+                bail!("最新的 JWT 密钥 {} 缺少私钥，无法用于签名", newest.kid)
+            }
+            Some(_) => Ok(Self { keys }),
+        }
+    }
+
+    /// 生成一个随机的临时 HS256 密钥
+    fn ephemeral() -> Self {
+        let secret: [u8; 32] = rand::random();
+        Self {
+            keys: vec![SigningKey {
+                kid: format!("ephemeral-{}", uuid::Uuid::new_v4()),
+                algorithm: Algorithm::HS256,
+                encoding: Some(EncodingKey::from_secret(&secret)),
+                decoding: DecodingKey::from_secret(&secret),
+                jwk: None,
+            }],
+        }
+    }
+
+    /// 使用最新的密钥签名
+    pub fn sign<T: Serialize>(&self, claims: &T) -> JwtResult<String> {
+        let key = self.keys.last().expect("key ring is never empty");
+        let encoding = key
+            .encoding
+            .as_ref()
+            .expect("newest key always has a private key");
+        let mut header = Header::new(key.algorithm);
+        header.kid = Some(key.kid.clone());
+        encode(&header, claims, encoding)
+    }
+
+    /// 根据 `kid` 查找密钥并校验 token
+    pub fn verify<T: DeserializeOwned>(&self, token: &str) -> JwtResult<TokenData<T>> {
+        let header = decode_header(token)?;
+        let kid = header.kid.ok_or(ErrorKind::InvalidToken)?;
+        let key = self
+            .keys
+            .iter()
+            .find(|key| key.kid == kid)
+            .ok_or(ErrorKind::InvalidSignature)?;
+        decode::<T>(token, &key.decoding, &Validation::new(key.algorithm))
+    }
+
+    /// 所有可公开的公钥，供其他服务离线校验 token
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: self.keys.iter().filter_map(|key| key.jwk.clone()).collect(),
+        }
+    }
+}
+
+/// 根据算法族加载单个密钥
+fn load_key(key: &JwtKey) -> Result<SigningKey> {
+    let read_pem = |path: &Option<String>, name: &str| -> Result<Option<Vec<u8>>> {
+        path.as_ref()
+            .map(|path| std::fs::read(path).with_context(|| format!("读取{name} {path} 失败")))
+            .transpose()
+    };
+    let private_pem = read_pem(&key.private_key_path, "私钥")?;
+    let public_pem = read_pem(&key.public_key_path, "公钥")?;
+    let require_public = || public_pem.as_deref().ok_or_else(|| anyhow!("缺少公钥路径"));
+
+    let (encoding, decoding, jwk) = match key.algorithm {
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+            let secret = load_secret(key)?;
+            (
+                Some(EncodingKey::from_secret(&secret)),
+                DecodingKey::from_secret(&secret),
+                None,
+            )
+        }
+        Algorithm::RS256
+        | Algorithm::RS384
+        | Algorithm::RS512
+        | Algorithm::PS256
+        | Algorithm::PS384
+        | Algorithm::PS512 => {
+            let public = require_public()?;
+            (
+                private_pem
+                    .as_deref()
+                    .map(EncodingKey::from_rsa_pem)
+                    .transpose()?,
+                DecodingKey::from_rsa_pem(public)?,
+                Some(rsa_jwk(public)?),
+            )
+        }
+        Algorithm::ES256 | Algorithm::ES384 => {
+            let public = require_public()?;
+            (
+                private_pem
+                    .as_deref()
+                    .map(EncodingKey::from_ec_pem)
+                    .transpose()?,
+                DecodingKey::from_ec_pem(public)?,
+                Some(ec_jwk(public, key.algorithm)?),
+            )
+        }
+        Algorithm::EdDSA => {
+            let public = require_public()?;
+            (
+                private_pem
+                    .as_deref()
+                    .map(EncodingKey::from_ed_pem)
+                    .transpose()?,
+                DecodingKey::from_ed_pem(public)?,
+                Some(ed_jwk(public)?),
+            )
+        }
+    };
+
+    Ok(SigningKey {
+        kid: key.kid.clone(),
+        algorithm: key.algorithm,
+        encoding,
+        decoding,
+        jwk: jwk.map(|algorithm| Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: KeyAlgorithm::from_str(&format!("{:?}", key.algorithm)).ok(),
+                key_id: Some(key.kid.clone()),
+                ..Default::default()
+            },
+            algorithm,
+        }),
+    })
+}
+
+/// 依次从环境变量、文件、配置项读取 HMAC 密钥
+fn load_secret(key: &JwtKey) -> Result<Vec<u8>> {
+    if let Some(name) = &key.secret_env {
+        return std::env::var(name)
+            .map(String::into_bytes)
+            .with_context(|| format!("读取环境变量 {name} 失败"));
+    }
+    if let Some(path) = &key.secret_file {
+        let secret = std::fs::read_to_string(path).with_context(|| format!("读取 {path} 失败"))?;
+        return Ok(secret.trim().as_bytes().to_vec());
+    }
+    key.secret
+        .as_ref()
+        .map(|secret| secret.as_bytes().to_vec())
+        .ok_or_else(|| anyhow!("HMAC 密钥需要配置 secret、secret_file 或 secret_env"))
+}
+
+/// 从 PEM 公钥 (SPKI 或 PKCS#1) 中提取 RSA 模数与指数
+fn rsa_jwk(public_pem: &[u8]) -> Result<AlgorithmParameters> {
+    let pem = std::str::from_utf8(public_pem)?;
+    let public = RsaPublicKey::from_public_key_pem(pem)
+        .or_else(|_| RsaPublicKey::from_pkcs1_pem(pem))
+        .context("无法解析 RSA 公钥")?;
+    Ok(AlgorithmParameters::RSA(RSAKeyParameters {
+        key_type: RSAKeyType::RSA,
+        n: URL_SAFE_NO_PAD.encode(public.n().to_bytes_be()),
+        e: URL_SAFE_NO_PAD.encode(public.e().to_bytes_be()),
+    }))
+}
+
+/// SPKI 结构中公钥位于末尾的 BIT STRING，EC 为未压缩点 `0x04 || x || y`
+fn ec_jwk(public_pem: &[u8], algorithm: Algorithm) -> Result<AlgorithmParameters> {
+    let (curve, size) = match algorithm {
+        Algorithm::ES384 => (EllipticCurve::P384, 48),
+        _ => (EllipticCurve::P256, 32),
+    };
+    let point = spki_tail(public_pem, 1 + size * 2)?;
+    if point[0] != 0x04 {
+        bail!("仅支持未压缩格式的 EC 公钥");
+    }
+    Ok(AlgorithmParameters::EllipticCurve(
+        EllipticCurveKeyParameters {
+            key_type: EllipticCurveKeyType::EC,
+            curve,
+            x: URL_SAFE_NO_PAD.encode(&point[1..=size]),
+            y: URL_SAFE_NO_PAD.encode(&point[size + 1..]),
+        },
+    ))
+}
+
+/// Ed25519 公钥为 SPKI 末尾的 32 字节
+fn ed_jwk(public_pem: &[u8]) -> Result<AlgorithmParameters> {
+    let x = spki_tail(public_pem, 32)?;
+    Ok(AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+        key_type: OctetKeyPairType::OctetKeyPair,
+        curve: EllipticCurve::Ed25519,
+        x: URL_SAFE_NO_PAD.encode(x),
+    }))
+}
+
+fn spki_tail(public_pem: &[u8], len: usize) -> Result<Vec<u8>> {
+    let pem = pem::parse(public_pem).context("无法解析 PEM 公钥")?;
+    if pem.tag() != "PUBLIC KEY" {
+        bail!("公钥必须为 SPKI 格式 (BEGIN PUBLIC KEY)");
+    }
+    let der = pem.contents();
+    der.len()
+        .checked_sub(len)
+        .map(|start| der[start..].to_vec())
+        .ok_or_else(|| anyhow!("公钥长度不正确"))
+}
@@ -0,0 +1,214 @@
+use crate::app_config::login_guard::LoginGuard as LoginGuardConfig;
+use crate::errors::{AppError, code};
+use crate::utils::{serde_timestamp, serde_timestamp_option};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use utoipa::ToSchema;
+
+/// 失败记录的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum LockoutKind {
+    /// 按账号 (邮箱) 统计
+    Account,
+    /// 按客户端 IP 统计
+    Ip,
+}
+
+#[derive(Debug, Clone)]
+struct Attempts {
+    failures: u32,
+    last_failure: DateTime<Utc>,
+    locked_until: Option<DateTime<Utc>>,
+}
+
+/// 供管理员查看的失败记录
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LockoutInfo {
+    pub kind: LockoutKind,
+    pub key: String,
+    pub failures: u32,
+    #[serde(with = "serde_timestamp")]
+    #[schema(value_type = String, example = "2025-01-01 00:00:00")]
+    pub last_failure: DateTime<Utc>,
+    #[serde(with = "serde_timestamp_option")]
+    #[schema(value_type = Option<String>, example = "2025-01-01 00:00:00")]
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+/// 登录失败次数统计与锁定
+///
+/// 同时按账号和客户端 IP 统计，数据保存在内存中，服务重启后清空
+#[derive(Debug)]
+pub struct LoginGuard {
+    config: LoginGuardConfig,
+    entries: Mutex<HashMap<(LockoutKind, String), Attempts>>,
+}
+
+impl LoginGuard {
+    pub fn new(config: &LoginGuardConfig) -> Self {
+        Self {
+            config: config.clone(),
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 超出统计窗口且不在锁定期内的记录
+    fn expired(attempts: &Attempts, window: Duration, now: DateTime<Utc>) -> bool {
+        attempts.last_failure + window <= now
+            && attempts.locked_until.is_none_or(|until| until <= now)
+    }
+
+    fn keys(email: &str, ip: Option<&str>) -> Vec<(LockoutKind, String)> {
+        let mut keys = vec![(LockoutKind::Account, email.trim().to_lowercase())];
+        if let Some(ip) = ip {
+            keys.push((LockoutKind::Ip, ip.to_string()));
+        }
+        keys
+    }
+
+    /// 登录前检查账号或 IP 是否处于锁定状态
+    pub fn check(&self, email: &str, ip: Option<&str>) -> Result<(), AppError> {
+        let now = Utc::now();
+        let entries = self.entries.lock().unwrap();
+        let locked = Self::keys(email, ip).iter().any(|key| {
+            entries
+                .get(key)
+                .and_then(|attempts| attempts.locked_until)
+                .is_some_and(|locked_until| locked_until > now)
+        });
+        if locked {
+            return Err(
+                AppError::TooManyRequests("登录失败次数过多，请稍后再试".to_string())
+                    .with_code(code::ACCOUNT_LOCKED),
+            );
+        }
+        Ok(())
+    }
+
+    /// 记录一次登录失败，返回本次失败响应前需要等待的时长
+    pub fn record_failure(&self, email: &str, ip: Option<&str>) -> std::time::Duration {
+        let now = Utc::now();
+        let window = Duration::seconds(self.config.window);
+        let mut entries = self.entries.lock().unwrap();
+
+        let mut max_failures = 0;
+        for key in Self::keys(email, ip) {
+            let threshold = match key.0 {
+                LockoutKind::Account => self.config.max_account_failures,
+                LockoutKind::Ip => self.config.max_ip_failures,
+            };
+            // 任何人都可以提交不存在的邮箱，达到上限后不再记录新的键，过期记录由清理任务删除
+            if !entries.contains_key(&key) && entries.len() >= self.config.max_entries {
+                warn!(kind = ?key.0, key = %key.1, "登录失败记录已达上限，本次失败不计数");
+                continue;
+            }
+            let attempts = entries.entry(key.clone()).or_insert(Attempts {
+                failures: 0,
+                last_failure: now,
+                locked_until: None,
+            });
+            // 锁定已经结束或超出统计窗口、尚未被清理的记录重新开始计数
+            if attempts.locked_until.is_some_and(|until| until <= now)
+                || Self::expired(attempts, window, now)
+            {
+                attempts.failures = 0;
+                attempts.locked_until = None;
+            }
+            attempts.failures += 1;
+            attempts.last_failure = now;
+            if attempts.failures >= threshold && attempts.locked_until.is_none() {
+                let locked_until = now + Duration::seconds(self.config.lockout);
+                attempts.locked_until = Some(locked_until);
+                warn!(
+                    kind = ?key.0,
+                    key = %key.1,
+                    failures = attempts.failures,
+                    %locked_until,
+                    "登录失败次数过多，已锁定"
+                );
+            }
+            max_failures = max_failures.max(attempts.failures);
+        }
+
+        let delay = self
+            .config
+            .base_delay_ms
+            .saturating_mul(1 << max_failures.saturating_sub(1).min(16))
+            .min(self.config.max_delay_ms);
+        std::time::Duration::from_millis(delay)
+    }
+
+    /// 登录成功后清除该账号的失败记录，IP 的记录保留
+    pub fn record_success(&self, email: &str) {
+        self.entries
+            .lock()
+            .unwrap()
+            .remove(&(LockoutKind::Account, email.trim().to_lowercase()));
+    }
+
+    /// 列出所有未过期的失败记录
+    pub fn list(&self) -> Vec<LockoutInfo> {
+        let now = Utc::now();
+        let window = Duration::seconds(self.config.window);
+        let mut list = self
+            .entries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, attempts)| !Self::expired(attempts, window, now))
+            .map(|((kind, key), attempts)| LockoutInfo {
+                kind: *kind,
+                key: key.clone(),
+                failures: attempts.failures,
+                last_failure: attempts.last_failure,
+                locked_until: attempts.locked_until.filter(|until| *until > now),
+            })
+            .collect::<Vec<_>>();
+        list.sort_by_key(|info| std::cmp::Reverse(info.last_failure));
+        list
+    }
+
+    /// 删除所有过期的失败记录，返回删除的条数
+    pub fn prune(&self) -> usize {
+        let now = Utc::now();
+        let window = Duration::seconds(self.config.window);
+        let mut entries = self.entries.lock().unwrap();
+        let before = entries.len();
+        entries.retain(|_, attempts| !Self::expired(attempts, window, now));
+        before - entries.len()
+    }
+
+    /// 清除某条失败记录并解除锁定，返回记录是否存在
+    pub fn clear(&self, kind: LockoutKind, key: &str) -> bool {
+        let key = match kind {
+            LockoutKind::Account => key.trim().to_lowercase(),
+            LockoutKind::Ip => key.to_string(),
+        };
+        let removed = self.entries.lock().unwrap().remove(&(kind, key.clone()));
+        if removed.is_some() {
+            info!(kind = ?kind, key = %key, "登录锁定已被清除");
+        }
+        removed.is_some()
+    }
+}
+
+/// 启动定期清理过期失败记录的后台任务
+pub fn spawn_prune_job(guard: Arc<LoginGuard>) {
+    if guard.config.prune_interval == 0 {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut ticker =
+            tokio::time::interval(std::time::Duration::from_secs(guard.config.prune_interval));
+        loop {
+            ticker.tick().await;
+            let pruned = guard.prune();
+            if pruned > 0 {
+                debug!("清理过期的登录失败记录 {pruned} 条");
+            }
+        }
+    });
+}
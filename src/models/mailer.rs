@@ -0,0 +1,160 @@
+use crate::app_config::mail::Mail;
+use crate::errors::AppError;
+use anyhow::{Context, Result, bail};
+use futures::future::BoxFuture;
+use lettre::message::{Mailbox, header::ContentType};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// 待发送的邮件
+#[derive(Debug, Clone)]
+pub struct Email {
+    /// 收件人邮箱
+    pub to: String,
+    /// 邮件主题
+    pub subject: String,
+    /// 纯文本正文
+    pub body: String,
+}
+
+/// 邮件发送接口，不同的发送方式实现该 trait
+pub trait Mailer: Send + Sync + fmt::Debug {
+    fn send<'a>(&'a self, email: &'a Email) -> BoxFuture<'a, Result<(), AppError>>;
+}
+
+/// 根据配置创建邮件发送器
+pub fn from_config(config: &Mail) -> Result<Arc<dyn Mailer>> {
+    let transport = config.transport.to_lowercase();
+    let mailer: Arc<dyn Mailer> = match transport.as_str() {
+        "smtp" => Arc::new(SmtpMailer::new(config)?),
+        "file" => Arc::new(FileMailer::new(config)?),
+        "memory" => Arc::new(MemoryMailer::default()),
+        _ => bail!("不支持的邮件发送方式: {}", config.transport),
+    };
+    Ok(mailer)
+}
+
+fn build_message(from: &Mailbox, email: &Email) -> Result<Message, AppError> {
+    let to = email
+        .to
+        .parse::<Mailbox>()
+        .map_err(|e| AppError::BadRequest(format!("无效的收件人 {}: {e}", email.to)))?;
+    Message::builder()
+        .from(from.clone())
+        .to(to)
+        .subject(&email.subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(email.body.clone())
+        .map_err(|e| AppError::InternalError(format!("邮件构建失败: {e}")))
+}
+
+/// 通过 SMTP 服务器发送邮件
+pub struct SmtpMailer {
+    from: Mailbox,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl fmt::Debug for SmtpMailer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SmtpMailer")
+            .field("from", &self.from)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SmtpMailer {
+    pub fn new(config: &Mail) -> Result<Self> {
+        let from = config.from.parse().context("无效的发件人地址")?;
+        let mut builder = match config.smtp_tls.to_lowercase().as_str() {
+            "starttls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host)?,
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.smtp_host)?,
+            "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.smtp_host),
+            other => bail!("不支持的 SMTP 加密方式: {other}"),
+        }
+        .port(config.smtp_port);
+        if !config.smtp_username.is_empty() {
+            let password = match &config.smtp_password_env {
+                Some(name) => {
+                    std::env::var(name).with_context(|| format!("读取环境变量 {name} 失败"))?
+                }
+                None => config.smtp_password.clone().unwrap_or_default(),
+            };
+            builder = builder.credentials(Credentials::new(config.smtp_username.clone(), password));
+        }
+        Ok(Self {
+            from,
+            transport: builder.build(),
+        })
+    }
+}
+
+impl Mailer for SmtpMailer {
+    fn send<'a>(&'a self, email: &'a Email) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let message = build_message(&self.from, email)?;
+            self.transport
+                .send(message)
+                .await
+                .map_err(|e| AppError::ServiceUnavailable(format!("邮件发送失败: {e}")))?;
+            Ok(())
+        })
+    }
+}
+
+/// 把邮件写入目录下的 .eml 文件，用于开发环境查看邮件内容
+#[derive(Debug)]
+pub struct FileMailer {
+    from: Mailbox,
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(config: &Mail) -> Result<Self> {
+        let dir = PathBuf::from(&config.file_dir);
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("创建邮件目录 {} 失败", dir.display()))?;
+        Ok(Self {
+            from: config.from.parse().context("无效的发件人地址")?,
+            dir,
+        })
+    }
+}
+
+impl Mailer for FileMailer {
+    fn send<'a>(&'a self, email: &'a Email) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async move {
+            let message = build_message(&self.from, email)?;
+            let path = self
+                .dir
+                .join(format!("{}.eml", uuid::Uuid::new_v4().simple()));
+            tokio::fs::write(&path, message.formatted())
+                .await
+                .map_err(|e| AppError::InternalError(format!("邮件写入失败: {e}")))?;
+            info!("邮件已写入 {}", path.display());
+            Ok(())
+        })
+    }
+}
+
+/// 把邮件保存在内存中，用于测试
+#[derive(Debug, Default)]
+pub struct MemoryMailer {
+    outbox: Mutex<Vec<Email>>,
+}
+
+impl MemoryMailer {
+    /// 已发送的全部邮件
+    pub fn sent(&self) -> Vec<Email> {
+        self.outbox.lock().unwrap().clone()
+    }
+}
+
+impl Mailer for MemoryMailer {
+    fn send<'a>(&'a self, email: &'a Email) -> BoxFuture<'a, Result<(), AppError>> {
+        self.outbox.lock().unwrap().push(email.clone());
+        Box::pin(async { Ok(()) })
+    }
+}
@@ -1 +1,23 @@
+pub mod api_key;
+pub mod audit;
+pub mod auth;
+pub mod client_info;
+pub mod document_store;
+pub mod keyring;
+pub mod login_guard;
+pub mod mailer;
+pub mod oauth;
+pub mod oidc;
+pub mod pagination;
+pub mod password;
+pub mod password_reset;
+pub mod rbac;
+pub mod refresh_token;
+pub mod secure_token;
+pub mod session;
 pub mod token;
+pub mod totp;
+pub mod two_factor;
+pub mod user_deletion;
+pub mod user_token;
+pub mod verification;
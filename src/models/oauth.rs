@@ -0,0 +1,300 @@
+use crate::app_config::oauth_server::OAuthServer;
+use crate::entity::{oauth_clients, oauth_consents};
+use crate::errors::AppError;
+use crate::models::secure_token;
+use actix_web::{
+    HttpResponse, ResponseError,
+    http::{
+        StatusCode,
+        header::{AUTHORIZATION, CACHE_CONTROL, HeaderMap, WWW_AUTHENTICATE},
+    },
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, Set,
+};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::sync::Mutex;
+
+/// 支持的授权方式
+pub mod grant_type {
+    /// 授权码模式 (第三方应用代表用户访问)
+    pub const AUTHORIZATION_CODE: &str = "authorization_code";
+    /// 使用刷新令牌换取新的访问令牌
+    pub const REFRESH_TOKEN: &str = "refresh_token";
+    /// 客户端凭据模式 (第三方服务以自身身份访问)
+    pub const CLIENT_CREDENTIALS: &str = "client_credentials";
+
+    pub const ALL: [&str; 3] = [AUTHORIZATION_CODE, REFRESH_TOKEN, CLIENT_CREDENTIALS];
+}
+
+/// 只用于识别用户身份的授权范围，不对应任何权限
+pub const PROFILE_SCOPE: &str = "profile";
+
+/// RFC 6749 格式的错误，令牌、内省与撤销接口面向第三方应用，不使用统一的 `AppError` 响应
+#[derive(Debug)]
+pub struct OAuthError {
+    pub error: &'static str,
+    pub description: String,
+}
+
+impl OAuthError {
+    pub fn new(error: &'static str, description: impl Into<String>) -> Self {
+        Self {
+            error,
+            description: description.into(),
+        }
+    }
+
+    pub fn invalid_request(description: impl Into<String>) -> Self {
+        Self::new("invalid_request", description)
+    }
+
+    pub fn invalid_client(description: impl Into<String>) -> Self {
+        Self::new("invalid_client", description)
+    }
+
+    pub fn invalid_grant(description: impl Into<String>) -> Self {
+        Self::new("invalid_grant", description)
+    }
+}
+
+#[derive(Serialize)]
+struct OAuthErrorResponse<'a> {
+    error: &'a str,
+    error_description: &'a str,
+}
+
+impl fmt::Display for OAuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.error, self.description)
+    }
+}
+
+impl ResponseError for OAuthError {
+    fn status_code(&self) -> StatusCode {
+        match self.error {
+            "invalid_client" => StatusCode::UNAUTHORIZED,
+            "server_error" => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+    fn error_response(&self) -> HttpResponse {
+        let mut builder = HttpResponse::build(self.status_code());
+        builder.insert_header((CACHE_CONTROL, "no-store"));
+        if self.error == "invalid_client" {
+            builder.insert_header((WWW_AUTHENTICATE, "Basic"));
+        }
+        builder.json(OAuthErrorResponse {
+            error: self.error,
+            error_description: &self.description,
+        })
+    }
+}
+
+impl From<AppError> for OAuthError {
+    fn from(e: AppError) -> Self {
+        error!("OAuth2 请求处理失败: {e}");
+        OAuthError::new("server_error", "服务器内部错误")
+    }
+}
+impl From<DbErr> for OAuthError {
+    fn from(e: DbErr) -> Self {
+        AppError::from(e).into()
+    }
+}
+
+/// 用户同意授权后签发的授权码，换取令牌时取出
+#[derive(Debug, Clone)]
+pub struct AuthorizationCode {
+    pub client_id: String,
+    pub user_id: i64,
+    pub redirect_uri: String,
+    pub scope: String,
+    /// PKCE S256 挑战码，公开客户端必须提供
+    pub code_challenge: Option<String>,
+    pub create_time: DateTime<Utc>,
+}
+
+/// 授权服务器的运行时状态
+///
+/// 授权码有效期很短且只能使用一次，保存在内存中，以摘要为键，重启后未使用的授权码全部失效
+#[derive(Debug)]
+pub struct AuthorizationServer {
+    config: OAuthServer,
+    codes: Mutex<HashMap<String, AuthorizationCode>>,
+}
+
+impl AuthorizationServer {
+    pub fn new(config: &OAuthServer) -> Self {
+        Self {
+            config: config.clone(),
+            codes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 保存授权码并返回明文
+    pub fn issue_code(&self, code: AuthorizationCode) -> String {
+        let plain = secure_token::generate();
+        let expire = Duration::seconds(self.config.code_expire);
+        let now = Utc::now();
+        let mut codes = self.codes.lock().unwrap();
+        // 顺便清理过期未使用的授权码
+        codes.retain(|_, code| code.create_time + expire > now);
+        codes.insert(secure_token::digest(&plain), code);
+        plain
+    }
+
+    /// 取出授权码，无论后续校验是否通过，授权码都已失效
+    pub fn take_code(&self, code: &str) -> Option<AuthorizationCode> {
+        self.codes
+            .lock()
+            .unwrap()
+            .remove(&secure_token::digest(code))
+            .filter(|code| {
+                code.create_time + Duration::seconds(self.config.code_expire) > Utc::now()
+            })
+    }
+}
+
+/// PKCE S256 校验: BASE64URL(SHA256(code_verifier)) == code_challenge
+pub fn verify_pkce(code_verifier: &str, code_challenge: &str) -> bool {
+    secure_token::digest(code_verifier) == code_challenge
+}
+
+/// 规范化空格分隔的授权范围: 去重并排序
+fn normalize_scope<'a>(scopes: impl Iterator<Item = &'a str>) -> String {
+    scopes
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 校验申请的授权范围，必须是客户端允许范围的子集，未指定时使用客户端允许的全部范围
+pub fn resolve_scope(
+    requested: Option<&str>,
+    client: &oauth_clients::Model,
+) -> Result<String, OAuthError> {
+    let Some(requested) = requested.filter(|scope| !scope.trim().is_empty()) else {
+        return Ok(normalize_scope(client.scopes.split_whitespace()));
+    };
+    let allowed: BTreeSet<&str> = client.scopes.split_whitespace().collect();
+    if let Some(scope) = requested
+        .split_whitespace()
+        .find(|scope| !allowed.contains(scope))
+    {
+        return Err(OAuthError::new(
+            "invalid_scope",
+            format!("客户端无权申请授权范围 {scope}"),
+        ));
+    }
+    Ok(normalize_scope(requested.split_whitespace()))
+}
+
+/// 认证客户端，支持 HTTP Basic 与表单中的 `client_id` / `client_secret`
+///
+/// 机密客户端必须提供正确的密钥，公开客户端不能提供密钥
+pub async fn authenticate_client<C: ConnectionTrait>(
+    db: &C,
+    headers: &HeaderMap,
+    client_id: Option<&str>,
+    client_secret: Option<&str>,
+) -> Result<oauth_clients::Model, OAuthError> {
+    let basic = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Basic "))
+        .map(|credentials| {
+            STANDARD
+                .decode(credentials.trim())
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+                .and_then(|pair| {
+                    pair.split_once(':')
+                        .map(|(id, secret)| (id.to_string(), secret.to_string()))
+                })
+                .ok_or_else(|| OAuthError::invalid_client("无效的 Basic 认证信息"))
+        })
+        .transpose()?;
+    let (client_id, client_secret) = match &basic {
+        Some((id, secret)) => (Some(id.as_str()), Some(secret.as_str())),
+        None => (client_id, client_secret),
+    };
+    let client_id = client_id.ok_or_else(|| OAuthError::invalid_client("缺少客户端 ID"))?;
+    let client = oauth_clients::Entity::find()
+        .filter(oauth_clients::Column::ClientId.eq(client_id))
+        .one(db)
+        .await?
+        .ok_or_else(|| OAuthError::invalid_client("客户端认证失败"))?;
+    let authenticated = match (&client.client_secret_hash, client_secret) {
+        (Some(hash), Some(secret)) => secure_token::digest(secret) == *hash,
+        (None, None) => true,
+        _ => false,
+    };
+    if !authenticated {
+        warn!("OAuth2 客户端 {client_id} 认证失败");
+        return Err(OAuthError::invalid_client("客户端认证失败"));
+    }
+    Ok(client)
+}
+
+/// 用户是否已经同意向该客户端授予 `scope` 中的全部范围
+pub async fn has_consent<C: ConnectionTrait>(
+    db: &C,
+    user_id: i64,
+    client_id: &str,
+    scope: &str,
+) -> Result<bool, AppError> {
+    let Some(consent) = oauth_consents::Entity::find_by_id((user_id, client_id.to_string()))
+        .one(db)
+        .await?
+    else {
+        return Ok(false);
+    };
+    let granted: BTreeSet<&str> = consent.scope.split_whitespace().collect();
+    Ok(scope
+        .split_whitespace()
+        .all(|scope| granted.contains(scope)))
+}
+
+/// 记录用户的授权，与之前同意的范围合并
+pub async fn record_consent<C: ConnectionTrait>(
+    db: &C,
+    user_id: i64,
+    client_id: &str,
+    scope: &str,
+) -> Result<(), AppError> {
+    let existing = oauth_consents::Entity::find_by_id((user_id, client_id.to_string()))
+        .one(db)
+        .await?;
+    match existing {
+        Some(consent) => {
+            let merged = normalize_scope(
+                consent
+                    .scope
+                    .split_whitespace()
+                    .chain(scope.split_whitespace()),
+            );
+            let mut model: oauth_consents::ActiveModel = consent.into();
+            model.scope = Set(merged);
+            model.update_time = Set(Utc::now());
+            model.update(db).await?;
+        }
+        None => {
+            oauth_consents::ActiveModel {
+                user_id: Set(user_id),
+                client_id: Set(client_id.to_string()),
+                scope: Set(normalize_scope(scope.split_whitespace())),
+                create_time: Set(Utc::now()),
+                update_time: Set(Utc::now()),
+            }
+            .insert(db)
+            .await?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,433 @@
+use crate::app_config::oidc::Oidc;
+use crate::entity::{api_keys, devices, user_identities, user_tokens, users};
+use crate::errors::AppError;
+use crate::models::password::hash_password;
+use crate::models::rbac::{DEFAULT_ROLE, assign_role};
+use crate::models::secure_token;
+use crate::models::two_factor;
+use crate::state::AppState;
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header};
+use reqwest::Url;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::NotSet, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    Set, TransactionTrait,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+
+/// 保存 `state` 的 Cookie，回调时必须与参数中的 `state` 一致，防止把他人发起的登录注入到当前浏览器
+pub const STATE_COOKIE: &str = "oidc_state";
+
+/// 发现文档中用到的字段
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+#[derive(Debug, Clone)]
+struct ProviderCache {
+    metadata: ProviderMetadata,
+    jwks: JwkSet,
+    fetched_at: DateTime<Utc>,
+}
+
+/// 跳转到身份提供方之后、回调之前需要保存的数据
+#[derive(Debug)]
+struct PendingLogin {
+    code_verifier: String,
+    nonce: String,
+    create_time: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+/// ID Token 中用到的声明
+#[derive(Debug, Deserialize)]
+pub struct IdTokenClaims {
+    pub sub: String,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub preferred_username: Option<String>,
+    #[serde(default)]
+    pub nonce: Option<String>,
+}
+
+/// OIDC 客户端，实现授权码 + PKCE 流程
+///
+/// 发现文档与 JWKS 首次使用时加载并缓存，遇到未知的 `kid` 时强制刷新一次，以支持身份提供方轮换密钥
+#[derive(Debug)]
+pub struct OidcClient {
+    config: Oidc,
+    http: reqwest::Client,
+    cache: RwLock<Option<ProviderCache>>,
+    pending: Mutex<HashMap<String, PendingLogin>>,
+}
+
+fn provider_error(e: impl std::fmt::Display) -> AppError {
+    AppError::ServiceUnavailable(format!("身份提供方请求失败: {e}"))
+}
+
+impl OidcClient {
+    pub fn new(config: &Oidc) -> anyhow::Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(config.request_timeout))
+            .connect_timeout(std::time::Duration::from_secs(config.connect_timeout))
+            .build()?;
+        Ok(Self {
+            config: config.clone(),
+            http,
+            cache: RwLock::new(None),
+            pending: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// 配置的 issuer，去掉末尾的 `/`
+    pub fn issuer(&self) -> &str {
+        self.config.issuer.trim_end_matches('/')
+    }
+
+    /// 读取发现文档与 JWKS，缓存过期或 `force` 为 true 时重新加载
+    async fn provider(&self, force: bool) -> Result<ProviderCache, AppError> {
+        if !force {
+            let cache = self.cache.read().unwrap();
+            if let Some(cache) = cache.as_ref().filter(|cache| {
+                cache.fetched_at + Duration::seconds(self.config.metadata_cache) > Utc::now()
+            }) {
+                return Ok(cache.clone());
+            }
+        }
+
+        let discovery_url = format!("{}/.well-known/openid-configuration", self.issuer());
+        let metadata: ProviderMetadata = self
+            .http
+            .get(&discovery_url)
+            .send()
+            .await
+            .and_then(|resp| resp.error_for_status())
+            .map_err(provider_error)?
+            .json()
+            .await
+            .map_err(provider_error)?;
+        if metadata.issuer.trim_end_matches('/') != self.issuer() {
+            return Err(provider_error(format!(
+                "发现文档中的 issuer {} 与配置不一致",
+                metadata.issuer
+            )));
+        }
+        let jwks: JwkSet = self
+            .http
+            .get(&metadata.jwks_uri)
+            .send()
+            .await
+            .and_then(|resp| resp.error_for_status())
+            .map_err(provider_error)?
+            .json()
+            .await
+            .map_err(provider_error)?;
+        info!(
+            "已加载身份提供方 {} 的发现文档，公钥 {} 个",
+            metadata.issuer,
+            jwks.keys.len()
+        );
+        let cache = ProviderCache {
+            metadata,
+            jwks,
+            fetched_at: Utc::now(),
+        };
+        *self.cache.write().unwrap() = Some(cache.clone());
+        Ok(cache)
+    }
+
+    /// 回调地址使用 HTTPS 时 `state` Cookie 只通过 HTTPS 发送
+    pub fn secure_cookie(&self) -> bool {
+        self.config.redirect_uri.starts_with("https://")
+    }
+
+    /// 生成跳转到身份提供方的授权地址，同时保存 state、nonce 与 PKCE 校验码，返回授权地址与 state
+    pub async fn authorization_url(&self) -> Result<(String, String), AppError> {
+        let provider = self.provider(false).await?;
+        let state = secure_token::generate();
+        let nonce = secure_token::generate();
+        let code_verifier = secure_token::generate();
+        // S256: BASE64URL(SHA256(code_verifier))
+        let code_challenge = secure_token::digest(&code_verifier);
+
+        let mut url = Url::parse(&provider.metadata.authorization_endpoint)
+            .map_err(|e| provider_error(format!("无效的授权地址: {e}")))?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.config.client_id)
+            .append_pair("redirect_uri", &self.config.redirect_uri)
+            .append_pair("scope", &self.config.scopes.join(" "))
+            .append_pair("state", &state)
+            .append_pair("nonce", &nonce)
+            .append_pair("code_challenge", &code_challenge)
+            .append_pair("code_challenge_method", "S256");
+
+        let now = Utc::now();
+        let mut pending = self.pending.lock().unwrap();
+        // 登录入口无需认证，限制数量避免内存无限增长，超时的记录由清理任务删除
+        if pending.len() >= self.config.max_pending {
+            warn!("等待回调的 OIDC 登录请求已达上限");
+            return Err(AppError::TooManyRequests(
+                "登录请求过多，请稍后再试".to_string(),
+            ));
+        }
+        pending.insert(
+            state.clone(),
+            PendingLogin {
+                code_verifier,
+                nonce,
+                create_time: now,
+            },
+        );
+        Ok((url.into(), state))
+    }
+
+    /// 删除超时未回调的登录请求，返回删除的条数
+    pub fn prune(&self) -> usize {
+        let expire = Duration::seconds(self.config.state_expire);
+        let now = Utc::now();
+        let mut pending = self.pending.lock().unwrap();
+        let before = pending.len();
+        pending.retain(|_, login| login.create_time + expire > now);
+        before - pending.len()
+    }
+
+    /// 处理回调: 校验 state，用授权码换取 ID Token 并完成校验
+    pub async fn exchange(&self, code: &str, state: &str) -> Result<IdTokenClaims, AppError> {
+        let pending = self
+            .pending
+            .lock()
+            .unwrap()
+            .remove(state)
+            .filter(|login| {
+                login.create_time + Duration::seconds(self.config.state_expire) > Utc::now()
+            })
+            .ok_or_else(|| AppError::BadRequest("登录状态无效或已过期，请重新登录".to_string()))?;
+
+        let provider = self.provider(false).await?;
+        let mut form = vec![
+            ("grant_type", "authorization_code".to_string()),
+            ("code", code.to_string()),
+            ("redirect_uri", self.config.redirect_uri.clone()),
+            ("client_id", self.config.client_id.clone()),
+            ("code_verifier", pending.code_verifier),
+        ];
+        if let Some(secret) = self.config.client_secret() {
+            form.push(("client_secret", secret));
+        }
+        let resp = self
+            .http
+            .post(&provider.metadata.token_endpoint)
+            .form(&form)
+            .send()
+            .await
+            .map_err(provider_error)?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            warn!("身份提供方换取令牌失败: {status} {body}");
+            return Err(AppError::Unauthorized("授权码无效或已过期".to_string()));
+        }
+        let token: TokenResponse = resp.json().await.map_err(provider_error)?;
+
+        let claims = self.validate_id_token(&token.id_token).await?;
+        if claims.nonce.as_deref() != Some(pending.nonce.as_str()) {
+            return Err(AppError::Unauthorized(
+                "ID Token 的 nonce 不匹配".to_string(),
+            ));
+        }
+        Ok(claims)
+    }
+
+    /// 使用身份提供方的 JWKS 校验 ID Token 的签名、issuer、audience 与有效期
+    async fn validate_id_token(&self, id_token: &str) -> Result<IdTokenClaims, AppError> {
+        let header = decode_header(id_token)?;
+        if matches!(
+            header.alg,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            return Err(AppError::Unauthorized(
+                "不支持对称算法签名的 ID Token".to_string(),
+            ));
+        }
+        let mut provider = self.provider(false).await?;
+        let mut jwk = find_jwk(&provider.jwks, header.kid.as_deref());
+        if jwk.is_none() {
+            provider = self.provider(true).await?;
+            jwk = find_jwk(&provider.jwks, header.kid.as_deref());
+        }
+        let jwk =
+            jwk.ok_or_else(|| AppError::Unauthorized("ID Token 的签名密钥未知".to_string()))?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&provider.metadata.issuer]);
+        validation.set_audience(&[&self.config.client_id]);
+        let token_data =
+            decode::<IdTokenClaims>(id_token, &DecodingKey::from_jwk(&jwk)?, &validation)?;
+        Ok(token_data.claims)
+    }
+}
+
+/// 启动定期清理超时未回调的登录请求的后台任务
+pub fn spawn_prune_job(client: Arc<OidcClient>) {
+    if client.config.prune_interval == 0 {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut ticker =
+            tokio::time::interval(std::time::Duration::from_secs(client.config.prune_interval));
+        loop {
+            ticker.tick().await;
+            let pruned = client.prune();
+            if pruned > 0 {
+                debug!("清理超时未回调的 OIDC 登录请求 {pruned} 条");
+            }
+        }
+    });
+}
+
+/// 按 `kid` 查找公钥，ID Token 没有 `kid` 且只有一个公钥时直接使用
+fn find_jwk(jwks: &JwkSet, kid: Option<&str>) -> Option<jsonwebtoken::jwk::Jwk> {
+    match kid {
+        Some(kid) => jwks.find(kid).cloned(),
+        None if jwks.keys.len() == 1 => jwks.keys.first().cloned(),
+        None => None,
+    }
+}
+
+/// 根据 ID Token 找到或创建本地用户
+///
+/// 1. 已关联过的外部账号直接登录
+/// 2. 身份提供方确认过的邮箱与已有用户一致时自动关联
+/// 3. 开启 `auto_provision` 时为新邮箱创建用户
+pub async fn sign_in(
+    issuer: &str,
+    claims: &IdTokenClaims,
+    app_data: &AppState,
+) -> Result<users::Model, AppError> {
+    let identity = user_identities::Entity::find()
+        .filter(user_identities::Column::Issuer.eq(issuer))
+        .filter(user_identities::Column::Subject.eq(&claims.sub))
+        .one(&app_data.db_pool)
+        .await?;
+    if let Some(identity) = identity {
+        let user = users::Entity::find_active_by_id(identity.user_id)
+            .one(&app_data.db_pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("关联的用户不存在".to_string()))?;
+        let mut active: user_identities::ActiveModel = identity.into();
+        active.email = Set(claims.email.clone());
+        active.update_time = Set(Utc::now());
+        active.update(&app_data.db_pool).await?;
+        return Ok(user);
+    }
+
+    let email = claims
+        .email
+        .as_deref()
+        .filter(|_| claims.email_verified)
+        .ok_or_else(|| AppError::Forbidden("身份提供方未返回已验证的邮箱".to_string()))?;
+    let txn = app_data.db_pool.begin().await?;
+    let existing = users::Entity::find()
+        .filter(users::Column::Email.eq(email))
+        .one(&txn)
+        .await?;
+    let user = match existing {
+        Some(user) if user.status == users::STATUS_PENDING => {
+            // 身份提供方已经验证过邮箱，不需要再走邮箱验证
+            // 未验证的账号可能是他人抢先注册的，关联前重置密码并撤销该账号已有的会话与凭据
+            reset_credentials(&txn, user.id).await?;
+            let mut active: users::ActiveModel = user.into();
+            active.status = Set(users::STATUS_NORMAL.to_string());
+            active.pass_word = Set(hash_password(
+                &secure_token::generate(),
+                &app_data.config.password,
+            )?);
+            active.update_time = Set(Utc::now());
+            active.update(&txn).await?
+        }
+        Some(user) if user.status == users::STATUS_DELETED => {
+            return Err(AppError::Forbidden("该账号已被删除".to_string()));
+        }
+        Some(user) => user,
+        None if app_data.config.oidc.auto_provision => {
+            let name = claims
+                .name
+                .clone()
+                .or_else(|| claims.preferred_username.clone())
+                .unwrap_or_else(|| email.split('@').next().unwrap_or(email).to_string());
+            // 外部账号不使用本地密码，写入无人知晓的随机密码，之后可通过找回密码设置
+            let user = users::ActiveModel {
+                id: NotSet,
+                name: Set(name),
+                email: Set(email.to_string()),
+                pass_word: Set(hash_password(
+                    &secure_token::generate(),
+                    &app_data.config.password,
+                )?),
+                status: Set(users::STATUS_NORMAL.to_string()),
+                create_time: Set(Utc::now()),
+                update_time: Set(Utc::now()),
+                deleted_at: Set(None),
+                status_before_delete: Set(None),
+                pending_email: Set(None),
+            }
+            .insert(&txn)
+            .await?;
+            assign_role(&txn, user.id, DEFAULT_ROLE).await?;
+            info!("通过 {issuer} 自动创建用户 {} ({email})", user.id);
+            user
+        }
+        None => {
+            return Err(AppError::Forbidden("该邮箱尚未注册".to_string()));
+        }
+    };
+    user_identities::ActiveModel {
+        id: NotSet,
+        user_id: Set(user.id),
+        issuer: Set(issuer.to_string()),
+        subject: Set(claims.sub.clone()),
+        email: Set(claims.email.clone()),
+        create_time: Set(Utc::now()),
+        update_time: Set(Utc::now()),
+    }
+    .insert(&txn)
+    .await?;
+    txn.commit().await?;
+    info!("用户 {} 关联了 {issuer} 的账号 {}", user.id, claims.sub);
+    Ok(user)
+}
+
+/// 撤销用户的所有会话、邮件令牌与 API Key，并关闭两步验证
+async fn reset_credentials<C: ConnectionTrait>(db: &C, user_id: i64) -> Result<(), AppError> {
+    devices::Entity::delete_many()
+        .filter(devices::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    user_tokens::Entity::delete_many()
+        .filter(user_tokens::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    api_keys::Entity::delete_many()
+        .filter(api_keys::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    two_factor::disable(db, user_id).await?;
+    warn!("用户 {user_id} 的未验证账号通过 OIDC 关联，已重置原有的密码与凭据");
+    Ok(())
+}
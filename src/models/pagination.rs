@@ -0,0 +1,242 @@
+//! 列表接口通用的分页与排序
+//!
+//! 支持页码分页与游标分页两种方式，传入 `cursor` 时使用游标分页并忽略 `page`。
+//! 游标分页按排序字段与主键定位上一页的最后一条记录，翻页过程中插入或删除数据不会导致重复或遗漏。
+
+use crate::errors::AppError;
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, EntityTrait, ModelTrait, Order, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, Select, Value,
+};
+use utoipa::ToSchema;
+
+/// 默认每页条数
+pub const DEFAULT_SIZE: u64 = 20;
+/// 每页最大条数
+pub const MAX_SIZE: u64 = 100;
+
+/// 分页参数
+#[derive(Debug, Default)]
+pub struct PageParams {
+    /// 页码，从 1 开始，默认第 1 页
+    pub page: Option<u64>,
+    /// 每页条数，默认 [`DEFAULT_SIZE`]
+    pub size: Option<u64>,
+    /// 上一页返回的 `nextCursor`
+    pub cursor: Option<String>,
+}
+
+/// 分页结果
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// 满足条件的总条数
+    pub total: u64,
+    /// 当前页码，游标分页时为空
+    pub page: Option<u64>,
+    pub size: u64,
+    /// 获取下一页的游标，没有下一页时为空
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// 转换每条记录，分页信息保持不变
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+            page: self.page,
+            size: self.size,
+            next_cursor: self.next_cursor,
+        }
+    }
+}
+
+/// 排序方式
+#[derive(Debug, Clone, Copy)]
+pub struct Sort<C> {
+    /// 接口中使用的字段名
+    pub field: &'static str,
+    pub column: C,
+    pub desc: bool,
+}
+
+impl<C: Copy> Sort<C> {
+    /// 解析 `sort` 参数，字段名前加 `-` 表示倒序，只允许按 `allowed` 中的字段排序，为空时使用第一个字段正序
+    pub fn parse(sort: Option<&str>, allowed: &[(&'static str, C)]) -> Result<Self, AppError> {
+        let sort = sort.map(str::trim).filter(|sort| !sort.is_empty());
+        let (name, desc) = match sort {
+            Some(sort) => match sort.strip_prefix('-') {
+                Some(name) => (name, true),
+                None => (sort, false),
+            },
+            None => (allowed[0].0, false),
+        };
+        let (field, column) = allowed
+            .iter()
+            .find(|(field, _)| *field == name)
+            .copied()
+            .ok_or_else(|| {
+                let fields: Vec<_> = allowed.iter().map(|(field, _)| *field).collect();
+                AppError::BadRequest(format!(
+                    "不支持按 {name} 排序，可选字段: {}",
+                    fields.join(", ")
+                ))
+            })?;
+        Ok(Sort {
+            field,
+            column,
+            desc,
+        })
+    }
+
+    /// 游标中记录的排序方式，排序方式变化后旧游标失效
+    fn key(&self) -> String {
+        format!("{}{}", if self.desc { "-" } else { "" }, self.field)
+    }
+}
+
+/// 游标中保存的排序字段值
+#[derive(Debug, Serialize, Deserialize)]
+enum CursorValue {
+    Int(i64),
+    Text(String),
+    Time(DateTime<Utc>),
+}
+
+impl CursorValue {
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Int(Some(value)) => Some(CursorValue::Int(value.into())),
+            Value::BigInt(Some(value)) => Some(CursorValue::Int(value)),
+            Value::String(Some(value)) => Some(CursorValue::Text(*value)),
+            Value::ChronoDateTimeUtc(Some(value)) => Some(CursorValue::Time(*value)),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> Value {
+        match self {
+            CursorValue::Int(value) => value.into(),
+            CursorValue::Text(value) => value.into(),
+            CursorValue::Time(value) => value.into(),
+        }
+    }
+}
+
+/// 上一页最后一条记录的位置
+#[derive(Debug, Serialize, Deserialize)]
+struct Cursor {
+    sort: String,
+    value: CursorValue,
+    id: i64,
+}
+
+impl Cursor {
+    fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    fn decode(cursor: &str, sort_key: &str) -> Result<Self, AppError> {
+        let invalid = || AppError::BadRequest("无效的游标".to_string());
+        let bytes = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+        let cursor: Cursor = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+        if cursor.sort != sort_key {
+            return Err(AppError::BadRequest(
+                "游标与排序方式不一致，请从第一页重新查询".to_string(),
+            ));
+        }
+        Ok(cursor)
+    }
+}
+
+/// 分页查询，`id` 为整数主键，排序字段相同时按主键排序保证顺序稳定
+pub async fn paginate<E, C>(
+    db: &C,
+    select: Select<E>,
+    id: E::Column,
+    sort: &Sort<E::Column>,
+    params: &PageParams,
+) -> Result<Page<E::Model>, AppError>
+where
+    E: EntityTrait,
+    E::Model: Sync,
+    C: ConnectionTrait,
+{
+    let size = params.size.unwrap_or(DEFAULT_SIZE);
+    if !(1..=MAX_SIZE).contains(&size) {
+        return Err(AppError::BadRequest(format!(
+            "size 必须在 1 到 {MAX_SIZE} 之间"
+        )));
+    }
+    let total = select.clone().count(db).await?;
+    let order = if sort.desc { Order::Desc } else { Order::Asc };
+    let mut query = select
+        .order_by(sort.column, order.clone())
+        .order_by(id, order);
+    let page = match &params.cursor {
+        Some(cursor) => {
+            let cursor = Cursor::decode(cursor, &sort.key())?;
+            let value = cursor.value.into_value();
+            let (after, after_id) = if sort.desc {
+                (sort.column.lt(value.clone()), id.lt(cursor.id))
+            } else {
+                (sort.column.gt(value.clone()), id.gt(cursor.id))
+            };
+            query = query.filter(
+                Condition::any()
+                    .add(after)
+                    .add(Condition::all().add(sort.column.eq(value)).add(after_id)),
+            );
+            None
+        }
+        None => {
+            let page = params.page.unwrap_or(1);
+            if page == 0 {
+                return Err(AppError::BadRequest("page 从 1 开始".to_string()));
+            }
+            query = query.offset((page - 1).saturating_mul(size));
+            Some(page)
+        }
+    };
+    // 多查询一条用于判断是否还有下一页
+    let mut items = query.limit(size + 1).all(db).await?;
+    let next_cursor = if items.len() as u64 > size {
+        items.truncate(size as usize);
+        items
+            .last()
+            .map(|last| next_cursor(last, id, sort))
+            .transpose()?
+    } else {
+        None
+    };
+    Ok(Page {
+        items,
+        total,
+        page,
+        size,
+        next_cursor,
+    })
+}
+
+fn next_cursor<M: ModelTrait>(
+    last: &M,
+    id: <M::Entity as EntityTrait>::Column,
+    sort: &Sort<<M::Entity as EntityTrait>::Column>,
+) -> Result<String, AppError> {
+    let unsupported = || AppError::InternalError(format!("字段 {} 不支持游标分页", sort.field));
+    let value = CursorValue::from_value(last.get(sort.column)).ok_or_else(unsupported)?;
+    let id = match CursorValue::from_value(last.get(id)) {
+        Some(CursorValue::Int(id)) => id,
+        _ => return Err(unsupported()),
+    };
+    Ok(Cursor {
+        sort: sort.key(),
+        value,
+        id,
+    }
+    .encode())
+}
@@ -0,0 +1,52 @@
+use crate::app_config::password::Password;
+use crate::errors::AppError;
+use crate::state::ARGON2_SALT;
+use base64::{Engine, engine::general_purpose::STANDARD_NO_PAD};
+use std::sync::OnceLock;
+
+/// 随机盐值长度 (字节)
+const SALT_LEN: usize = 16;
+
+/// 使用随机盐值计算密码哈希，返回 PHC 格式的字符串
+pub fn hash_password(password: &str, config: &Password) -> Result<String, AppError> {
+    let salt: [u8; SALT_LEN] = rand::random();
+    argon2::hash_encoded(password.as_bytes(), &salt, &config.argon2_config())
+        .map_err(|e| AppError::InternalError(format!("Password hashing error: {e}")))
+}
+
+/// 校验密码与哈希是否匹配，哈希中自带算法参数与盐值
+pub fn verify_password(hash: &str, password: &str) -> Result<bool, AppError> {
+    argon2::verify_encoded(hash, password.as_bytes())
+        .map_err(|e| AppError::InternalError(format!("Password verification error: {e}")))
+}
+
+/// 判断哈希是否需要重新计算
+///
+/// 以下情况返回 `true`:
+/// - 不是 argon2id / v19 算法
+/// - 内存、迭代次数、并行度与当前配置不一致
+/// - 使用了旧版本所有用户共用的盐值 `ARGON2_SALT`
+pub fn needs_rehash(hash: &str, config: &Password) -> bool {
+    // $argon2id$v=19$m=19456,t=2,p=1$<salt>$<hash>
+    let parts: Vec<&str> = hash.split('$').collect();
+    let [_, variant, version, params, salt, _] = parts[..] else {
+        return true;
+    };
+    let expected_params = format!(
+        "m={},t={},p={}",
+        config.mem_cost, config.time_cost, config.lanes
+    );
+    variant != "argon2id"
+        || version != "v=19"
+        || params != expected_params
+        || salt == STANDARD_NO_PAD.encode(ARGON2_SALT)
+}
+
+/// 用户不存在时执行一次同等开销的校验，避免通过响应时间判断邮箱是否已注册
+pub fn dummy_verify(password: &str, config: &Password) {
+    static DUMMY_HASH: OnceLock<Option<String>> = OnceLock::new();
+    let dummy_hash = DUMMY_HASH.get_or_init(|| hash_password("dummy-password", config).ok());
+    if let Some(hash) = dummy_hash {
+        let _ = verify_password(hash, password);
+    }
+}
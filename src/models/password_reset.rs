@@ -0,0 +1,68 @@
+use crate::entity::{devices, users};
+use crate::errors::AppError;
+use crate::models::mailer::Email;
+use crate::models::password::hash_password;
+use crate::models::user_token::{self, purpose};
+use crate::state::AppState;
+use chrono::{Duration, Utc};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, TransactionTrait};
+
+/// 签发重置令牌并发送找回密码邮件，两次发送的间隔小于 `resend_interval` 时返回 `TooManyRequests`
+pub async fn send_reset_email(user: &users::Model, app_data: &AppState) -> Result<(), AppError> {
+    let config = &app_data.config.password_reset;
+    let interval = Duration::seconds(config.resend_interval);
+    if let Some(last) = user_token::last_issued(app_data, user.id, purpose::RESET_PASSWORD).await? {
+        let wait = last + interval - Utc::now();
+        if wait > Duration::zero() {
+            return Err(AppError::TooManyRequests(format!(
+                "发送过于频繁，请 {} 秒后再试",
+                wait.num_seconds() + 1
+            )));
+        }
+    }
+    let token = user_token::issue(
+        app_data,
+        user.id,
+        purpose::RESET_PASSWORD,
+        config.token_expire,
+    )
+    .await?;
+    let email = Email {
+        to: user.email.clone(),
+        subject: "重置你的密码".to_string(),
+        body: format!(
+            "{}，你好:\n\n请在 {} 分钟内打开以下链接设置新密码:\n{}?token={}\n\n如果不是你本人操作，请忽略这封邮件，你的密码不会被修改。\n",
+            user.name,
+            config.token_expire / 60,
+            config.link_url,
+            token
+        ),
+    };
+    app_data.mailer.send(&email).await
+}
+
+/// 更新用户密码并撤销该用户的所有设备会话，返回撤销的会话数量
+pub async fn update_password(
+    user_id: i64,
+    new_password: &str,
+    app_data: &AppState,
+) -> Result<u64, AppError> {
+    let hashed_password = hash_password(new_password, &app_data.config.password)?;
+    let txn = app_data.db_pool.begin().await?;
+    users::Entity::update_many()
+        .col_expr(users::Column::PassWord, hashed_password.into())
+        .col_expr(users::Column::UpdateTime, Utc::now().into())
+        .filter(users::Column::Id.eq(user_id))
+        .exec(&txn)
+        .await?;
+    let delete_result = devices::Entity::delete_many()
+        .filter(devices::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+    txn.commit().await?;
+    info!(
+        "用户 {user_id} 修改了密码，撤销会话 {} 个",
+        delete_result.rows_affected
+    );
+    Ok(delete_result.rows_affected)
+}
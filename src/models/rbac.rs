@@ -0,0 +1,259 @@
+use crate::entity::{permissions, role_permissions, roles, user_roles, users};
+use crate::errors::AppError;
+use crate::models::auth::AuthUser;
+use crate::state::AppState;
+use actix_web::{FromRequest, HttpMessage, HttpRequest, dev::Payload, web};
+use chrono::Utc;
+use futures::future::LocalBoxFuture;
+use sea_orm::{
+    ActiveValue::NotSet, ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait, QueryFilter,
+    QuerySelect, QueryTrait, Set, sea_query::OnConflict,
+};
+use std::collections::HashSet;
+use std::rc::Rc;
+
+/// 角色名称
+pub mod role {
+    pub const ADMIN: &str = "admin";
+    pub const TEACHER: &str = "teacher";
+    pub const STUDENT: &str = "student";
+}
+
+/// 权限编码
+pub mod perm {
+    /// 查询用户
+    pub const USER_READ: &str = "user:read";
+    /// 删除用户
+    pub const USER_DELETE: &str = "user:delete";
+    /// 修改其他用户的资料
+    pub const USER_UPDATE: &str = "user:update";
+    /// 强制用户退出登录
+    pub const USER_LOGOUT: &str = "user:logout";
+    /// 授予与撤销角色
+    pub const ROLE_MANAGE: &str = "role:manage";
+    /// 查看与清除登录锁定
+    pub const LOGIN_LOCKOUT: &str = "login:lockout";
+    /// 重置用户的两步验证
+    pub const USER_RESET_2FA: &str = "user:reset_2fa";
+    /// 管理 OAuth2 客户端
+    pub const OAUTH_CLIENT: &str = "oauth:client";
+    /// 查询审计日志
+    pub const AUDIT_READ: &str = "audit:read";
+}
+
+/// 新注册用户的默认角色
+pub const DEFAULT_ROLE: &str = role::STUDENT;
+
+/// 写入内置角色与权限，已存在的记录保持不变，由数据库迁移调用
+///
+/// - `roles` (名称, 描述)
+/// - `permissions` (编码, 描述)
+/// - `role_permissions` (角色, 权限编码)
+///
+/// 迁移中写入的数据需要直接写在迁移文件里，不能引用会随代码变化的常量
+pub async fn seed<C: ConnectionTrait>(
+    db: &C,
+    roles: &[(&str, &str)],
+    permissions: &[(&str, &str)],
+    role_permissions: &[(&str, &str)],
+) -> Result<(), DbErr> {
+    for &(name, description) in roles {
+        roles::Entity::insert(roles::ActiveModel {
+            id: NotSet,
+            name: Set(name.to_string()),
+            description: Set(description.to_string()),
+            create_time: Set(Utc::now()),
+        })
+        .on_conflict(
+            OnConflict::column(roles::Column::Name)
+                .do_nothing_on([roles::Column::Name])
+                .to_owned(),
+        )
+        .do_nothing()
+        .exec(db)
+        .await?;
+    }
+    for &(code, description) in permissions {
+        permissions::Entity::insert(permissions::ActiveModel {
+            id: NotSet,
+            code: Set(code.to_string()),
+            description: Set(description.to_string()),
+        })
+        .on_conflict(
+            OnConflict::column(permissions::Column::Code)
+                .do_nothing_on([permissions::Column::Code])
+                .to_owned(),
+        )
+        .do_nothing()
+        .exec(db)
+        .await?;
+    }
+    for &(role_name, code) in role_permissions {
+        let role = roles::Entity::find()
+            .filter(roles::Column::Name.eq(role_name))
+            .one(db)
+            .await?;
+        let permission = permissions::Entity::find()
+            .filter(permissions::Column::Code.eq(code))
+            .one(db)
+            .await?;
+        let (Some(role), Some(permission)) = (role, permission) else {
+            continue;
+        };
+        role_permissions::Entity::insert(role_permissions::ActiveModel {
+            role_id: Set(role.id),
+            permission_id: Set(permission.id),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                role_permissions::Column::RoleId,
+                role_permissions::Column::PermissionId,
+            ])
+            .do_nothing_on([role_permissions::Column::RoleId])
+            .to_owned(),
+        )
+        .do_nothing()
+        .exec(db)
+        .await?;
+    }
+    Ok(())
+}
+
+/// 数据库中所有的权限编码，用于校验 OAuth2 客户端与 API Key 的授权范围
+pub async fn permission_codes<C: ConnectionTrait>(db: &C) -> Result<HashSet<String>, AppError> {
+    Ok(permissions::Entity::find()
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|permission| permission.code)
+        .collect())
+}
+
+/// 为用户授予角色，已拥有时忽略
+pub async fn assign_role<C: ConnectionTrait>(
+    db: &C,
+    user_id: i64,
+    role_name: &str,
+) -> Result<(), AppError> {
+    let role = roles::Entity::find()
+        .filter(roles::Column::Name.eq(role_name))
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("角色 {role_name} 不存在")))?;
+    user_roles::Entity::insert(user_roles::ActiveModel {
+        user_id: Set(user_id),
+        role_id: Set(role.id),
+        create_time: Set(Utc::now()),
+    })
+    .on_conflict(
+        OnConflict::columns([user_roles::Column::UserId, user_roles::Column::RoleId])
+            .do_nothing_on([user_roles::Column::UserId])
+            .to_owned(),
+    )
+    .do_nothing()
+    .exec(db)
+    .await?;
+    Ok(())
+}
+
+struct PermissionSet {
+    roles: HashSet<String>,
+    codes: HashSet<String>,
+}
+
+/// 当前登录用户的角色与权限
+///
+/// 首次提取时查询数据库，结果缓存在请求扩展中，同一请求内多次提取或检查不会重复查询
+#[derive(Clone)]
+pub struct Permissions {
+    pub user: AuthUser,
+    inner: Rc<PermissionSet>,
+}
+
+impl Permissions {
+    /// 是否拥有某个权限
+    pub fn has(&self, code: &str) -> bool {
+        self.inner.codes.contains(code)
+    }
+
+    /// 是否拥有某个角色
+    pub fn has_role(&self, role_name: &str) -> bool {
+        self.inner.roles.contains(role_name)
+    }
+
+    /// 要求拥有某个权限，否则返回 `Forbidden`
+    pub fn require(&self, code: &str) -> Result<(), AppError> {
+        if self.has(code) {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden(format!("缺少权限: {code}"))
+                .with_code(crate::errors::code::PERMISSION_DENIED))
+        }
+    }
+
+    async fn load(user: &AuthUser, app_data: &AppState) -> Result<PermissionSet, AppError> {
+        // `auth.admins` 中配置的邮箱视为管理员，用于初始化第一个管理员账号
+        // 只认可已验证邮箱的账号，避免他人抢先注册管理员邮箱获得权限
+        let role_ids = user_roles::Entity::find()
+            .select_only()
+            .column(user_roles::Column::RoleId)
+            .filter(user_roles::Column::UserId.eq(user.id))
+            .into_query();
+        let mut condition = Condition::any().add(roles::Column::Id.in_subquery(role_ids));
+        if user.status == users::STATUS_NORMAL && app_data.config.auth.is_admin(&user.email) {
+            condition = condition.add(roles::Column::Name.eq(role::ADMIN));
+        }
+        let role_list = roles::Entity::find()
+            .filter(condition)
+            .all(&app_data.db_pool)
+            .await?;
+
+        // OAuth2 授权访问时只保留授权范围内的权限
+        let scope: Option<HashSet<&str>> = user
+            .scope
+            .as_deref()
+            .map(|scope| scope.split_whitespace().collect());
+        let codes = permissions::Entity::find()
+            .inner_join(role_permissions::Entity)
+            .filter(role_permissions::Column::RoleId.is_in(role_list.iter().map(|role| role.id)))
+            .all(&app_data.db_pool)
+            .await?
+            .into_iter()
+            .map(|permission| permission.code)
+            .filter(|code| {
+                scope
+                    .as_ref()
+                    .is_none_or(|scope| scope.contains(code.as_str()))
+            })
+            .collect();
+        Ok(PermissionSet {
+            roles: role_list.into_iter().map(|role| role.name).collect(),
+            codes,
+        })
+    }
+}
+
+impl FromRequest for Permissions {
+    type Error = AppError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        let auth_user = AuthUser::from_extensions(&req);
+        Box::pin(async move {
+            let user = auth_user?;
+            if let Some(inner) = req.extensions().get::<Rc<PermissionSet>>() {
+                return Ok(Permissions {
+                    user,
+                    inner: inner.clone(),
+                });
+            }
+            let app_data = req
+                .app_data::<web::Data<AppState>>()
+                .ok_or_else(|| AppError::InternalError("应用状态未初始化".to_string()))?;
+            let inner = Rc::new(Permissions::load(&user, app_data).await?);
+            req.extensions_mut().insert(inner.clone());
+            Ok(Permissions { user, inner })
+        })
+    }
+}
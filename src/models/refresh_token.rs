@@ -0,0 +1,54 @@
+use crate::models::secure_token;
+use std::fmt;
+
+/// 不透明的刷新令牌，格式为 `<family>.<secret>`
+///
+/// - `family` 标识一个设备上的刷新令牌链，轮换时保持不变
+/// - `secret` 每次轮换都重新生成，数据库中只保存其摘要
+///
+/// 如果某个 `family` 收到的 `secret` 与当前记录不一致，说明旧令牌被重复使用，整条链需要作废
+pub struct RefreshToken {
+    pub family: String,
+    secret: String,
+}
+
+impl RefreshToken {
+    /// 登录时创建新的令牌链
+    pub fn new_family() -> Self {
+        Self {
+            family: uuid::Uuid::new_v4().simple().to_string(),
+            secret: secure_token::generate(),
+        }
+    }
+
+    /// 轮换令牌，保留 `family`
+    pub fn rotate(&self) -> Self {
+        Self {
+            family: self.family.clone(),
+            secret: secure_token::generate(),
+        }
+    }
+
+    /// 解析客户端提交的刷新令牌
+    pub fn parse(token: &str) -> Option<Self> {
+        let (family, secret) = token.split_once('.')?;
+        if family.is_empty() || secret.is_empty() {
+            return None;
+        }
+        Some(Self {
+            family: family.to_string(),
+            secret: secret.to_string(),
+        })
+    }
+
+    /// 数据库中保存的摘要
+    pub fn hash(&self) -> String {
+        secure_token::digest(&self.secret)
+    }
+}
+
+impl fmt::Display for RefreshToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.family, self.secret)
+    }
+}
@@ -0,0 +1,15 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use sha2::{Digest, Sha256};
+
+/// 生成 32 字节随机数并编码为 URL 安全的字符串，用于不透明令牌
+pub fn generate() -> String {
+    let bytes: [u8; 32] = rand::random();
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// 计算令牌的 SHA-256 摘要，数据库中只保存摘要
+///
+/// 随机令牌本身熵足够高，不需要 argon2 这类慢哈希
+pub fn digest(token: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(token.as_bytes()))
+}
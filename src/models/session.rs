@@ -0,0 +1,177 @@
+use crate::entity::{devices, oauth_clients, users};
+use crate::errors::AppError;
+use crate::models::client_info::ClientInfo;
+use crate::models::refresh_token::RefreshToken;
+use crate::models::token::generate_scoped_token;
+use crate::state::AppState;
+use chrono::{Duration, Utc};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::NotSet, ColumnTrait, EntityTrait, ModelTrait, QueryFilter, Set,
+};
+use utoipa::ToSchema;
+
+/// 登录或刷新成功后签发给客户端的令牌
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenPair {
+    /// 短期访问令牌 (JWT)
+    pub token: String,
+    /// 不透明的刷新令牌，每次使用后轮换
+    pub refresh_token: String,
+    /// 访问令牌有效期 (秒)
+    pub expires_in: i64,
+}
+
+/// 为用户创建新的设备会话并签发令牌
+pub async fn create_session(
+    user: &users::Model,
+    client: &ClientInfo,
+    app_data: &AppState,
+) -> Result<TokenPair, AppError> {
+    insert_device(user, client, client.device_name(), None, app_data).await
+}
+
+/// 用户授权第三方应用后创建的会话，令牌只包含授权的范围
+pub async fn create_grant(
+    user: &users::Model,
+    oauth_client: &oauth_clients::Model,
+    scope: &str,
+    client: &ClientInfo,
+    app_data: &AppState,
+) -> Result<TokenPair, AppError> {
+    insert_device(
+        user,
+        client,
+        Some(oauth_client.name.clone()),
+        Some((&oauth_client.client_id, scope)),
+        app_data,
+    )
+    .await
+}
+
+async fn insert_device(
+    user: &users::Model,
+    client: &ClientInfo,
+    name: Option<String>,
+    grant: Option<(&str, &str)>,
+    app_data: &AppState,
+) -> Result<TokenPair, AppError> {
+    let jwt_config = &app_data.config.jwt;
+    let (client_id, scope) = grant.unzip();
+    let token = generate_scoped_token(
+        &app_data.jwt_keys,
+        &user.email,
+        jwt_config.access_token_expire,
+        client_id,
+        scope,
+    )?;
+    let refresh_token = RefreshToken::new_family();
+    let device = devices::ActiveModel {
+        id: NotSet,
+        user_id: Set(user.id),
+        token: Set(token.clone()),
+        refresh_family: Set(Some(refresh_token.family.clone())),
+        refresh_token_hash: Set(Some(refresh_token.hash())),
+        refresh_expire_time: Set(Some(
+            Utc::now() + Duration::seconds(jwt_config.refresh_token_expire),
+        )),
+        name: Set(name),
+        ip: Set(client.ip.clone()),
+        user_agent: Set(client.user_agent.clone()),
+        client_id: Set(client_id.map(str::to_string)),
+        scope: Set(scope.map(str::to_string)),
+        create_time: Set(Utc::now()),
+        update_time: Set(Utc::now()),
+    };
+    device.insert(&app_data.db_pool).await?;
+    Ok(TokenPair {
+        token,
+        refresh_token: refresh_token.to_string(),
+        expires_in: jwt_config.access_token_expire,
+    })
+}
+
+/// 使用刷新令牌换取新的令牌，旧的刷新令牌立即失效
+///
+/// 如果提交的是已经轮换过的旧令牌，视为令牌泄露，整个设备会话被撤销。
+/// `client_id` 为发起刷新的 OAuth2 客户端，本站登录为 `None`，与签发时不一致的令牌无效
+pub async fn refresh_session(
+    refresh_token: &str,
+    client_id: Option<&str>,
+    client: &ClientInfo,
+    app_data: &AppState,
+) -> Result<TokenPair, AppError> {
+    let invalid = || AppError::Unauthorized("刷新令牌无效或已过期".to_string());
+    let presented = RefreshToken::parse(refresh_token).ok_or_else(invalid)?;
+    let device = devices::Entity::find()
+        .filter(devices::Column::RefreshFamily.eq(&presented.family))
+        .one(&app_data.db_pool)
+        .await?
+        .filter(|device| device.client_id.as_deref() == client_id)
+        .ok_or_else(invalid)?;
+
+    let presented_hash = presented.hash();
+    if device.refresh_token_hash.as_deref() != Some(presented_hash.as_str()) {
+        warn!(
+            "设备 {} (用户 {}) 的刷新令牌被重复使用，撤销该设备会话",
+            device.id, device.user_id
+        );
+        device.delete(&app_data.db_pool).await?;
+        return Err(invalid());
+    }
+    if device
+        .refresh_expire_time
+        .is_none_or(|expire_time| expire_time <= Utc::now())
+    {
+        device.delete(&app_data.db_pool).await?;
+        return Err(invalid());
+    }
+
+    let user = users::Entity::find_active_by_id(device.user_id)
+        .one(&app_data.db_pool)
+        .await?
+        .ok_or_else(invalid)?;
+    let jwt_config = &app_data.config.jwt;
+    let token = generate_scoped_token(
+        &app_data.jwt_keys,
+        &user.email,
+        jwt_config.access_token_expire,
+        device.client_id.as_deref(),
+        device.scope.as_deref(),
+    )?;
+    let rotated = presented.rotate();
+
+    // 以旧摘要为条件更新，并发刷新时只有一个请求能成功，另一个按重复使用处理
+    let update_result = devices::Entity::update_many()
+        .col_expr(devices::Column::Token, token.clone().into())
+        .col_expr(
+            devices::Column::RefreshTokenHash,
+            Some(rotated.hash()).into(),
+        )
+        .col_expr(
+            devices::Column::RefreshExpireTime,
+            Some(Utc::now() + Duration::seconds(jwt_config.refresh_token_expire)).into(),
+        )
+        .col_expr(devices::Column::UpdateTime, Utc::now().into())
+        .col_expr(devices::Column::Ip, client.ip.clone().into())
+        .filter(devices::Column::Id.eq(device.id))
+        .filter(devices::Column::RefreshTokenHash.eq(presented_hash))
+        .exec(&app_data.db_pool)
+        .await?;
+    if update_result.rows_affected == 0 {
+        warn!(
+            "设备 {} (用户 {}) 的刷新令牌被并发使用，撤销该设备会话",
+            device.id, device.user_id
+        );
+        devices::Entity::delete_by_id(device.id)
+            .exec(&app_data.db_pool)
+            .await?;
+        return Err(invalid());
+    }
+
+    Ok(TokenPair {
+        token,
+        refresh_token: rotated.to_string(),
+        expires_in: jwt_config.access_token_expire,
+    })
+}
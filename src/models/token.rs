@@ -1,38 +1,49 @@
-use crate::state::{ARGON2_SALT, TOKEN_EXPIRE_TIME};
+use crate::models::keyring::KeyRing;
 use chrono::{Duration, Utc};
-use jsonwebtoken::{
-    DecodingKey, EncodingKey, Header, TokenData, Validation, decode, encode,
-    errors::Result as JwtResult,
-};
+use jsonwebtoken::{TokenData, errors::Result as JwtResult};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
     pub exp: usize,
+    /// 令牌唯一 ID，保证同一秒内签发的 token 也互不相同
+    #[serde(default)]
+    pub jti: String,
+    /// OAuth2 客户端 ID，本站登录签发的 token 没有该字段
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    /// OAuth2 授权范围，空格分隔
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
 }
 
-pub fn generate_token(sub: &str) -> String {
+pub fn generate_token(keys: &KeyRing, sub: &str, expire_secs: i64) -> JwtResult<String> {
+    generate_scoped_token(keys, sub, expire_secs, None, None)
+}
+
+/// 签发带 OAuth2 客户端与授权范围的 token
+pub fn generate_scoped_token(
+    keys: &KeyRing,
+    sub: &str,
+    expire_secs: i64,
+    client_id: Option<&str>,
+    scope: Option<&str>,
+) -> JwtResult<String> {
     let expiration = Utc::now()
-        .checked_add_signed(Duration::seconds(TOKEN_EXPIRE_TIME))
+        .checked_add_signed(Duration::seconds(expire_secs))
         .expect("valid timestamp")
         .timestamp() as usize;
     let claims = Claims {
         sub: sub.to_owned(),
         exp: expiration,
+        jti: uuid::Uuid::new_v4().simple().to_string(),
+        client_id: client_id.map(str::to_string),
+        scope: scope.map(str::to_string),
     };
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(ARGON2_SALT),
-    )
-    .unwrap()
+    keys.sign(&claims)
 }
 
-pub fn verify_token(token: &str) -> JwtResult<TokenData<Claims>> {
-    decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(ARGON2_SALT),
-        &Validation::default(),
-    )
+pub fn verify_token(keys: &KeyRing, token: &str) -> JwtResult<TokenData<Claims>> {
+    keys.verify(token)
 }
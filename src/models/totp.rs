@@ -0,0 +1,70 @@
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+
+/// 时间步长 (秒)
+pub const STEP: i64 = 30;
+/// 验证码位数
+pub const DIGITS: u32 = 6;
+/// 共享密钥长度 (字节)，RFC 4226 推荐 160 位
+const SECRET_LEN: usize = 20;
+
+/// 生成 Base32 编码的随机共享密钥
+pub fn generate_secret() -> String {
+    let bytes: [u8; SECRET_LEN] = rand::random();
+    BASE32_NOPAD.encode(&bytes)
+}
+
+/// 计算某个时间步的验证码 (RFC 6238, HMAC-SHA1)
+fn code_at(key: &[u8], step: i64) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(&step.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+    format!(
+        "{:0width$}",
+        binary % 10u32.pow(DIGITS),
+        width = DIGITS as usize
+    )
+}
+
+/// 校验验证码，允许前后 `skew` 个时间步的时钟偏差
+///
+/// 匹配成功时返回验证码所属的时间步，调用方据此拒绝同一时间步内的重复使用
+pub fn verify(secret: &str, code: &str, timestamp: i64, skew: i64) -> Option<i64> {
+    let code = code.trim();
+    if code.len() != DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let key = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+    let current = timestamp.div_euclid(STEP);
+    (current - skew..=current + skew).find(|step| code_at(&key, *step) == code)
+}
+
+/// 生成身份验证器 App 扫码用的 otpauth URI
+pub fn otpauth_uri(issuer: &str, account: &str, secret: &str) -> String {
+    format!(
+        "otpauth://totp/{}:{}?secret={secret}&issuer={}&algorithm=SHA1&digits={DIGITS}&period={STEP}",
+        percent_encode(issuer),
+        percent_encode(account),
+        percent_encode(issuer),
+    )
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
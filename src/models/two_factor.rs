@@ -0,0 +1,196 @@
+use crate::entity::{recovery_codes, user_totp, users};
+use crate::errors::{AppError, code};
+use crate::models::{secure_token, totp};
+use crate::state::AppState;
+use chrono::Utc;
+use rand::Rng;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::NotSet, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    Set, TransactionTrait,
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// 恢复码字符集，去掉了容易混淆的 0/o/1/l/i
+const RECOVERY_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+/// 开始绑定时返回给客户端的密钥信息
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpSetup {
+    /// Base32 编码的共享密钥，用于手动输入
+    pub secret: String,
+    /// otpauth URI，用于生成二维码
+    pub otpauth_uri: String,
+}
+
+/// 用户是否已启用两步验证
+pub async fn is_enabled<C: ConnectionTrait>(db: &C, user_id: i64) -> Result<bool, AppError> {
+    Ok(user_totp::Entity::find_by_id(user_id)
+        .one(db)
+        .await?
+        .is_some_and(|totp| totp.enabled))
+}
+
+/// 为用户生成新的 TOTP 密钥，需要调用 `confirm_setup` 确认后才会启用
+///
+/// 重复调用会覆盖尚未确认的密钥，已启用时返回 `Conflict`
+pub async fn begin_setup(user: &users::Model, app_data: &AppState) -> Result<TotpSetup, AppError> {
+    let existing = user_totp::Entity::find_by_id(user.id)
+        .one(&app_data.db_pool)
+        .await?;
+    if existing.as_ref().is_some_and(|totp| totp.enabled) {
+        return Err(AppError::Conflict("已启用两步验证".to_string()));
+    }
+    let secret = totp::generate_secret();
+    let model = user_totp::ActiveModel {
+        user_id: Set(user.id),
+        secret: Set(secret.clone()),
+        enabled: Set(false),
+        last_used_step: Set(0),
+        create_time: Set(Utc::now()),
+        enable_time: Set(None),
+    };
+    if existing.is_some() {
+        model.update(&app_data.db_pool).await?;
+    } else {
+        model.insert(&app_data.db_pool).await?;
+    }
+    Ok(TotpSetup {
+        otpauth_uri: totp::otpauth_uri(&app_data.config.two_factor.issuer, &user.email, &secret),
+        secret,
+    })
+}
+
+/// 使用首个验证码确认启用两步验证，返回一次性恢复码明文
+pub async fn confirm_setup(
+    user_id: i64,
+    code: &str,
+    app_data: &AppState,
+) -> Result<Vec<String>, AppError> {
+    let totp_model = user_totp::Entity::find_by_id(user_id)
+        .one(&app_data.db_pool)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("请先生成两步验证密钥".to_string()))?;
+    if totp_model.enabled {
+        return Err(AppError::Conflict("已启用两步验证".to_string()));
+    }
+    let step = totp::verify(
+        &totp_model.secret,
+        code,
+        Utc::now().timestamp(),
+        app_data.config.two_factor.skew,
+    )
+    .ok_or_else(|| {
+        AppError::BadRequest("验证码错误".to_string()).with_code(code::INVALID_TWO_FACTOR_CODE)
+    })?;
+
+    let txn = app_data.db_pool.begin().await?;
+    user_totp::Entity::update_many()
+        .col_expr(user_totp::Column::Enabled, true.into())
+        .col_expr(user_totp::Column::LastUsedStep, step.into())
+        .col_expr(user_totp::Column::EnableTime, Some(Utc::now()).into())
+        .filter(user_totp::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+    let codes =
+        regenerate_recovery_codes(&txn, user_id, app_data.config.two_factor.recovery_codes).await?;
+    txn.commit().await?;
+    info!("用户 {user_id} 启用了两步验证");
+    Ok(codes)
+}
+
+/// 删除旧的恢复码并生成新的一组，返回明文
+async fn regenerate_recovery_codes<C: ConnectionTrait>(
+    db: &C,
+    user_id: i64,
+    count: usize,
+) -> Result<Vec<String>, AppError> {
+    recovery_codes::Entity::delete_many()
+        .filter(recovery_codes::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    let mut rng = rand::rng();
+    let codes: Vec<String> = (0..count)
+        .map(|_| {
+            let chars: String = (0..10)
+                .map(|_| RECOVERY_ALPHABET[rng.random_range(0..RECOVERY_ALPHABET.len())] as char)
+                .collect();
+            format!("{}-{}", &chars[..5], &chars[5..])
+        })
+        .collect();
+    for code in &codes {
+        recovery_codes::ActiveModel {
+            id: NotSet,
+            user_id: Set(user_id),
+            code_hash: Set(recovery_code_digest(code)),
+            used_time: Set(None),
+            create_time: Set(Utc::now()),
+        }
+        .insert(db)
+        .await?;
+    }
+    Ok(codes)
+}
+
+/// 恢复码摘要，忽略大小写、空格与连字符
+fn recovery_code_digest(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    secure_token::digest(&normalized)
+}
+
+/// 校验两步验证码，可以是 TOTP 验证码或未使用过的恢复码
+///
+/// TOTP 验证码所在的时间步必须晚于上次使用的时间步，同一个验证码在有效期内不能重复使用
+pub async fn verify_code(user_id: i64, code: &str, app_data: &AppState) -> Result<bool, AppError> {
+    let Some(totp_model) = user_totp::Entity::find_by_id(user_id)
+        .one(&app_data.db_pool)
+        .await?
+        .filter(|totp| totp.enabled)
+    else {
+        return Ok(false);
+    };
+
+    if let Some(step) = totp::verify(
+        &totp_model.secret,
+        code,
+        Utc::now().timestamp(),
+        app_data.config.two_factor.skew,
+    ) {
+        // 以上次时间步为条件更新，并发提交同一个验证码时只有一个请求能成功
+        let update_result = user_totp::Entity::update_many()
+            .col_expr(user_totp::Column::LastUsedStep, step.into())
+            .filter(user_totp::Column::UserId.eq(user_id))
+            .filter(user_totp::Column::LastUsedStep.lt(step))
+            .exec(&app_data.db_pool)
+            .await?;
+        return Ok(update_result.rows_affected > 0);
+    }
+
+    let update_result = recovery_codes::Entity::update_many()
+        .col_expr(recovery_codes::Column::UsedTime, Some(Utc::now()).into())
+        .filter(recovery_codes::Column::UserId.eq(user_id))
+        .filter(recovery_codes::Column::CodeHash.eq(recovery_code_digest(code)))
+        .filter(recovery_codes::Column::UsedTime.is_null())
+        .exec(&app_data.db_pool)
+        .await?;
+    if update_result.rows_affected > 0 {
+        warn!("用户 {user_id} 使用恢复码通过了两步验证");
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+/// 关闭两步验证并删除密钥与恢复码，返回之前是否存在密钥
+pub async fn disable<C: ConnectionTrait>(db: &C, user_id: i64) -> Result<bool, AppError> {
+    recovery_codes::Entity::delete_many()
+        .filter(recovery_codes::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    let delete_result = user_totp::Entity::delete_by_id(user_id).exec(db).await?;
+    Ok(delete_result.rows_affected > 0)
+}
@@ -0,0 +1,135 @@
+//! 删除用户: 先标记为已删除，保留期内可以恢复，过期后由后台任务彻底删除
+
+use crate::app_config::user_deletion::UserDeletion;
+use crate::entity::{devices, user_tokens, users};
+use crate::errors::{AppError, code};
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    QuerySelect, Set, TransactionTrait, sea_query::Expr,
+};
+
+/// 每批彻底删除的用户数量
+const PURGE_BATCH: u64 = 500;
+
+/// 已删除用户可以恢复的截止时间
+pub fn restore_deadline(deleted_at: DateTime<Utc>, config: &UserDeletion) -> DateTime<Utc> {
+    deleted_at + Duration::days(config.retention_days as i64)
+}
+
+/// 标记删除用户，同时撤销该用户的所有会话与未使用的邮件令牌，用户不存在或已删除时返回 `false`
+pub async fn soft_delete(db: &DatabaseConnection, user_id: i64) -> Result<bool, DbErr> {
+    let now = Utc::now();
+    let txn = db.begin().await?;
+    // MySQL 按顺序执行赋值，需要先保存删除前的状态再修改
+    let result = users::Entity::update_many()
+        .col_expr(
+            users::Column::StatusBeforeDelete,
+            Expr::col(users::Column::Status).into(),
+        )
+        .col_expr(users::Column::Status, users::STATUS_DELETED.into())
+        .col_expr(users::Column::DeletedAt, now.into())
+        .col_expr(users::Column::UpdateTime, now.into())
+        .filter(users::Column::Id.eq(user_id))
+        .filter(users::Column::Status.ne(users::STATUS_DELETED))
+        .exec(&txn)
+        .await?;
+    if result.rows_affected == 0 {
+        return Ok(false);
+    }
+    devices::Entity::delete_many()
+        .filter(devices::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+    user_tokens::Entity::delete_many()
+        .filter(user_tokens::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+    txn.commit().await?;
+    Ok(true)
+}
+
+/// 恢复保留期内的已删除用户，还原删除前的状态，需要重新登录
+pub async fn restore(
+    db: &DatabaseConnection,
+    user_id: i64,
+    config: &UserDeletion,
+) -> Result<users::Model, AppError> {
+    let user = users::Entity::find_by_id(user_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound(format!("用户ID {user_id} 不存在")).with_code(code::USER_NOT_FOUND)
+        })?;
+    let deleted_at = match user.deleted_at {
+        Some(deleted_at) if user.status == users::STATUS_DELETED => deleted_at,
+        _ => return Err(AppError::Conflict(format!("用户ID {user_id} 未被删除"))),
+    };
+    if restore_deadline(deleted_at, config) <= Utc::now() {
+        return Err(
+            AppError::Conflict(format!("用户ID {user_id} 已超过保留期，无法恢复"))
+                .with_code(code::RESTORE_EXPIRED),
+        );
+    }
+    let status = user
+        .status_before_delete
+        .clone()
+        .unwrap_or_else(|| users::STATUS_NORMAL.to_string());
+    let mut active: users::ActiveModel = user.into();
+    active.status = Set(status);
+    active.deleted_at = Set(None);
+    active.status_before_delete = Set(None);
+    Ok(active.update(db).await?)
+}
+
+/// 彻底删除超过保留期的用户，关联数据随外键一并删除，返回删除的用户数量
+pub async fn purge_expired(db: &DatabaseConnection, config: &UserDeletion) -> Result<u64, DbErr> {
+    let cutoff = Utc::now() - Duration::days(config.retention_days as i64);
+    let mut purged = 0;
+    loop {
+        let ids: Vec<i64> = users::Entity::find()
+            .select_only()
+            .column(users::Column::Id)
+            .filter(users::Column::Status.eq(users::STATUS_DELETED))
+            .filter(users::Column::DeletedAt.lt(cutoff))
+            .limit(PURGE_BATCH)
+            .into_tuple()
+            .all(db)
+            .await?;
+        if ids.is_empty() {
+            return Ok(purged);
+        }
+        let txn = db.begin().await?;
+        // 设备表的外键不会级联删除
+        devices::Entity::delete_many()
+            .filter(devices::Column::UserId.is_in(ids.clone()))
+            .exec(&txn)
+            .await?;
+        let result = users::Entity::delete_many()
+            .filter(users::Column::Id.is_in(ids))
+            .exec(&txn)
+            .await?;
+        txn.commit().await?;
+        purged += result.rows_affected;
+    }
+}
+
+/// 启动后台清理任务，按 `purge_interval` 定期彻底删除超过保留期的用户
+pub fn spawn_purge_job(db: DatabaseConnection, config: &UserDeletion) {
+    if config.purge_interval == 0 {
+        return;
+    }
+    let config = config.clone();
+    tokio::spawn(async move {
+        let mut ticker =
+            tokio::time::interval(std::time::Duration::from_secs(config.purge_interval));
+        loop {
+            ticker.tick().await;
+            match purge_expired(&db, &config).await {
+                Ok(0) => {}
+                Ok(purged) => info!("彻底删除超过保留期的用户 {purged} 个"),
+                Err(e) => warn!("清理已删除的用户失败: {e}"),
+            }
+        }
+    });
+}
@@ -0,0 +1,135 @@
+use crate::entity::user_tokens;
+use crate::errors::AppError;
+use crate::models::secure_token;
+use crate::state::AppState;
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::NotSet, ColumnTrait, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set,
+};
+use serde::{Deserialize, Serialize};
+
+/// 一次性令牌的用途
+pub mod purpose {
+    /// 注册邮箱验证
+    pub const VERIFY_EMAIL: &str = "verify_email";
+    /// 修改邮箱时验证新邮箱
+    pub const CHANGE_EMAIL: &str = "change_email";
+    /// 找回密码
+    pub const RESET_PASSWORD: &str = "reset_password";
+    /// 两步验证登录的挑战令牌
+    pub const TWO_FACTOR_CHALLENGE: &str = "two_factor_challenge";
+}
+
+/// 一次性令牌的 JWT 载荷，`purpose` 防止不同用途的令牌互相冒用
+#[derive(Debug, Serialize, Deserialize)]
+struct ActionClaims {
+    /// 用户 ID
+    sub: String,
+    purpose: String,
+    exp: usize,
+    jti: String,
+}
+
+/// 签发一次性令牌，同一用户同一用途之前签发的令牌全部作废
+///
+/// 令牌本身由密钥环签名，数据库只保存 jti 的摘要用于保证只能使用一次
+pub async fn issue(
+    app_data: &AppState,
+    user_id: i64,
+    purpose: &str,
+    expire_secs: i64,
+) -> Result<String, AppError> {
+    let expire_time = Utc::now() + Duration::seconds(expire_secs);
+    let claims = ActionClaims {
+        sub: user_id.to_string(),
+        purpose: purpose.to_string(),
+        exp: expire_time.timestamp() as usize,
+        jti: uuid::Uuid::new_v4().simple().to_string(),
+    };
+    let token = app_data.jwt_keys.sign(&claims)?;
+
+    user_tokens::Entity::delete_many()
+        .filter(user_tokens::Column::UserId.eq(user_id))
+        .filter(user_tokens::Column::Purpose.eq(purpose))
+        .exec(&app_data.db_pool)
+        .await?;
+    user_tokens::ActiveModel {
+        id: NotSet,
+        user_id: Set(user_id),
+        purpose: Set(purpose.to_string()),
+        token_hash: Set(secure_token::digest(&claims.jti)),
+        expire_time: Set(expire_time),
+        create_time: Set(Utc::now()),
+    }
+    .insert(&app_data.db_pool)
+    .await?;
+    Ok(token)
+}
+
+/// 校验签名与用途，返回用户 ID 与 jti 摘要
+fn decode(app_data: &AppState, token: &str, purpose: &str) -> Option<(i64, String)> {
+    let claims = app_data.jwt_keys.verify::<ActionClaims>(token).ok()?.claims;
+    let user_id = claims.sub.parse::<i64>().ok()?;
+    (claims.purpose == purpose).then(|| (user_id, secure_token::digest(&claims.jti)))
+}
+
+/// 校验令牌但不消费，成功时返回用户 ID
+///
+/// 用于需要先完成其他校验再决定是否消费的场景，例如两步验证的挑战令牌
+pub async fn peek(
+    app_data: &AppState,
+    token: &str,
+    purpose: &str,
+) -> Result<Option<i64>, AppError> {
+    let Some((user_id, token_hash)) = decode(app_data, token, purpose) else {
+        return Ok(None);
+    };
+    let exists = user_tokens::Entity::find()
+        .filter(user_tokens::Column::TokenHash.eq(token_hash))
+        .filter(user_tokens::Column::UserId.eq(user_id))
+        .filter(user_tokens::Column::Purpose.eq(purpose))
+        .filter(user_tokens::Column::ExpireTime.gt(Utc::now()))
+        .one(&app_data.db_pool)
+        .await?
+        .is_some();
+    Ok(exists.then_some(user_id))
+}
+
+/// 校验并消费令牌，成功时返回用户 ID
+///
+/// 签名错误、已过期、用途不符或已经使用过的令牌返回 `None`
+pub async fn consume(
+    app_data: &AppState,
+    token: &str,
+    purpose: &str,
+) -> Result<Option<i64>, AppError> {
+    let Some((user_id, token_hash)) = decode(app_data, token, purpose) else {
+        return Ok(None);
+    };
+    // 删除成功才算有效，并发提交同一个令牌时只有一个请求能成功
+    let delete_result = user_tokens::Entity::delete_many()
+        .filter(user_tokens::Column::TokenHash.eq(token_hash))
+        .filter(user_tokens::Column::UserId.eq(user_id))
+        .filter(user_tokens::Column::Purpose.eq(purpose))
+        .filter(user_tokens::Column::ExpireTime.gt(Utc::now()))
+        .exec(&app_data.db_pool)
+        .await?;
+    Ok((delete_result.rows_affected > 0).then_some(user_id))
+}
+
+/// 最近一次为用户签发该用途令牌的时间，用于限制重新发送的频率
+pub async fn last_issued(
+    app_data: &AppState,
+    user_id: i64,
+    purpose: &str,
+) -> Result<Option<DateTime<Utc>>, AppError> {
+    let token = user_tokens::Entity::find()
+        .filter(user_tokens::Column::UserId.eq(user_id))
+        .filter(user_tokens::Column::Purpose.eq(purpose))
+        .order_by_desc(user_tokens::Column::CreateTime)
+        .limit(1)
+        .one(&app_data.db_pool)
+        .await?;
+    Ok(token.map(|token| token.create_time))
+}
@@ -0,0 +1,126 @@
+use crate::entity::{devices, users};
+use crate::errors::{AppError, Constraint, code, constraint_violation};
+use crate::models::mailer::Email;
+use crate::models::user_token::{self, purpose};
+use crate::state::AppState;
+use chrono::{Duration, Utc};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait};
+
+/// 签发验证令牌并发送验证邮件
+pub async fn send_verification_email(
+    user: &users::Model,
+    app_data: &AppState,
+) -> Result<(), AppError> {
+    let config = &app_data.config.verification;
+    let token = user_token::issue(
+        app_data,
+        user.id,
+        purpose::VERIFY_EMAIL,
+        config.token_expire,
+    )
+    .await?;
+    let email = Email {
+        to: user.email.clone(),
+        subject: "请验证你的邮箱".to_string(),
+        body: format!(
+            "{}，你好:\n\n请在 {} 小时内打开以下链接完成邮箱验证:\n{}?token={}\n\n如果不是你本人注册，请忽略这封邮件。\n",
+            user.name,
+            config.token_expire / 3600,
+            config.link_url,
+            token
+        ),
+    };
+    app_data.mailer.send(&email).await
+}
+
+/// 重新发送验证邮件，两次发送的间隔小于 `resend_interval` 时返回 `TooManyRequests`
+pub async fn resend_verification_email(
+    user: &users::Model,
+    app_data: &AppState,
+) -> Result<(), AppError> {
+    let interval = Duration::seconds(app_data.config.verification.resend_interval);
+    if let Some(last) = user_token::last_issued(app_data, user.id, purpose::VERIFY_EMAIL).await? {
+        let wait = last + interval - Utc::now();
+        if wait > Duration::zero() {
+            return Err(AppError::TooManyRequests(format!(
+                "发送过于频繁，请 {} 秒后再试",
+                wait.num_seconds() + 1
+            )));
+        }
+    }
+    send_verification_email(user, app_data).await
+}
+
+/// 向待验证的新邮箱发送验证邮件，验证前原邮箱与会话保持不变
+pub async fn send_email_change_email(
+    user: &users::Model,
+    new_email: &str,
+    app_data: &AppState,
+) -> Result<(), AppError> {
+    let config = &app_data.config.verification;
+    let token = user_token::issue(
+        app_data,
+        user.id,
+        purpose::CHANGE_EMAIL,
+        config.token_expire,
+    )
+    .await?;
+    let email = Email {
+        to: new_email.to_string(),
+        subject: "请验证你的新邮箱".to_string(),
+        body: format!(
+            "{}，你好:\n\n你正在把账号邮箱修改为 {}，请在 {} 小时内打开以下链接完成验证:\n{}?token={}\n\n验证前仍使用原邮箱登录。如果不是你本人操作，请忽略这封邮件。\n",
+            user.name,
+            new_email,
+            config.token_expire / 3600,
+            config.link_url,
+            token
+        ),
+    };
+    app_data.mailer.send(&email).await
+}
+
+/// 新邮箱验证通过后替换原邮箱，并撤销该用户的所有会话
+///
+/// 新邮箱已验证，待验证状态的账号同时变为正常状态
+pub async fn confirm_email_change(
+    user_id: i64,
+    app_data: &AppState,
+) -> Result<users::Model, AppError> {
+    let user = users::Entity::find_active_by_id(user_id)
+        .one(&app_data.db_pool)
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound(format!("用户ID {user_id} 不存在")).with_code(code::USER_NOT_FOUND)
+        })?;
+    let Some(email) = user.pending_email.clone() else {
+        return Err(AppError::BadRequest("验证链接无效或已过期".to_string()));
+    };
+    let mut active: users::ActiveModel = user.into();
+    active.email = Set(email.clone());
+    active.pending_email = Set(None);
+    active.status = Set(users::STATUS_NORMAL.to_string());
+
+    let txn = app_data.db_pool.begin().await?;
+    // 验证期间新邮箱可能已被他人注册，以数据库的唯一约束为准
+    let user = active
+        .update(&txn)
+        .await
+        .map_err(|e| match constraint_violation(&e) {
+            Some(Constraint::Unique) => {
+                AppError::Conflict(format!("邮箱 {email} 已被注册")).with_code(code::EMAIL_TAKEN)
+            }
+            _ => e.into(),
+        })?;
+    // 令牌与邮箱绑定，原有会话需要重新登录
+    let revoked = devices::Entity::delete_many()
+        .filter(devices::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+    txn.commit().await?;
+    info!(
+        "用户 {user_id} 完成邮箱修改，撤销会话 {} 个",
+        revoked.rows_affected
+    );
+    Ok(user)
+}
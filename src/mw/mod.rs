@@ -1,22 +1,53 @@
+use crate::entity::{devices, users};
+use crate::errors::{AppError, code};
+use crate::models::api_key;
+use crate::models::auth::AuthUser;
+use crate::models::client_info::ClientInfo;
+use crate::models::token::verify_token;
+use crate::state::AppState;
 use actix_web::{
-    Error,
-    body::MessageBody,
+    Error, HttpMessage,
+    body::{EitherBody, MessageBody},
     dev::{ServiceRequest, ServiceResponse},
+    http::header::{
+        AUTHORIZATION, COOKIE, HeaderMap, HeaderName, HeaderValue, PROXY_AUTHORIZATION,
+    },
     middleware::Next,
+    web,
 };
+use chrono::{Duration, Utc};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use tracing_actix_web::RequestId;
 
-// api 授权白名单
-pub const AUTH_WHITELIST: [&str; 1] = ["/api/login"];
+/// 设备最近活跃时间的更新间隔 (秒)
+const LAST_SEEN_INTERVAL: i64 = 60;
 
 pub async fn auth(
     req: ServiceRequest,
     next: Next<impl MessageBody>,
-) -> Result<ServiceResponse<impl MessageBody>, Error> {
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
     let method_path_str = format!("[{}] - [{}]", req.method(), req.path());
     // 记录请求信息到日志
-    info!("PRE: {method_path_str} - Headers: {:#?}", &req.headers());
+    info!(
+        "PRE: {method_path_str} - Headers: {:#?}",
+        redacted_headers(&req)
+    );
+
+    // 白名单之外的接口需要校验 token，并把调用者身份写入请求扩展
+    if !is_whitelisted(&req) {
+        match authenticate(&req).await {
+            Ok(auth_user) => {
+                req.extensions_mut().insert(auth_user);
+            }
+            Err(err) => {
+                warn!("AUTH: {method_path_str} - {err}");
+                return Ok(req.error_response(err).map_into_right_body());
+            }
+        }
+    }
+
     let res = next.call(req).await;
-    let _ = match &res {
+    match &res {
         Ok(response) => {
             // 记录响应信息到日志
             info!(
@@ -28,5 +59,149 @@ pub async fn auth(
             error!("Error occurred: {}", err);
         }
     };
-    res
+    res.map(ServiceResponse::map_into_left_body)
+}
+
+/// 返回请求 ID 的响应头
+const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+tokio::task_local! {
+    /// 当前请求的 ID，用于在错误响应中返回
+    static REQUEST_ID: String;
+}
+
+/// 当前正在处理的请求 ID，不在请求处理过程中时为 `None`
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(String::clone).ok()
+}
+
+/// 为每个请求分配 ID，通过 `X-Request-Id` 响应头返回，出错时同时写入响应内容
+///
+/// 需要放在 `TracingLogger` 内层以使用与日志相同的 ID，未启用 `TracingLogger` 时自动生成
+pub async fn request_id(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let id = req
+        .extensions()
+        .get::<RequestId>()
+        .map(RequestId::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let header = HeaderValue::from_str(&id).ok();
+    let mut res = REQUEST_ID.scope(id, next.call(req)).await?;
+    if let Some(header) = header {
+        res.headers_mut().insert(REQUEST_ID_HEADER, header);
+    }
+    Ok(res)
+}
+
+/// 判断请求路径是否在配置的授权白名单中
+fn is_whitelisted(req: &ServiceRequest) -> bool {
+    req.app_data::<web::Data<AppState>>()
+        .is_some_and(|app_data| app_data.config.auth.is_whitelisted(req.path()))
+}
+
+/// 复制请求头用于日志输出，隐藏 token、Cookie 与 API Key 的值
+fn redacted_headers(req: &ServiceRequest) -> HeaderMap {
+    let api_key_header = req
+        .app_data::<web::Data<AppState>>()
+        .map(|app_data| app_data.config.api_key.header.as_str());
+    let mut headers = HeaderMap::with_capacity(req.headers().len());
+    for (name, value) in req.headers().iter() {
+        let sensitive = name == AUTHORIZATION
+            || name == PROXY_AUTHORIZATION
+            || name == COOKIE
+            || api_key_header.is_some_and(|header| name.as_str().eq_ignore_ascii_case(header));
+        let value = if sensitive {
+            HeaderValue::from_static("***")
+        } else {
+            value.clone()
+        };
+        headers.append(name.clone(), value);
+    }
+    headers
+}
+
+/// 从 `Authorization: Bearer <token>` 请求头中提取 token
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+}
+
+/// 从配置的请求头中提取 API Key
+fn api_key_header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get(name)?
+        .to_str()
+        .ok()
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+}
+
+/// 校验 API Key 或 token，并查询对应的用户
+async fn authenticate(req: &ServiceRequest) -> Result<AuthUser, AppError> {
+    let app_data = req
+        .app_data::<web::Data<AppState>>()
+        .ok_or_else(|| AppError::InternalError("应用状态未初始化".to_string()))?;
+    if let Some(key) = api_key_header(req.headers(), &app_data.config.api_key.header) {
+        let client = ClientInfo::new(req.request());
+        let (api_key, user) = api_key::authenticate(key, &client, app_data).await?;
+        return Ok(AuthUser {
+            id: user.id,
+            email: user.email,
+            status: user.status,
+            device_id: 0,
+            token: key.to_string(),
+            client_id: None,
+            scope: api_key.scope,
+            api_key_id: Some(api_key.id),
+        });
+    }
+
+    let token = bearer_token(req.headers())
+        .ok_or_else(|| AppError::Unauthorized("缺少访问令牌".to_string()))?;
+    let claims = verify_token(&app_data.jwt_keys, token)?.claims;
+
+    // token 必须仍然存在于设备表中，登出后的 token 即使未过期也不再有效
+    let device = devices::Entity::find()
+        .filter(devices::Column::Token.eq(token))
+        .one(&app_data.db_pool)
+        .await?
+        .ok_or_else(|| {
+            AppError::Unauthorized("访问令牌已失效".to_string()).with_code(code::INVALID_TOKEN)
+        })?;
+    let user = users::Entity::find_active_by_id(device.user_id)
+        .one(&app_data.db_pool)
+        .await?
+        .filter(|user| user.email == claims.sub)
+        .ok_or_else(|| {
+            AppError::Unauthorized("访问令牌已失效".to_string()).with_code(code::INVALID_TOKEN)
+        })?;
+
+    // 更新设备最近活跃时间与 IP，同一设备一分钟内只写一次
+    if Utc::now() - device.update_time > Duration::seconds(LAST_SEEN_INTERVAL) {
+        let client = ClientInfo::new(req.request());
+        devices::Entity::update_many()
+            .col_expr(devices::Column::UpdateTime, Utc::now().into())
+            .col_expr(devices::Column::Ip, client.ip.into())
+            .filter(devices::Column::Id.eq(device.id))
+            .exec(&app_data.db_pool)
+            .await?;
+    }
+
+    Ok(AuthUser {
+        id: user.id,
+        email: user.email,
+        status: user.status,
+        device_id: device.id,
+        token: token.to_string(),
+        client_id: device.client_id,
+        scope: device.scope,
+        api_key_id: None,
+    })
 }
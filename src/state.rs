@@ -1,28 +1,65 @@
+use crate::models::audit::AuditLog;
+use crate::models::document_store::{self, DocumentStore};
+use crate::models::keyring::KeyRing;
+use crate::models::login_guard::{self, LoginGuard};
+use crate::models::mailer::{self, Mailer};
+use crate::models::oauth::AuthorizationServer;
+use crate::models::oidc::{self, OidcClient};
+use crate::models::user_deletion;
 use anyhow::Result;
-use mongodb::Client;
+use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub db_pool: sea_orm::DatabaseConnection,
-    pub mongodb_client: Client,
+    /// 文档存储，未启用时为 `None`
+    pub document_store: Option<Arc<dyn DocumentStore>>,
+    /// 审计日志，写入文档存储
+    pub audit: Arc<AuditLog>,
+    pub config: crate::app_config::Config,
+    pub jwt_keys: Arc<KeyRing>,
+    pub login_guard: Arc<LoginGuard>,
+    pub mailer: Arc<dyn Mailer>,
+    /// 未启用 OIDC 登录时为 `None`
+    pub oidc: Option<Arc<OidcClient>>,
+    pub oauth: Arc<AuthorizationServer>,
 }
 
 impl AppState {
     pub async fn new(app_config: &crate::app_config::Config) -> Result<Self> {
+        crate::errors::set_expose_internal_errors(app_config.server.expose_internal_errors);
         let db_pool = app_config.db.init_db().await?;
-        let mongodb_client = app_config.mongodb.client().await?;
+        let document_store = document_store::from_config(&app_config.store, &app_config.mongodb)?;
+        let audit = Arc::new(AuditLog::new(&app_config.audit, document_store.clone()));
+        let jwt_keys = Arc::new(KeyRing::from_config(&app_config.jwt)?);
+        user_deletion::spawn_purge_job(db_pool.clone(), &app_config.user_deletion);
+        let login_guard = Arc::new(LoginGuard::new(&app_config.login_guard));
+        login_guard::spawn_prune_job(login_guard.clone());
+        // 未启用 OIDC 登录时不创建客户端
+        let oidc = if app_config.oidc.enabled {
+            let oidc = Arc::new(OidcClient::new(&app_config.oidc)?);
+            oidc::spawn_prune_job(oidc.clone());
+            Some(oidc)
+        } else {
+            None
+        };
         Ok(Self {
             db_pool,
-            mongodb_client,
+            document_store,
+            audit,
+            config: app_config.clone(),
+            jwt_keys,
+            login_guard,
+            mailer: mailer::from_config(&app_config.mail)?,
+            oidc,
+            oauth: Arc::new(AuthorizationServer::new(&app_config.oauth_server)),
         })
     }
 }
 
 /// `Cargo.toml` 中的 package.name
-pub const CARGO_PKG_NAME: &'static str = env!("CARGO_PKG_NAME");
+pub const CARGO_PKG_NAME: &str = env!("CARGO_PKG_NAME");
 /// `Cargo.toml` 中的 package.version
-pub const CARGO_PKG_VERSION: &'static str = env!("CARGO_PKG_VERSION");
-/// argon2 盐值
+pub const CARGO_PKG_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// 旧版本所有用户共用的 argon2 盐值，新密码改用随机盐值，保留用于识别需要重新计算的旧哈希
 pub const ARGON2_SALT: &[u8] = b"81d84995-8531-49b2-b563-12b0e17bc784";
-/// TOKEN 过期时间
-pub const TOKEN_EXPIRE_TIME: i64 = 60 * 60 * 24 * 7; // 7 天
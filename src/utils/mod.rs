@@ -1,12 +1,18 @@
+use crate::dto::FieldError;
 use crate::errors::AppError;
 use actix_web::{Result, web};
+use sea_orm::{
+    ColumnTrait,
+    sea_query::{Expr, Func, LikeExpr, SimpleExpr},
+};
+use validator::Validate;
 
 /// 提供用于序列化和反序列化 `chrono::NaiveDateTime` 的字段属性的工具
 pub mod serde_timestamp {
     use chrono::{DateTime, NaiveDateTime, Utc};
     use serde::{self, Deserialize, Deserializer, Serializer};
 
-    const FORMAT: &'static str = "%Y-%m-%d %H:%M:%S";
+    const FORMAT: &str = "%Y-%m-%d %H:%M:%S";
 
     pub fn serialize<S>(date: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -26,6 +32,32 @@ pub mod serde_timestamp {
     }
 }
 
+/// `serde_timestamp` 的 `Option<DateTime<Utc>>` 版本
+pub mod serde_timestamp_option {
+    use chrono::{DateTime, Utc};
+    use serde::{self, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(date: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match date {
+            Some(date) => super::serde_timestamp::serialize(date, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Wrapper(#[serde(with = "super::serde_timestamp")] DateTime<Utc>);
+        let wrapper = Option::<Wrapper>::deserialize(deserializer)?;
+        Ok(wrapper.map(|Wrapper(date)| date))
+    }
+}
+
 // 提取路径参数
 pub fn extract_path_param<T>(param: Result<web::Path<T>>, param_name: &str) -> Result<T, AppError> {
     match param {
@@ -33,3 +65,37 @@ pub fn extract_path_param<T>(param: Result<web::Path<T>>, param_name: &str) -> R
         Err(_) => Err(AppError::BadRequest(format!("无效的{param_name}"))),
     }
 }
+
+/// 参数校验，失败时返回各字段的错误信息，未设置自定义错误信息的规则返回规则名称
+pub fn validate_params<T: Validate>(params: &T) -> Result<(), AppError> {
+    params.validate().map_err(|e| {
+        let mut fields: Vec<FieldError> = e
+            .field_errors()
+            .into_iter()
+            .flat_map(|(field, errs)| {
+                errs.iter().map(move |err| FieldError {
+                    field: field.to_string(),
+                    message: err
+                        .message
+                        .as_ref()
+                        .map_or_else(|| err.code.to_string(), |msg| msg.to_string()),
+                })
+            })
+            .collect();
+        fields.sort_by(|a, b| a.field.cmp(&b.field));
+        AppError::ValidationError(fields)
+    })
+}
+
+/// 不区分大小写的包含查询，在各数据库上行为一致
+///
+/// 直接使用 `contains` 时 PostgreSQL 区分大小写而 SQLite、MySQL 不区分，且关键字中的 `%`、`_` 会被当作通配符
+pub fn contains_ignore_case<C: ColumnTrait>(col: C, keyword: &str) -> SimpleExpr {
+    let escaped = keyword
+        .to_lowercase()
+        .replace('!', "!!")
+        .replace('%', "!%")
+        .replace('_', "!_");
+    Expr::expr(Func::lower(Expr::col((col.entity_name(), col))))
+        .like(LikeExpr::new(format!("%{escaped}%")).escape('!'))
+}
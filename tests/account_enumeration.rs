@@ -0,0 +1,83 @@
+//! 找回密码等按邮箱发送邮件的接口，无论邮箱是否注册都返回相同的响应
+
+use actix_web::test;
+use futures::future::BoxFuture;
+use rust_class_web::errors::AppError;
+use rust_class_web::models::mailer::{Email, Mailer};
+use rust_class_web::state::AppState;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[macro_use]
+mod common;
+
+const REGISTERED: &str = "alice@example.com";
+const UNKNOWN: &str = "nobody@example.com";
+/// 模拟较慢的邮件服务器
+const SEND_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Debug)]
+struct SlowMailer;
+
+impl Mailer for SlowMailer {
+    fn send<'a>(&'a self, _email: &'a Email) -> BoxFuture<'a, Result<(), AppError>> {
+        Box::pin(async {
+            tokio::time::sleep(SEND_DELAY).await;
+            Ok(())
+        })
+    }
+}
+
+#[actix_web::test]
+async fn email_endpoints_do_not_reveal_registration() {
+    let state = AppState::new(&common::config()).await.unwrap();
+    let app = test::init_service(common::app(&state)).await;
+    sign_up!(app, "alice", REGISTERED);
+    let post = |uri: &str, email: &str| {
+        test::TestRequest::post()
+            .uri(uri)
+            .set_json(json!({ "email": email }))
+    };
+
+    // 连续请求触发发送间隔限制，已注册的邮箱同样返回成功
+    for uri in ["/api/users/password/forgot", "/api/users/verify/resend"] {
+        for _ in 0..2 {
+            let registered = call!(app, post(uri, REGISTERED), None);
+            let unknown = call!(app, post(uri, UNKNOWN), None);
+            assert!(registered.0.is_success(), "{uri}: {}", registered.1);
+            assert_eq!(registered, unknown, "{uri}");
+        }
+    }
+}
+
+#[actix_web::test]
+async fn email_endpoints_respond_without_waiting_for_the_mail() {
+    let mut config = common::config();
+    // 注册时已发送验证邮件，取消发送间隔限制以便再次发送
+    config.verification.resend_interval = 0;
+    let mut state = AppState::new(&config).await.unwrap();
+    state.mailer = Arc::new(SlowMailer);
+    let app = test::init_service(common::app(&state)).await;
+    sign_up!(app, "alice", REGISTERED);
+
+    // 邮件在后台发送，已注册邮箱的响应不会因为发送邮件而明显变慢
+    for uri in ["/api/users/password/forgot", "/api/users/verify/resend"] {
+        for email in [REGISTERED, UNKNOWN] {
+            let started = Instant::now();
+            let (status, body) = call!(
+                app,
+                test::TestRequest::post()
+                    .uri(uri)
+                    .set_json(json!({ "email": email })),
+                None
+            );
+            assert!(status.is_success(), "{uri}: {body}");
+            assert!(
+                started.elapsed() < SEND_DELAY / 2,
+                "{uri} {email}: {:?}",
+                started.elapsed()
+            );
+        }
+    }
+}
@@ -0,0 +1,254 @@
+//! API Key 鉴权: 优先于 Bearer token、授权范围、过期与撤销、最近使用记录以及不能访问仅限本人登录的接口
+
+use actix_web::http::StatusCode;
+use actix_web::test;
+use chrono::{Duration, Utc};
+use rust_class_web::entity::api_keys;
+use rust_class_web::state::AppState;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use serde_json::{Value, json};
+
+#[macro_use]
+mod common;
+
+const ADMIN_EMAIL: &str = "admin@example.com";
+const BOB_EMAIL: &str = "bob@example.com";
+const KEY_HEADER: &str = "X-API-Key";
+
+/// 创建 API Key，返回明文与 ID
+macro_rules! create_key {
+    ($app:expr, $token:expr, $body:expr) => {{
+        let (status, body) = call!(
+            $app,
+            test::TestRequest::post()
+                .uri("/api/api-keys")
+                .set_json($body),
+            Some($token)
+        );
+        assert_eq!(status, StatusCode::OK, "{body}");
+        (
+            body["data"]["key"].as_str().unwrap().to_string(),
+            body["data"]["apiKey"]["id"].as_i64().unwrap(),
+        )
+    }};
+}
+
+/// 携带 API Key 发送请求
+macro_rules! with_key {
+    ($app:expr, $request:expr, $key:expr) => {{
+        let request: test::TestRequest = $request;
+        let key: &str = $key;
+        call!($app, request.insert_header((KEY_HEADER, key)), None)
+    }};
+}
+
+async fn setup() -> AppState {
+    let mut config = common::config();
+    config.auth.admins = vec![ADMIN_EMAIL.to_string()];
+    AppState::new(&config).await.unwrap()
+}
+
+fn user_id(user: &Value) -> i64 {
+    user["data"]["id"].as_i64().unwrap()
+}
+
+#[actix_web::test]
+async fn api_key_takes_precedence_over_bearer_token() {
+    let state = setup().await;
+    let app = test::init_service(common::app(&state)).await;
+    let admin = sign_up!(app, "admin", ADMIN_EMAIL);
+    sign_up!(app, "bob", BOB_EMAIL);
+    common::verify_email(&state.db_pool, ADMIN_EMAIL).await;
+    let admin_token = login!(app, ADMIN_EMAIL);
+    let bob_token = login!(app, BOB_EMAIL);
+    let (bob_key, _) = create_key!(app, &bob_token, json!({ "name": "bob" }));
+    let admin_uri = format!("/api/users/{}", user_id(&admin));
+
+    let (status, _) = call!(
+        app,
+        test::TestRequest::get().uri(&admin_uri),
+        Some(&admin_token)
+    );
+    assert_eq!(status, StatusCode::OK);
+    // 同时携带时以 API Key 的身份访问，bob 不能查询其他用户
+    let (status, body) = call!(
+        app,
+        test::TestRequest::get()
+            .uri(&admin_uri)
+            .insert_header((KEY_HEADER, bob_key.as_str())),
+        Some(&admin_token)
+    );
+    assert_eq!(status, StatusCode::FORBIDDEN, "{body}");
+    // API Key 无效时不会退回到 Bearer token
+    let (status, body) = call!(
+        app,
+        test::TestRequest::get()
+            .uri(&admin_uri)
+            .insert_header((KEY_HEADER, "rcw_invalid")),
+        Some(&admin_token)
+    );
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "{body}");
+}
+
+#[actix_web::test]
+async fn scopes_narrow_the_owner_permissions() {
+    let state = setup().await;
+    let app = test::init_service(common::app(&state)).await;
+    sign_up!(app, "admin", ADMIN_EMAIL);
+    sign_up!(app, "bob", BOB_EMAIL);
+    common::verify_email(&state.db_pool, ADMIN_EMAIL).await;
+    let admin_token = login!(app, ADMIN_EMAIL);
+    let bob_token = login!(app, BOB_EMAIL);
+
+    let (full_key, _) = create_key!(app, &admin_token, json!({ "name": "full" }));
+    let (read_key, _) = create_key!(
+        app,
+        &admin_token,
+        json!({ "name": "read", "scopes": ["user:read"] })
+    );
+    for (key, roles_status) in [
+        (&full_key, StatusCode::OK),
+        (&read_key, StatusCode::FORBIDDEN),
+    ] {
+        let (status, body) = with_key!(app, test::TestRequest::get().uri("/api/users"), key);
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let (status, body) = with_key!(app, test::TestRequest::get().uri("/api/admin/roles"), key);
+        assert_eq!(status, roles_status, "{body}");
+    }
+
+    // 授权范围只能收窄，不能获得用户本身没有的权限
+    let (bob_key, _) = create_key!(
+        app,
+        &bob_token,
+        json!({ "name": "read", "scopes": ["user:read"] })
+    );
+    let (status, body) = with_key!(app, test::TestRequest::get().uri("/api/users"), &bob_key);
+    assert_eq!(status, StatusCode::FORBIDDEN, "{body}");
+
+    let (status, body) = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/api-keys")
+            .set_json(json!({ "name": "unknown", "scopes": ["no:such"] })),
+        Some(&bob_token)
+    );
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+}
+
+#[actix_web::test]
+async fn expired_and_revoked_keys_are_rejected() {
+    let state = setup().await;
+    let app = test::init_service(common::app(&state)).await;
+    let bob = sign_up!(app, "bob", BOB_EMAIL);
+    let token = login!(app, BOB_EMAIL);
+    let me = format!("/api/users/{}", user_id(&bob));
+
+    let (expiring_key, expiring_id) =
+        create_key!(app, &token, json!({ "name": "expiring", "expire_days": 1 }));
+    let (status, body) = with_key!(app, test::TestRequest::get().uri(&me), &expiring_key);
+    assert_eq!(status, StatusCode::OK, "{body}");
+    api_keys::Entity::update_many()
+        .col_expr(
+            api_keys::Column::ExpireTime,
+            Some(Utc::now() - Duration::seconds(1)).into(),
+        )
+        .filter(api_keys::Column::Id.eq(expiring_id))
+        .exec(&state.db_pool)
+        .await
+        .unwrap();
+    let (status, body) = with_key!(app, test::TestRequest::get().uri(&me), &expiring_key);
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "{body}");
+
+    let (revoked_key, revoked_id) = create_key!(app, &token, json!({ "name": "revoked" }));
+    let (status, body) = with_key!(app, test::TestRequest::get().uri(&me), &revoked_key);
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let (status, body) = call!(
+        app,
+        test::TestRequest::delete().uri(&format!("/api/api-keys/{revoked_id}")),
+        Some(&token)
+    );
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let (status, body) = with_key!(app, test::TestRequest::get().uri(&me), &revoked_key);
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "{body}");
+}
+
+#[actix_web::test]
+async fn last_used_time_and_ip_are_recorded() {
+    let state = setup().await;
+    let app = test::init_service(common::app(&state)).await;
+    let bob = sign_up!(app, "bob", BOB_EMAIL);
+    let token = login!(app, BOB_EMAIL);
+    let (key, id) = create_key!(app, &token, json!({ "name": "bob" }));
+    let unused = api_keys::Entity::find_by_id(id)
+        .one(&state.db_pool)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(unused.last_used_time.is_none());
+
+    let before = Utc::now() - Duration::seconds(1);
+    let (status, body) = with_key!(
+        app,
+        test::TestRequest::get()
+            .uri(&format!("/api/users/{}", user_id(&bob)))
+            .peer_addr("203.0.113.7:40000".parse().unwrap()),
+        &key
+    );
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let (status, body) = call!(
+        app,
+        test::TestRequest::get().uri("/api/api-keys"),
+        Some(&token)
+    );
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let listed = &body["data"][0];
+    assert_eq!(listed["lastUsedIp"], "203.0.113.7", "{body}");
+    let used = api_keys::Entity::find_by_id(id)
+        .one(&state.db_pool)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(used.last_used_time.unwrap() >= before, "{used:?}");
+}
+
+/// 修改密码、管理会话与 API Key 等接口只接受本站登录签发的 token
+#[actix_web::test]
+async fn api_keys_cannot_access_self_only_routes() {
+    let state = setup().await;
+    let app = test::init_service(common::app(&state)).await;
+    sign_up!(app, "bob", BOB_EMAIL);
+    let token = login!(app, BOB_EMAIL);
+    let (key, id) = create_key!(app, &token, json!({ "name": "bob" }));
+
+    let requests = [
+        test::TestRequest::get().uri("/api/sessions"),
+        test::TestRequest::delete().uri("/api/sessions/others"),
+        test::TestRequest::get().uri("/api/api-keys"),
+        test::TestRequest::post()
+            .uri("/api/api-keys")
+            .set_json(json!({ "name": "minted" })),
+        test::TestRequest::delete().uri(&format!("/api/api-keys/{id}")),
+        test::TestRequest::post()
+            .uri("/api/users/password/change")
+            .set_json(
+                json!({ "old_pass_word": common::PASSWORD, "new_pass_word": "new-password" }),
+            ),
+        test::TestRequest::post().uri("/api/users/2fa/setup"),
+        test::TestRequest::post().uri("/api/logout"),
+    ];
+    for request in requests {
+        let (status, body) = with_key!(app, request, &key);
+        assert_eq!(status, StatusCode::FORBIDDEN, "{body}");
+    }
+
+    // 被拒绝的请求没有产生任何影响
+    let (status, body) = call!(
+        app,
+        test::TestRequest::get().uri("/api/api-keys"),
+        Some(&token)
+    );
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["data"].as_array().unwrap().len(), 1, "{body}");
+    login!(app, BOB_EMAIL);
+}
@@ -0,0 +1,235 @@
+//! 审计日志: 后台批量写入文档存储、按条件查询、文档存储不可用时丢弃最早的事件以及退出前写入剩余事件
+
+use chrono::Utc;
+use futures::future::{self, BoxFuture};
+use rust_class_web::app_config::audit::Audit;
+use rust_class_web::errors::AppError;
+use rust_class_web::models::audit::{AuditEvent, AuditFilter, AuditLog, Outcome, action};
+use rust_class_web::models::client_info::ClientInfo;
+use rust_class_web::models::document_store::memory::MemoryStore;
+use rust_class_web::models::document_store::{
+    Document, DocumentStore, Filter, FindOptions, Index, StoreStatus,
+};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// 可以模拟断开的内存文档存储
+#[derive(Debug, Default)]
+struct FlakyStore {
+    inner: MemoryStore,
+    down: AtomicBool,
+}
+
+impl FlakyStore {
+    fn set_down(&self, down: bool) {
+        self.down.store(down, Ordering::SeqCst);
+    }
+}
+
+impl DocumentStore for FlakyStore {
+    fn status(&self) -> StoreStatus {
+        if self.down.load(Ordering::SeqCst) {
+            StoreStatus::Down
+        } else {
+            StoreStatus::Up
+        }
+    }
+
+    fn insert_many<'a>(
+        &'a self,
+        collection: &'a str,
+        documents: Vec<Document>,
+    ) -> BoxFuture<'a, Result<(), AppError>> {
+        if self.down.load(Ordering::SeqCst) {
+            return Box::pin(future::ready(Err(AppError::ServiceUnavailable(
+                "文档存储不可用".to_string(),
+            ))));
+        }
+        self.inner.insert_many(collection, documents)
+    }
+
+    fn find<'a>(
+        &'a self,
+        collection: &'a str,
+        filter: &'a Filter,
+        options: &'a FindOptions,
+    ) -> BoxFuture<'a, Result<Vec<Document>, AppError>> {
+        self.inner.find(collection, filter, options)
+    }
+
+    fn update_many<'a>(
+        &'a self,
+        collection: &'a str,
+        filter: &'a Filter,
+        set: Document,
+    ) -> BoxFuture<'a, Result<u64, AppError>> {
+        self.inner.update_many(collection, filter, set)
+    }
+
+    fn delete_many<'a>(
+        &'a self,
+        collection: &'a str,
+        filter: &'a Filter,
+    ) -> BoxFuture<'a, Result<u64, AppError>> {
+        self.inner.delete_many(collection, filter)
+    }
+
+    fn create_index<'a>(
+        &'a self,
+        collection: &'a str,
+        index: &'a Index,
+    ) -> BoxFuture<'a, Result<(), AppError>> {
+        self.inner.create_index(collection, index)
+    }
+}
+
+/// 每次写入一条，每 10 毫秒写入一次
+fn config() -> Audit {
+    Audit {
+        batch_size: 1,
+        flush_interval: 10,
+        ..Audit::default()
+    }
+}
+
+fn event(action: &str, actor_id: i64, detail: &str) -> AuditEvent {
+    AuditEvent::new(action, Outcome::Success, &ClientInfo::default())
+        .actor(actor_id, &format!("user{actor_id}@example.com"))
+        .detail(detail)
+}
+
+/// 按条件查询，返回按时间倒序排列的 `detail`
+async fn details(audit: &AuditLog, filter: AuditFilter) -> Vec<String> {
+    audit
+        .query(&AuditFilter {
+            limit: 100,
+            ..filter
+        })
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|record| record.event.detail.unwrap())
+        .collect()
+}
+
+async fn wait_for_flush() {
+    tokio::time::sleep(Duration::from_millis(100)).await;
+}
+
+#[tokio::test]
+async fn query_filters_by_actor_action_and_time() {
+    let audit = AuditLog::new(&config(), Some(Arc::new(MemoryStore::default())));
+    audit.record(event(action::LOGIN, 1, "first"));
+    // 时间只精确到毫秒，间隔开才能确定排序
+    tokio::time::sleep(Duration::from_millis(5)).await;
+    audit.record(event(action::LOGOUT, 1, "second"));
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let middle = Utc::now();
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    audit.record(event(action::LOGIN, 2, "third"));
+    wait_for_flush().await;
+
+    assert_eq!(
+        details(&audit, AuditFilter::default()).await,
+        ["third", "second", "first"]
+    );
+    let by_actor = AuditFilter {
+        actor_id: Some(1),
+        ..Default::default()
+    };
+    assert_eq!(details(&audit, by_actor).await, ["second", "first"]);
+    let by_action = AuditFilter {
+        action: Some(action::LOGIN.to_string()),
+        ..Default::default()
+    };
+    assert_eq!(details(&audit, by_action).await, ["third", "first"]);
+    let after = AuditFilter {
+        from: Some(middle),
+        ..Default::default()
+    };
+    assert_eq!(details(&audit, after).await, ["third"]);
+    let before = AuditFilter {
+        to: Some(middle),
+        ..Default::default()
+    };
+    assert_eq!(details(&audit, before).await, ["second", "first"]);
+    let paged = AuditFilter {
+        offset: 1,
+        limit: 1,
+        ..Default::default()
+    };
+    let records = audit.query(&paged).await.unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].event.detail.as_deref(), Some("second"));
+}
+
+/// 未攒够一批时等到写入间隔才写入
+#[tokio::test]
+async fn partial_batches_are_written_on_flush_interval() {
+    let config = Audit {
+        batch_size: 100,
+        flush_interval: 200,
+        ..Audit::default()
+    };
+    let audit = AuditLog::new(&config, Some(Arc::new(MemoryStore::default())));
+    // 第一次计时立即触发，等它过去后再记录
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    audit.record(event(action::LOGIN, 1, "pending"));
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(details(&audit, AuditFilter::default()).await.is_empty());
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(details(&audit, AuditFilter::default()).await, ["pending"]);
+}
+
+/// 文档存储不可用时只保留最新的 `buffer_size` 条，恢复后写入
+#[tokio::test]
+async fn drops_oldest_events_while_store_is_down() {
+    let store = Arc::new(FlakyStore::default());
+    store.set_down(true);
+    let config = Audit {
+        buffer_size: 3,
+        ..config()
+    };
+    let audit = AuditLog::new(&config, Some(store.clone()));
+    for i in 1..=5 {
+        audit.record(event(action::LOGIN, 1, &i.to_string()));
+        // 等后台任务取走，避免队列已满时丢弃的是新事件
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    wait_for_flush().await;
+
+    store.set_down(false);
+    wait_for_flush().await;
+    assert_eq!(
+        details(&audit, AuditFilter::default()).await,
+        ["5", "4", "3"]
+    );
+}
+
+/// 服务退出时不等写入间隔，立即写入队列中剩余的事件
+#[tokio::test]
+async fn shutdown_writes_pending_events() {
+    let config = Audit {
+        batch_size: 100,
+        flush_interval: 60_000,
+        ..Audit::default()
+    };
+    let audit = AuditLog::new(&config, Some(Arc::new(MemoryStore::default())));
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    audit.record(event(action::LOGIN, 1, "first"));
+    // 时间只精确到毫秒，间隔开才能确定排序
+    tokio::time::sleep(Duration::from_millis(5)).await;
+    audit.record(event(action::LOGOUT, 1, "second"));
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(details(&audit, AuditFilter::default()).await.is_empty());
+
+    audit.shutdown().await;
+    assert_eq!(
+        details(&audit, AuditFilter::default()).await,
+        ["second", "first"]
+    );
+    // 再次调用不做任何事
+    audit.shutdown().await;
+}
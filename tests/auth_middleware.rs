@@ -0,0 +1,91 @@
+//! 鉴权中间件: 白名单之外的接口必须携带有效且未登出的访问令牌
+
+use actix_web::{http::StatusCode, test};
+use rust_class_web::app_config::auth::Auth;
+use rust_class_web::state::AppState;
+use serde_json::json;
+
+#[macro_use]
+mod common;
+
+const EMAIL: &str = "alice@example.com";
+
+#[actix_web::test]
+async fn whitelist_and_token_checks() {
+    let state = AppState::new(&common::config()).await.unwrap();
+    let app = test::init_service(common::app(&state)).await;
+
+    // 白名单中的接口无需令牌，路径先规范化再匹配
+    for uri in ["/health", "/health/", "/.well-known/jwks.json"] {
+        let (status, _) = call!(app, test::TestRequest::get().uri(uri), None);
+        assert_eq!(status, StatusCode::OK, "{uri}");
+    }
+    let (status, body) = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/users/login/")
+            .set_json(json!({ "email": EMAIL, "pass_word": "password" })),
+        None
+    );
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["errorCode"], "LOGIN_FAILED");
+
+    // 白名单之外的接口，包括不存在的路径，都需要令牌
+    for uri in ["/api/sessions", "/api/users/login/extra", "/api/unknown"] {
+        let (status, body) = call!(app, test::TestRequest::get().uri(uri), None);
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{uri}");
+        assert_eq!(body["errorCode"], "UNAUTHORIZED", "{uri}");
+    }
+
+    sign_up!(app, "alice", EMAIL);
+    let token = login!(app, EMAIL);
+    let sessions = || test::TestRequest::get().uri("/api/sessions");
+
+    let (status, body) = call!(app, sessions(), Some(&token));
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    // 只接受 Bearer 方式
+    let basic = sessions().insert_header(("Authorization", format!("Basic {token}")));
+    let (status, body) = call!(app, basic, None);
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["errorCode"], "UNAUTHORIZED");
+    let (status, body) = call!(app, sessions(), Some(""));
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["errorCode"], "UNAUTHORIZED");
+
+    // 格式错误或签名被篡改的令牌
+    let (status, body) = call!(app, sessions(), Some("not-a-jwt"));
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["errorCode"], "INVALID_TOKEN");
+    let (unsigned, signature) = token.rsplit_once('.').unwrap();
+    let first = if signature.starts_with('A') { 'B' } else { 'A' };
+    let tampered = format!("{unsigned}.{first}{}", &signature[1..]);
+    let (status, body) = call!(app, sessions(), Some(&tampered));
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["errorCode"], "INVALID_TOKEN");
+
+    // 登出后令牌即使未过期也不再有效
+    let (status, _) = call!(
+        app,
+        test::TestRequest::post().uri("/api/logout"),
+        Some(&token)
+    );
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = call!(app, sessions(), Some(&token));
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["errorCode"], "INVALID_TOKEN");
+}
+
+#[actix_web::test]
+async fn whitelist_matches_exact_paths_and_prefixes() {
+    let auth = Auth {
+        whitelist: vec!["/api/users/login".to_string(), "/static/*".to_string()],
+        admins: vec![],
+    };
+    assert!(auth.is_whitelisted("/api/users/login"));
+    assert!(!auth.is_whitelisted("/api/users/login/2fa"));
+    assert!(!auth.is_whitelisted("/api/users"));
+    assert!(auth.is_whitelisted("/static/"));
+    assert!(auth.is_whitelisted("/static/js/app.js"));
+    assert!(!auth.is_whitelisted("/static"));
+}
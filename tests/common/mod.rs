@@ -0,0 +1,136 @@
+//! 集成测试共用的配置、应用初始化与请求辅助
+
+// 每个测试文件只用到其中一部分
+#![allow(dead_code, unused_macros)]
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
+use actix_web::{App, middleware, web::Data};
+use rust_class_web::app_config::Config;
+use rust_class_web::entity::users;
+use rust_class_web::models::mailer::MemoryMailer;
+use rust_class_web::state::AppState;
+use rust_class_web::{handlers, mw};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use std::sync::Arc;
+
+pub const PASSWORD: &str = "password";
+
+/// 测试配置: 内存数据库，邮件只保存在内存中，不连接文档存储，不启动后台清理
+pub fn config() -> Config {
+    let mut config = Config::default();
+    config.db.url = "sqlite::memory:".to_string();
+    config.db.max_connections = 1;
+    config.db.min_connections = 1;
+    config.mail.transport = "memory".to_string();
+    config.store.backend = "none".to_string();
+    config.verification.allow_unverified_login = true;
+    config.login_guard.base_delay_ms = 0;
+    config.user_deletion.purge_interval = 0;
+    config.login_guard.prune_interval = 0;
+    config.oidc.prune_interval = 0;
+    config
+}
+
+/// 与 `main` 相同的路由与业务中间件，交给 `test::init_service` 初始化
+pub fn app(
+    state: &AppState,
+) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody + use<>>,
+        Error = actix_web::Error,
+        InitError = (),
+    > + use<>,
+> {
+    App::new()
+        .wrap(middleware::from_fn(mw::auth))
+        .wrap(middleware::NormalizePath::trim())
+        .wrap(middleware::from_fn(mw::request_id))
+        .app_data(Data::new(state.clone()))
+        .configure(handlers::config)
+}
+
+/// 发送请求，返回状态码与响应内容，响应不是 JSON 时内容为 `Null`
+///
+/// `$token` 为 `Option<&str>`，有值时以 Bearer 方式携带
+macro_rules! call {
+    ($app:expr, $request:expr, $token:expr) => {{
+        let mut request: actix_web::test::TestRequest = $request;
+        let token: Option<&str> = $token;
+        if let Some(token) = token {
+            request = request.insert_header(("Authorization", format!("Bearer {token}")));
+        }
+        let response = actix_web::test::call_service(&$app, request.to_request()).await;
+        let status = response.status();
+        let body = actix_web::test::read_body(response).await;
+        (
+            status,
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap_or(serde_json::Value::Null),
+        )
+    }};
+}
+
+/// 以 `PASSWORD` 注册用户，返回注册接口的响应内容
+macro_rules! sign_up {
+    ($app:expr, $name:expr, $email:expr) => {{
+        let (status, body) = call!(
+            $app,
+            actix_web::test::TestRequest::post()
+                .uri("/api/users/create")
+                .set_json(serde_json::json!({
+                    "name": $name,
+                    "email": $email,
+                    "pass_word": common::PASSWORD,
+                })),
+            None
+        );
+        assert_eq!(status, actix_web::http::StatusCode::OK, "{body}");
+        body
+    }};
+}
+
+/// 以 `PASSWORD` 登录，返回访问令牌
+macro_rules! login {
+    ($app:expr, $email:expr) => {{
+        let (status, body) = call!(
+            $app,
+            actix_web::test::TestRequest::post()
+                .uri("/api/users/login")
+                .set_json(serde_json::json!({ "email": $email, "pass_word": common::PASSWORD })),
+            None
+        );
+        assert_eq!(status, actix_web::http::StatusCode::OK, "{body}");
+        body["data"]["token"].as_str().unwrap().to_string()
+    }};
+}
+
+/// 跳过邮件直接把账号标记为已验证邮箱
+pub async fn verify_email(db: &DatabaseConnection, email: &str) {
+    users::Entity::update_many()
+        .col_expr(users::Column::Status, users::STATUS_NORMAL.into())
+        .filter(users::Column::Email.eq(email))
+        .exec(db)
+        .await
+        .unwrap();
+}
+
+/// 替换为可以读取已发送邮件的发件箱，需要在 `app` 之前调用
+pub fn capture_mail(state: &mut AppState) -> Arc<MemoryMailer> {
+    let mailer = Arc::new(MemoryMailer::default());
+    state.mailer = mailer.clone();
+    mailer
+}
+
+/// 发给 `to` 的最后一封邮件中链接携带的令牌
+pub fn mailed_token(mailer: &MemoryMailer, to: &str) -> String {
+    let email = mailer
+        .sent()
+        .into_iter()
+        .rev()
+        .find(|email| email.to == to)
+        .unwrap_or_else(|| panic!("没有发给 {to} 的邮件"));
+    let (_, rest) = email.body.split_once("?token=").unwrap();
+    rest.split_whitespace().next().unwrap().to_string()
+}
@@ -0,0 +1,31 @@
+//! 输出配置时不包含密钥
+
+use rust_class_web::app_config::Config;
+use rust_class_web::app_config::jwt::JwtKey;
+
+#[test]
+fn debug_output_redacts_secrets() {
+    let mut config = Config::default();
+    config.jwt.keys = vec![JwtKey {
+        kid: "k1".to_string(),
+        algorithm: jsonwebtoken::Algorithm::HS256,
+        secret: Some("jwt-secret-value".to_string()),
+        secret_file: None,
+        secret_env: None,
+        private_key_path: None,
+        public_key_path: None,
+    }];
+    config.mail.smtp_password = Some("smtp-password-value".to_string());
+    config.oidc.client_secret = Some("oidc-secret-value".to_string());
+
+    let output = format!("{config:#?}");
+    for secret in [
+        "jwt-secret-value",
+        "smtp-password-value",
+        "oidc-secret-value",
+    ] {
+        assert!(!output.contains(secret), "{secret} leaked");
+    }
+    assert!(output.contains("smtp_password: Some(\"***\")"), "{output}");
+    assert!(output.contains("kid: \"k1\""));
+}
@@ -0,0 +1,394 @@
+//! 数据库迁移与查询在各数据库上的行为一致性
+//!
+//! 默认只测试 SQLite 内存数据库。设置 `TEST_POSTGRES_URL`、`TEST_MYSQL_URL` 并启用对应 feature 后同时测试
+//! PostgreSQL、MySQL，测试会清空目标数据库，例如:
+//!
+//! `TEST_POSTGRES_URL=postgres://postgres@127.0.0.1:5432/rcw_test cargo test --features postgres --test db_backends`
+
+use actix_web::ResponseError;
+use chrono::{Duration, Utc};
+use rust_class_web::app_config::{db::Db, user_deletion::UserDeletion};
+use rust_class_web::entity::{devices, permissions, role_permissions, roles, user_roles, users};
+use rust_class_web::errors::{AppError, Constraint, code, constraint_violation};
+use rust_class_web::migration::Migrator;
+use rust_class_web::models::pagination::{PageParams, Sort, paginate};
+use rust_class_web::models::rbac::{self, perm, role};
+use rust_class_web::models::user_deletion;
+use rust_class_web::utils::contains_ignore_case;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::NotSet, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr,
+    EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, Set,
+};
+use sea_orm_migration::{MigratorTrait, SchemaManager};
+
+/// 需要测试的数据库连接地址
+fn database_urls() -> Vec<String> {
+    let mut urls = vec!["sqlite::memory:".to_string()];
+    if cfg!(feature = "postgres") {
+        urls.extend(std::env::var("TEST_POSTGRES_URL").ok());
+    }
+    if cfg!(feature = "mysql") {
+        urls.extend(std::env::var("TEST_MYSQL_URL").ok());
+    }
+    urls
+}
+
+/// 连接数据库并从空库执行全部迁移
+async fn fresh_db(url: &str) -> DatabaseConnection {
+    let db = Db {
+        url: url.to_string(),
+        // SQLite 内存数据库每个连接都是独立的库
+        max_connections: 1,
+        min_connections: 1,
+        auto_migrate: false,
+    }
+    .connect()
+    .await
+    .unwrap_or_else(|e| panic!("{url}: {e}"));
+    Migrator::fresh(&db).await.expect("执行迁移失败");
+    db
+}
+
+async fn search(db: &DatabaseConnection, keyword: &str) -> Vec<String> {
+    users::Entity::find()
+        .filter(contains_ignore_case(users::Column::Name, keyword))
+        .order_by_asc(users::Column::Id)
+        .all(db)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|user| user.name)
+        .collect()
+}
+
+#[tokio::test]
+async fn migrations_apply_and_roll_back() {
+    for url in database_urls() {
+        let db = fresh_db(&url).await;
+        let pending = Migrator::get_pending_migrations(&db).await.unwrap();
+        assert!(pending.is_empty(), "{url}");
+        assert_eq!(roles::Entity::find().count(&db).await.unwrap(), 3, "{url}");
+        // 各迁移新增的权限都授予了管理员
+        let admin = roles::Entity::find()
+            .filter(roles::Column::Name.eq(role::ADMIN))
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+        let granted = role_permissions::Entity::find()
+            .filter(role_permissions::Column::RoleId.eq(admin.id))
+            .count(&db)
+            .await
+            .unwrap();
+        assert_eq!(
+            granted,
+            permissions::Entity::find().count(&db).await.unwrap()
+        );
+        // 重复写入内置数据不报错也不产生重复记录
+        rbac::seed(
+            &db,
+            &[(role::ADMIN, "管理员")],
+            &[(perm::USER_READ, "查询用户")],
+            &[(role::ADMIN, perm::USER_READ)],
+        )
+        .await
+        .unwrap();
+        assert_eq!(roles::Entity::find().count(&db).await.unwrap(), 3, "{url}");
+
+        Migrator::down(&db, None).await.unwrap();
+        let manager = SchemaManager::new(&db);
+        assert!(!manager.has_table("users").await.unwrap(), "{url}");
+        assert!(!manager.has_table("roles").await.unwrap(), "{url}");
+    }
+}
+
+fn new_user(email: &str) -> users::ActiveModel {
+    users::ActiveModel {
+        id: NotSet,
+        name: Set("user".to_string()),
+        email: Set(email.to_string()),
+        pass_word: Set(String::new()),
+        status: Set(users::STATUS_NORMAL.to_string()),
+        create_time: Set(Utc::now()),
+        update_time: Set(Utc::now()),
+        deleted_at: NotSet,
+        status_before_delete: NotSet,
+        pending_email: NotSet,
+    }
+}
+
+/// 断言为违反约束的错误，转换后的客户端错误不包含原始的数据库错误信息
+fn assert_violation(url: &str, e: DbErr, constraint: Constraint, error_code: &str, status: u16) {
+    assert_eq!(constraint_violation(&e), Some(constraint), "{url}: {e}");
+    let error = AppError::from(e);
+    assert_eq!(error.code(), error_code, "{url}");
+    assert_eq!(error.status_code().as_u16(), status, "{url}");
+    let message = error.to_string();
+    for word in ["INSERT", "users", "user_roles", "constraint_check"] {
+        assert!(!message.contains(word), "{url}: {message}");
+    }
+}
+
+#[tokio::test]
+async fn constraint_violations_are_classified() {
+    for url in database_urls() {
+        let db = fresh_db(&url).await;
+        let user = new_user("a@example.com").insert(&db).await.unwrap();
+
+        let e = new_user("a@example.com").insert(&db).await.unwrap_err();
+        assert_violation(&url, e, Constraint::Unique, code::DUPLICATE_ENTRY, 409);
+
+        let e = user_roles::ActiveModel {
+            user_id: Set(user.id + 1000),
+            role_id: Set(1),
+            create_time: Set(Utc::now()),
+        }
+        .insert(&db)
+        .await
+        .unwrap_err();
+        assert_violation(
+            &url,
+            e,
+            Constraint::ForeignKey,
+            code::INVALID_REFERENCE,
+            400,
+        );
+
+        let e = users::ActiveModel {
+            name: NotSet,
+            ..new_user("b@example.com")
+        }
+        .insert(&db)
+        .await
+        .unwrap_err();
+        assert_violation(&url, e, Constraint::NotNull, code::MISSING_FIELD, 400);
+
+        db.execute_unprepared("CREATE TABLE constraint_check (n INTEGER CHECK (n > 0))")
+            .await
+            .unwrap();
+        let e = db
+            .execute_unprepared("INSERT INTO constraint_check (n) VALUES (0)")
+            .await
+            .unwrap_err();
+        assert_violation(&url, e, Constraint::Check, code::INVALID_VALUE, 400);
+        db.execute_unprepared("DROP TABLE constraint_check")
+            .await
+            .unwrap();
+
+        // 其他数据库错误不做转换
+        let e = db
+            .execute_unprepared("SELECT * FROM missing_table")
+            .await
+            .unwrap_err();
+        assert_eq!(constraint_violation(&e), None, "{url}");
+        assert_eq!(AppError::from(e).code(), code::DATABASE_ERROR, "{url}");
+    }
+}
+
+#[tokio::test]
+async fn deleted_users_are_purged_after_retention() {
+    let config = UserDeletion {
+        retention_days: 30,
+        purge_interval: 0,
+    };
+    for url in database_urls() {
+        let db = fresh_db(&url).await;
+        let expired = new_user("expired@example.com").insert(&db).await.unwrap();
+        let recent = new_user("recent@example.com").insert(&db).await.unwrap();
+        let kept = new_user("kept@example.com").insert(&db).await.unwrap();
+        rbac::assign_role(&db, expired.id, rbac::DEFAULT_ROLE)
+            .await
+            .unwrap();
+        for user in [&expired, &recent] {
+            assert!(
+                user_deletion::soft_delete(&db, user.id).await.unwrap(),
+                "{url}"
+            );
+        }
+        // 重复删除时返回 false
+        assert!(
+            !user_deletion::soft_delete(&db, recent.id).await.unwrap(),
+            "{url}"
+        );
+        // 设备表的外键不会级联删除，需要先删除设备
+        devices::ActiveModel {
+            user_id: Set(expired.id),
+            token: Set("token".to_string()),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        users::Entity::update_many()
+            .col_expr(
+                users::Column::DeletedAt,
+                (Utc::now() - Duration::days(31)).into(),
+            )
+            .filter(users::Column::Id.eq(expired.id))
+            .exec(&db)
+            .await
+            .unwrap();
+
+        let active: Vec<_> = users::Entity::find_active()
+            .all(&db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|user| user.id)
+            .collect();
+        assert_eq!(active, [kept.id], "{url}");
+
+        assert_eq!(
+            user_deletion::purge_expired(&db, &config).await.unwrap(),
+            1,
+            "{url}"
+        );
+        let remaining: Vec<_> = users::Entity::find()
+            .order_by_asc(users::Column::Id)
+            .all(&db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|user| (user.id, user.status))
+            .collect();
+        assert_eq!(
+            remaining,
+            [
+                (recent.id, users::STATUS_DELETED.to_string()),
+                (kept.id, users::STATUS_NORMAL.to_string()),
+            ],
+            "{url}"
+        );
+        assert_eq!(
+            user_roles::Entity::find().count(&db).await.unwrap(),
+            0,
+            "{url}"
+        );
+        assert_eq!(
+            devices::Entity::find().count(&db).await.unwrap(),
+            0,
+            "{url}"
+        );
+        assert_eq!(
+            user_deletion::purge_expired(&db, &config).await.unwrap(),
+            0,
+            "{url}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn contains_search_is_consistent() {
+    for url in database_urls() {
+        let db = fresh_db(&url).await;
+        for (i, name) in ["Alice", "alice_b", "100%", "Bob", "张三"]
+            .iter()
+            .enumerate()
+        {
+            users::ActiveModel {
+                id: NotSet,
+                name: Set(name.to_string()),
+                email: Set(format!("u{i}@example.com")),
+                pass_word: Set(String::new()),
+                status: Set(users::STATUS_NORMAL.to_string()),
+                create_time: Set(Utc::now()),
+                update_time: Set(Utc::now()),
+                deleted_at: NotSet,
+                status_before_delete: NotSet,
+                pending_email: NotSet,
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+
+        assert_eq!(search(&db, "ALICE").await, ["Alice", "alice_b"], "{url}");
+        assert_eq!(search(&db, "_").await, ["alice_b"], "{url}");
+        assert_eq!(search(&db, "%").await, ["100%"], "{url}");
+        assert_eq!(search(&db, "三").await, ["张三"], "{url}");
+        assert_eq!(search(&db, "").await.len(), 5, "{url}");
+        assert!(search(&db, "carol").await.is_empty(), "{url}");
+    }
+}
+
+#[tokio::test]
+async fn cursor_pagination_is_consistent() {
+    let allowed = [
+        ("id", users::Column::Id),
+        ("name", users::Column::Name),
+        ("createTime", users::Column::CreateTime),
+    ];
+    for url in database_urls() {
+        let db = fresh_db(&url).await;
+        let start = Utc::now();
+        // 部分记录的排序字段相同，需要按主键区分先后
+        for (i, (name, minutes)) in [("c", 0), ("a", 1), ("b", 1), ("a", 2), ("d", 3)]
+            .iter()
+            .enumerate()
+        {
+            users::ActiveModel {
+                id: NotSet,
+                name: Set(name.to_string()),
+                email: Set(format!("u{i}@example.com")),
+                pass_word: Set(String::new()),
+                status: Set(users::STATUS_NORMAL.to_string()),
+                create_time: Set(start + Duration::minutes(*minutes)),
+                update_time: NotSet,
+                deleted_at: NotSet,
+                status_before_delete: NotSet,
+                pending_email: NotSet,
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+
+        for (sort, expected) in [
+            ("name", vec![2, 4, 3, 1, 5]),
+            ("-createTime", vec![5, 4, 3, 2, 1]),
+            ("-id", vec![5, 4, 3, 2, 1]),
+        ] {
+            let sort = Sort::parse(Some(sort), &allowed).unwrap();
+            let mut params = PageParams {
+                size: Some(2),
+                ..Default::default()
+            };
+            let mut ids = Vec::new();
+            loop {
+                let page = paginate(
+                    &db,
+                    users::Entity::find(),
+                    users::Column::Id,
+                    &sort,
+                    &params,
+                )
+                .await
+                .unwrap();
+                assert_eq!(page.total, 5, "{url}");
+                ids.extend(page.items.iter().map(|user| user.id));
+                match page.next_cursor {
+                    Some(cursor) => params.cursor = Some(cursor),
+                    None => break,
+                }
+            }
+            assert_eq!(ids, expected, "{url}: {sort:?}");
+
+            // 页码分页的结果与游标分页一致
+            let page = paginate(
+                &db,
+                users::Entity::find(),
+                users::Column::Id,
+                &sort,
+                &PageParams {
+                    page: Some(2),
+                    size: Some(2),
+                    cursor: None,
+                },
+            )
+            .await
+            .unwrap();
+            let ids: Vec<_> = page.items.iter().map(|user| user.id).collect();
+            assert_eq!(ids, expected[2..4], "{url}: {sort:?}");
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//! 文档存储各实现的行为一致性
+//!
+//! 默认只测试内存实现。设置 `TEST_MONGODB_URL` 后同时测试 MongoDB，测试会清空 `rcw_test` 数据库中用到的集合。
+//! MongoDB 每 60 秒才清理一次过期文档，TTL 测试最长需要等待约两分钟，例如:
+//!
+//! `TEST_MONGODB_URL=mongodb://127.0.0.1:27017 cargo test --test document_store`
+
+use chrono::{Duration as ChronoDuration, Utc};
+use rust_class_web::models::document_store::{
+    self, Document, DocumentStore, Filter, FindOptions, Index, SortOrder, memory::MemoryStore,
+};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 需要测试的文档存储
+async fn stores() -> Vec<(&'static str, Arc<dyn DocumentStore>)> {
+    let mut stores: Vec<(&'static str, Arc<dyn DocumentStore>)> =
+        vec![("memory", Arc::new(MemoryStore::default()))];
+    stores.extend(mongo_store().await.map(|store| ("mongodb", store)));
+    stores
+}
+
+#[cfg(feature = "mongodb")]
+async fn mongo_store() -> Option<Arc<dyn DocumentStore>> {
+    use rust_class_web::app_config::mongodb::Mongodb;
+    use rust_class_web::models::document_store::{StoreStatus, mongo::MongoStore};
+
+    let url = std::env::var("TEST_MONGODB_URL").ok()?;
+    let store = MongoStore::start(&Mongodb {
+        url,
+        database: "rcw_test".to_string(),
+        ..Default::default()
+    });
+    for _ in 0..100 {
+        if store.status() != StoreStatus::Connecting {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(store.status(), StoreStatus::Up, "MongoDB 连接失败");
+    Some(store)
+}
+
+#[cfg(not(feature = "mongodb"))]
+async fn mongo_store() -> Option<Arc<dyn DocumentStore>> {
+    None
+}
+
+/// 清空集合并写入测试数据
+async fn seed(store: &dyn DocumentStore, collection: &str, documents: Vec<Value>) {
+    store.delete_many(collection, &Filter::new()).await.unwrap();
+    let documents = documents
+        .into_iter()
+        .map(|document| match document {
+            Value::Object(document) => document,
+            _ => panic!("文档必须是对象"),
+        })
+        .collect();
+    store.insert_many(collection, documents).await.unwrap();
+}
+
+async fn find(store: &dyn DocumentStore, collection: &str, filter: Filter) -> Vec<Document> {
+    let options = FindOptions {
+        sort: vec![("n".to_string(), SortOrder::Asc)],
+        ..Default::default()
+    };
+    store.find(collection, &filter, &options).await.unwrap()
+}
+
+fn names(documents: &[Document]) -> Vec<&str> {
+    documents
+        .iter()
+        .map(|document| document["name"].as_str().unwrap())
+        .collect()
+}
+
+fn sample() -> Vec<Value> {
+    let start = Utc::now() - ChronoDuration::hours(1);
+    ["a", "b", "c", "d"]
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            json!({
+                "n": i as i64,
+                "name": name,
+                "group": if i % 2 == 0 { "even" } else { "odd" },
+                "at": document_store::date(start + ChronoDuration::minutes(i as i64 * 10)),
+            })
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn insert_and_find() {
+    for (backend, store) in stores().await {
+        let store = store.as_ref();
+        let sample = sample();
+        seed(store, "insert_and_find", sample.clone()).await;
+
+        let documents = find(store, "insert_and_find", Filter::new()).await;
+        assert_eq!(names(&documents), ["a", "b", "c", "d"], "{backend}");
+        // 写入时自动生成 `_id`，时间字段原样读出
+        assert!(documents.iter().all(|d| d.contains_key("_id")), "{backend}");
+        assert_eq!(documents[0]["at"], sample[0]["at"], "{backend}");
+
+        let options = FindOptions {
+            sort: vec![("n".to_string(), SortOrder::Desc)],
+            skip: 1,
+            limit: Some(2),
+        };
+        let documents = store
+            .find("insert_and_find", &Filter::new(), &options)
+            .await
+            .unwrap();
+        assert_eq!(names(&documents), ["c", "b"], "{backend}");
+
+        let empty = find(store, "missing_collection", Filter::new()).await;
+        assert!(empty.is_empty(), "{backend}");
+    }
+}
+
+#[tokio::test]
+async fn find_with_filters() {
+    for (backend, store) in stores().await {
+        let store = store.as_ref();
+        let sample = sample();
+        seed(store, "find_with_filters", sample.clone()).await;
+        let at = |i: usize| sample[i]["at"].clone();
+        let cases = [
+            (Filter::new().eq("group", "even"), vec!["a", "c"]),
+            (Filter::new().eq("n", 2), vec!["c"]),
+            (
+                Filter::new().eq("missing", Value::Null),
+                vec!["a", "b", "c", "d"],
+            ),
+            (Filter::new().gt("n", 1), vec!["c", "d"]),
+            (Filter::new().gte("n", 1).lt("n", 3), vec!["b", "c"]),
+            (Filter::new().lte("name", "b"), vec!["a", "b"]),
+            (
+                Filter::new().gte("at", at(1)).lt("at", at(3)),
+                vec!["b", "c"],
+            ),
+            (Filter::new().eq("group", "odd").gt("at", at(1)), vec!["d"]),
+            // 不同类型的值不能比较大小
+            (Filter::new().gt("name", 0), vec![]),
+            (Filter::new().eq("group", "none"), vec![]),
+        ];
+        for (filter, expected) in cases {
+            let documents = find(store, "find_with_filters", filter.clone()).await;
+            assert_eq!(names(&documents), expected, "{backend}: {filter:?}");
+        }
+    }
+}
+
+#[tokio::test]
+async fn update_documents() {
+    for (backend, store) in stores().await {
+        let store = store.as_ref();
+        seed(store, "update_documents", sample()).await;
+
+        let mut set = Document::new();
+        set.insert("group".to_string(), json!("updated"));
+        set.insert("flag".to_string(), json!(true));
+        let updated = store
+            .update_many("update_documents", &Filter::new().gte("n", 2), set)
+            .await
+            .unwrap();
+        assert_eq!(updated, 2, "{backend}");
+
+        let documents = find(store, "update_documents", Filter::new().eq("flag", true)).await;
+        assert_eq!(names(&documents), ["c", "d"], "{backend}");
+        assert!(
+            documents.iter().all(|d| d["group"] == "updated"),
+            "{backend}"
+        );
+        let documents = find(store, "update_documents", Filter::new().eq("group", "even")).await;
+        assert_eq!(names(&documents), ["a"], "{backend}");
+
+        let updated = store
+            .update_many(
+                "update_documents",
+                &Filter::new().eq("n", 99),
+                Document::new(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(updated, 0, "{backend}");
+    }
+}
+
+#[tokio::test]
+async fn delete_documents() {
+    for (backend, store) in stores().await {
+        let store = store.as_ref();
+        seed(store, "delete_documents", sample()).await;
+
+        let deleted = store
+            .delete_many("delete_documents", &Filter::new().eq("group", "odd"))
+            .await
+            .unwrap();
+        assert_eq!(deleted, 2, "{backend}");
+        let documents = find(store, "delete_documents", Filter::new()).await;
+        assert_eq!(names(&documents), ["a", "c"], "{backend}");
+
+        let deleted = store
+            .delete_many("delete_documents", &Filter::new())
+            .await
+            .unwrap();
+        assert_eq!(deleted, 2, "{backend}");
+        assert!(
+            find(store, "delete_documents", Filter::new())
+                .await
+                .is_empty(),
+            "{backend}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn ttl_expiry() {
+    for (backend, store) in stores().await {
+        let store = store.as_ref();
+        let now = Utc::now();
+        seed(
+            store,
+            "ttl_expiry",
+            vec![
+                json!({ "n": 0, "name": "expired", "at": document_store::date(now - ChronoDuration::hours(1)) }),
+                json!({ "n": 1, "name": "fresh", "at": document_store::date(now) }),
+                json!({ "n": 2, "name": "no_date" }),
+            ],
+        )
+        .await;
+        let index = Index {
+            keys: vec![("at".to_string(), SortOrder::Asc)],
+            expire_after: Some(Duration::from_secs(600)),
+        };
+        store.create_index("ttl_expiry", &index).await.unwrap();
+        // 重复创建同样的索引不报错
+        store.create_index("ttl_expiry", &index).await.unwrap();
+
+        let mut documents = find(store, "ttl_expiry", Filter::new()).await;
+        for _ in 0..150 {
+            if documents.len() < 3 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            documents = find(store, "ttl_expiry", Filter::new()).await;
+        }
+        assert_eq!(names(&documents), ["fresh", "no_date"], "{backend}");
+    }
+}
@@ -0,0 +1,83 @@
+//! 修改邮箱: 新邮箱验证前原邮箱与会话保持不变，验证后替换邮箱并要求重新登录
+
+use actix_web::{http::StatusCode, test};
+use rust_class_web::entity::users;
+use rust_class_web::state::AppState;
+use sea_orm::EntityTrait;
+use serde_json::json;
+
+#[macro_use]
+mod common;
+
+const EMAIL: &str = "alice@example.com";
+const NEW_EMAIL: &str = "alice@example.org";
+
+fn verify(token: &str) -> test::TestRequest {
+    test::TestRequest::get().uri(&format!("/api/users/verify?token={token}"))
+}
+
+#[actix_web::test]
+async fn email_changes_take_effect_after_verification() {
+    let mut state = AppState::new(&common::config()).await.unwrap();
+    let mailer = common::capture_mail(&mut state);
+    let db = state.db_pool.clone();
+    let app = test::init_service(common::app(&state)).await;
+
+    let created = sign_up!(app, "alice", EMAIL);
+    let user_id = created["data"]["id"].as_i64().unwrap();
+    common::verify_email(&db, EMAIL).await;
+    let token = login!(app, EMAIL);
+    let update = |email: &str| {
+        test::TestRequest::patch()
+            .uri(&format!("/api/users/{user_id}"))
+            .set_json(json!({ "email": email }))
+    };
+    let sessions = || test::TestRequest::get().uri("/api/sessions");
+
+    // 提交新邮箱后只记录为待验证，原邮箱、状态与会话不变
+    let (status, body) = call!(app, update(NEW_EMAIL), Some(&token));
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["data"]["email"], EMAIL);
+    assert_eq!(body["data"]["pendingEmail"], NEW_EMAIL);
+    assert_eq!(body["data"]["status"], users::STATUS_NORMAL);
+    let (status, _) = call!(app, sessions(), Some(&token));
+    assert_eq!(status, StatusCode::OK);
+    login!(app, EMAIL);
+
+    // 验证新邮箱后替换邮箱，原有会话失效，需要使用新邮箱登录
+    let link = common::mailed_token(&mailer, NEW_EMAIL);
+    let (status, body) = call!(app, verify(&link), None);
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let (status, _) = call!(app, sessions(), Some(&token));
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/users/login")
+            .set_json(json!({ "email": EMAIL, "pass_word": common::PASSWORD })),
+        None
+    );
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let token = login!(app, NEW_EMAIL);
+    let user = users::Entity::find_by_id(user_id)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(user.email, NEW_EMAIL);
+    assert!(user.pending_email.is_none());
+    // 验证链接只能使用一次
+    let (status, _) = call!(app, verify(&link), None);
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // 验证前新邮箱被他人注册时不能完成修改
+    let (status, _) = call!(app, update("taken@example.com"), Some(&token));
+    assert_eq!(status, StatusCode::OK);
+    let link = common::mailed_token(&mailer, "taken@example.com");
+    sign_up!(app, "bob", "taken@example.com");
+    let (status, body) = call!(app, verify(&link), None);
+    assert_eq!(status, StatusCode::CONFLICT, "{body}");
+    assert_eq!(body["errorCode"], "EMAIL_TAKEN");
+    let (status, body) = call!(app, sessions(), Some(&token));
+    assert_eq!(status, StatusCode::OK, "{body}");
+}
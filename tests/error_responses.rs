@@ -0,0 +1,173 @@
+//! 统一响应格式: 错误码、字段校验详情与请求 ID
+
+use actix_web::{http::StatusCode, test};
+use rust_class_web::state::AppState;
+use serde_json::{Value, json};
+
+mod common;
+
+const ADMIN_EMAIL: &str = "admin@example.com";
+
+/// 发送请求，返回状态码、`X-Request-Id` 响应头与响应内容
+macro_rules! call {
+    ($app:expr, $request:expr, $token:expr, $body:expr) => {{
+        let mut request = $request;
+        let token: Option<&str> = $token;
+        let body: Option<Value> = $body;
+        if let Some(token) = token {
+            request = request.insert_header(("Authorization", format!("Bearer {token}")));
+        }
+        if let Some(body) = body {
+            request = request.set_json(body);
+        }
+        let response = test::call_service(&$app, request.to_request()).await;
+        let status = response.status();
+        let request_id = response
+            .headers()
+            .get("x-request-id")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let body: Value = test::read_body_json(response).await;
+        (status, request_id, body)
+    }};
+}
+
+/// 断言为统一格式的错误响应，返回的请求 ID 与响应头一致
+fn assert_error(
+    (status, request_id, body): &(StatusCode, Option<String>, Value),
+    expected_status: StatusCode,
+    error_code: &str,
+) {
+    assert_eq!(*status, expected_status, "{body}");
+    assert_eq!(body["code"], expected_status.as_u16(), "{body}");
+    assert_eq!(body["errorCode"], error_code, "{body}");
+    assert!(body["data"].is_null(), "{body}");
+    assert!(body["message"].as_str().is_some_and(|m| !m.is_empty()));
+    let request_id = request_id.as_deref().expect("缺少 X-Request-Id 响应头");
+    assert_eq!(body["requestId"], request_id, "{body}");
+}
+
+#[actix_web::test]
+async fn errors_use_envelope_with_code_and_request_id() {
+    let mut config = common::config();
+    config.auth.admins = vec![ADMIN_EMAIL.to_string()];
+    let state = AppState::new(&config).await.unwrap();
+    let db = state.db_pool.clone();
+    let app = test::init_service(common::app(&state)).await;
+
+    // 鉴权中间件返回的错误
+    let response = call!(app, test::TestRequest::get().uri("/api/users"), None, None);
+    assert_error(&response, StatusCode::UNAUTHORIZED, "UNAUTHORIZED");
+
+    // 参数校验失败时返回各字段的错误信息
+    let response = call!(
+        app,
+        test::TestRequest::post().uri("/api/users/create"),
+        None,
+        Some(json!({ "name": "a", "email": "not-an-email", "pass_word": "password" }))
+    );
+    assert_error(&response, StatusCode::BAD_REQUEST, "VALIDATION_FAILED");
+    assert_eq!(
+        response.2["errors"],
+        json!([{ "field": "email", "message": "无效的邮箱地址" }])
+    );
+
+    // 请求体无法解析
+    let response = call!(
+        app,
+        test::TestRequest::post().uri("/api/users/create"),
+        None,
+        Some(json!({ "name": "a" }))
+    );
+    assert_error(&response, StatusCode::BAD_REQUEST, "INVALID_BODY");
+
+    let body = json!({ "name": "admin", "email": ADMIN_EMAIL, "pass_word": "password" });
+    let (status, request_id, created) = call!(
+        app,
+        test::TestRequest::post().uri("/api/users/create"),
+        None,
+        Some(body)
+    );
+    // 成功的响应不包含错误相关的字段，请求 ID 只通过响应头返回
+    assert_eq!(status, StatusCode::OK);
+    assert!(request_id.is_some());
+    assert_eq!(created["code"], 200);
+    assert_eq!(created["message"], "ok");
+    for field in ["errorCode", "errors", "requestId"] {
+        assert!(created.get(field).is_none(), "{created}");
+    }
+
+    // 违反唯一约束时返回 409，不返回数据库的原始错误信息
+    let response = call!(
+        app,
+        test::TestRequest::post().uri("/api/users/create"),
+        None,
+        Some(json!({ "name": "other", "email": ADMIN_EMAIL, "pass_word": "password" }))
+    );
+    assert_error(&response, StatusCode::CONFLICT, "EMAIL_TAKEN");
+    let message = response.2["message"].as_str().unwrap();
+    assert!(!message.contains("UNIQUE"), "{message}");
+
+    // 配置中的管理员邮箱验证后才拥有管理员权限
+    let (_, _, login) = call!(
+        app,
+        test::TestRequest::post().uri("/api/users/login"),
+        None,
+        Some(json!({ "email": ADMIN_EMAIL, "pass_word": "password" }))
+    );
+    let pending_token = login["data"]["token"].as_str().unwrap();
+    let response = call!(
+        app,
+        test::TestRequest::get().uri("/api/users"),
+        Some(pending_token),
+        None
+    );
+    assert_error(&response, StatusCode::FORBIDDEN, "PERMISSION_DENIED");
+    common::verify_email(&db, ADMIN_EMAIL).await;
+    let response = call!(
+        app,
+        test::TestRequest::post().uri("/api/users/login"),
+        None,
+        Some(json!({ "email": ADMIN_EMAIL, "pass_word": "wrong-password" }))
+    );
+    assert_error(&response, StatusCode::UNAUTHORIZED, "LOGIN_FAILED");
+
+    let (_, _, login) = call!(
+        app,
+        test::TestRequest::post().uri("/api/users/login"),
+        None,
+        Some(json!({ "email": ADMIN_EMAIL, "pass_word": "password" }))
+    );
+    let token = login["data"]["token"].as_str().unwrap();
+    let response = call!(
+        app,
+        test::TestRequest::get().uri("/api/users"),
+        Some(pending_token),
+        None
+    );
+    assert_eq!(response.0, StatusCode::OK, "{}", response.2);
+
+    let response = call!(
+        app,
+        test::TestRequest::get().uri("/api/users/999"),
+        Some(token),
+        None
+    );
+    assert_error(&response, StatusCode::NOT_FOUND, "USER_NOT_FOUND");
+
+    let response = call!(
+        app,
+        test::TestRequest::get().uri("/api/users?sort=unknown"),
+        Some(token),
+        None
+    );
+    assert_error(&response, StatusCode::BAD_REQUEST, "BAD_REQUEST");
+
+    let response = call!(
+        app,
+        test::TestRequest::get().uri("/api/users?size=abc"),
+        Some(token),
+        None
+    );
+    assert_error(&response, StatusCode::BAD_REQUEST, "INVALID_QUERY");
+}
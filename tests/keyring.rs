@@ -0,0 +1,155 @@
+//! JWT 密钥环: 各算法的签名与校验、JWKS 公钥以及密钥轮换
+//!
+//! `tests/fixtures/keyring` 中的密钥由 openssl 生成，仅用于测试
+
+use actix_web::http::StatusCode;
+use actix_web::test::{TestRequest, init_service};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode};
+use rust_class_web::app_config::jwt::{Jwt, JwtKey};
+use rust_class_web::models::keyring::KeyRing;
+use rust_class_web::state::AppState;
+use serde_json::{Value, json};
+
+#[macro_use]
+mod common;
+
+fn fixture(name: &str) -> Option<String> {
+    Some(format!(
+        "{}/tests/fixtures/keyring/{name}",
+        env!("CARGO_MANIFEST_DIR")
+    ))
+}
+
+/// 非对称密钥，`private` 为空时表示仅保留用于校验的旧密钥
+fn pem_key(kid: &str, algorithm: Algorithm, private: Option<&str>, public: &str) -> JwtKey {
+    JwtKey {
+        kid: kid.to_string(),
+        algorithm,
+        secret: None,
+        secret_file: None,
+        secret_env: None,
+        private_key_path: private.and_then(fixture),
+        public_key_path: fixture(public),
+    }
+}
+
+fn hmac_key(kid: &str) -> JwtKey {
+    JwtKey {
+        kid: kid.to_string(),
+        algorithm: Algorithm::HS256,
+        secret: Some("test-secret".to_string()),
+        secret_file: None,
+        secret_env: None,
+        private_key_path: None,
+        public_key_path: None,
+    }
+}
+
+fn key_ring(keys: Vec<JwtKey>, max_keys: usize) -> KeyRing {
+    KeyRing::from_config(&Jwt {
+        keys,
+        max_keys,
+        ..Jwt::default()
+    })
+    .unwrap()
+}
+
+fn claims() -> Value {
+    json!({ "sub": "alice@example.com", "exp": chrono::Utc::now().timestamp() + 600 })
+}
+
+/// 各算法签发的 token 能通过自身校验，也能用 JWKS 接口公开的公钥校验
+#[actix_web::test]
+async fn jwks_public_keys_verify_signed_tokens() {
+    let cases = [
+        ("rsa", Algorithm::RS256, "rsa.key", "rsa.pub"),
+        ("rsa-pkcs1", Algorithm::RS256, "rsa.key", "rsa-pkcs1.pub"),
+        ("rsa-pss", Algorithm::PS256, "rsa.key", "rsa.pub"),
+        ("ec256", Algorithm::ES256, "ec256.key", "ec256.pub"),
+        ("ec384", Algorithm::ES384, "ec384.key", "ec384.pub"),
+        ("ed25519", Algorithm::EdDSA, "ed25519.key", "ed25519.pub"),
+    ];
+    for (kid, algorithm, private, public) in cases {
+        let mut config = common::config();
+        config.jwt.keys = vec![pem_key(kid, algorithm, Some(private), public)];
+        let state = AppState::new(&config).await.unwrap();
+        let app = init_service(common::app(&state)).await;
+
+        let token = state.jwt_keys.sign(&claims()).unwrap();
+        state.jwt_keys.verify::<Value>(&token).unwrap();
+
+        let (status, body) = call!(app, TestRequest::get().uri("/.well-known/jwks.json"), None);
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let jwks: JwkSet = serde_json::from_value(body).unwrap();
+        assert_eq!(jwks.keys.len(), 1, "{kid}");
+        let jwk = jwks.find(kid).unwrap_or_else(|| panic!("JWKS 缺少 {kid}"));
+        let decoding = DecodingKey::from_jwk(jwk).unwrap();
+        let data = decode::<Value>(&token, &decoding, &Validation::new(algorithm))
+            .unwrap_or_else(|e| panic!("{kid} 的 JWKS 公钥无法校验 token: {e}"));
+        assert_eq!(data.claims["sub"], "alice@example.com");
+    }
+}
+
+/// HMAC 密钥不公开，其他服务无法离线校验
+#[test]
+fn hmac_keys_are_not_published() {
+    let ring = key_ring(
+        vec![
+            pem_key("ec256", Algorithm::ES256, None, "ec256.pub"),
+            hmac_key("hmac"),
+        ],
+        3,
+    );
+    let kids = ring
+        .jwks()
+        .keys
+        .into_iter()
+        .filter_map(|jwk| jwk.common.key_id)
+        .collect::<Vec<_>>();
+    assert_eq!(kids, ["ec256"]);
+    let token = ring.sign(&claims()).unwrap();
+    ring.verify::<Value>(&token).unwrap();
+}
+
+/// 轮换后旧密钥签发的 token 仍然有效，直到旧密钥超出 `max_keys` 被丢弃
+#[test]
+fn retired_keys_verify_until_dropped() {
+    let old = || pem_key("old", Algorithm::ES256, Some("ec256.key"), "ec256.pub");
+    let old_token = key_ring(vec![old()], 3).sign(&claims()).unwrap();
+
+    // 新密钥签名，旧密钥只保留公钥用于校验
+    let retired = pem_key("old", Algorithm::ES256, None, "ec256.pub");
+    let newest = || pem_key("new", Algorithm::EdDSA, Some("ed25519.key"), "ed25519.pub");
+    let ring = key_ring(vec![retired.clone(), newest()], 3);
+    assert_eq!(
+        ring.verify::<Value>(&old_token).unwrap().claims["sub"],
+        "alice@example.com"
+    );
+    let new_token = ring.sign(&claims()).unwrap();
+    let header = jsonwebtoken::decode_header(&new_token).unwrap();
+    assert_eq!(header.kid.as_deref(), Some("new"));
+    assert_eq!(header.alg, Algorithm::EdDSA);
+    assert!(ring.jwks().find("old").is_some());
+
+    // 再轮换一次，旧密钥超出 `max_keys` 后签发的 token 失效，也不再公开
+    let middle = pem_key("middle", Algorithm::RS256, None, "rsa.pub");
+    let ring = key_ring(vec![retired, middle, newest()], 2);
+    assert!(ring.verify::<Value>(&old_token).is_err());
+    assert!(ring.jwks().find("old").is_none());
+    ring.verify::<Value>(&new_token).unwrap();
+}
+
+/// 最新的密钥用于签名，缺少私钥时拒绝启动
+#[test]
+fn newest_key_requires_private_key() {
+    let e = KeyRing::from_config(&Jwt {
+        keys: vec![
+            hmac_key("hmac"),
+            pem_key("ec256", Algorithm::ES256, None, "ec256.pub"),
+        ],
+        ..Jwt::default()
+    })
+    .unwrap_err();
+    assert!(e.to_string().contains("ec256"), "{e}");
+}
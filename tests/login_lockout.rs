@@ -0,0 +1,168 @@
+//! 登录失败锁定: 账号与 IP 的失败次数阈值，客户端 IP 不能通过伪造请求头绕过，失败记录有数量上限并定期清理
+
+use actix_web::{http::StatusCode, test, web::Data};
+use rust_class_web::app_config::Config;
+use rust_class_web::models::client_info::ClientInfo;
+use rust_class_web::models::login_guard::{LockoutKind, LoginGuard};
+use rust_class_web::state::AppState;
+use serde_json::json;
+use std::net::SocketAddr;
+
+#[macro_use]
+mod common;
+
+const EMAIL: &str = "alice@example.com";
+
+fn test_config() -> Config {
+    let mut config = common::config();
+    config.login_guard.max_account_failures = 3;
+    config.login_guard.max_ip_failures = 5;
+    config
+}
+
+fn peer(ip: &str) -> SocketAddr {
+    format!("{ip}:40000").parse().unwrap()
+}
+
+/// 从 `ip` 发起登录，附带伪造的 `X-Forwarded-For`，返回状态码与错误码
+macro_rules! attempt {
+    ($app:expr, $ip:expr, $forwarded:expr, $email:expr, $password:expr) => {{
+        let request = test::TestRequest::post()
+            .uri("/api/users/login")
+            .peer_addr(peer($ip))
+            .insert_header(("X-Forwarded-For", $forwarded))
+            .set_json(json!({ "email": $email, "pass_word": $password }));
+        let (status, body) = call!($app, request, None);
+        (status, body["errorCode"].as_str().map(str::to_string))
+    }};
+}
+
+#[actix_web::test]
+async fn lockout_thresholds_apply_to_accounts_and_ips() {
+    let state = AppState::new(&test_config()).await.unwrap();
+    let app = test::init_service(common::app(&state)).await;
+    for email in [EMAIL, "bob@example.com"] {
+        sign_up!(app, "user", email);
+    }
+
+    // 阈值之前登录成功会清零账号的失败次数
+    for _ in 0..2 {
+        let result = attempt!(app, "198.51.100.1", "1.1.1.1", EMAIL, "wrong");
+        assert_eq!(result.1.as_deref(), Some("LOGIN_FAILED"));
+    }
+    let result = attempt!(app, "198.51.100.2", "1.1.1.1", EMAIL, common::PASSWORD);
+    assert_eq!(result.0, StatusCode::OK);
+
+    // 账号连续失败达到阈值后锁定，即使密码正确、换了 IP 也不能登录
+    for i in 0..3 {
+        let ip = format!("198.51.100.{}", 10 + i);
+        let result = attempt!(app, &ip, "1.1.1.1", EMAIL, "wrong");
+        assert_eq!(result.1.as_deref(), Some("LOGIN_FAILED"), "第 {i} 次");
+    }
+    let result = attempt!(app, "198.51.100.20", "1.1.1.1", EMAIL, common::PASSWORD);
+    assert_eq!(result.0, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(result.1.as_deref(), Some("ACCOUNT_LOCKED"));
+
+    // 同一 IP 的失败达到阈值后锁定，轮换 `X-Forwarded-For` 不能绕过
+    for i in 0..5 {
+        let forwarded = format!("203.0.113.{i}");
+        let email = format!("unknown{i}@example.com");
+        let result = attempt!(app, "192.0.2.1", forwarded.as_str(), email, "wrong");
+        assert_eq!(result.1.as_deref(), Some("LOGIN_FAILED"), "第 {i} 次");
+    }
+    let result = attempt!(
+        app,
+        "192.0.2.1",
+        "203.0.113.99",
+        "bob@example.com",
+        common::PASSWORD
+    );
+    assert_eq!(result.0, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(result.1.as_deref(), Some("ACCOUNT_LOCKED"));
+    // 其他 IP 不受影响
+    let result = attempt!(
+        app,
+        "192.0.2.2",
+        "203.0.113.99",
+        "bob@example.com",
+        common::PASSWORD
+    );
+    assert_eq!(result.0, StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn forwarded_for_is_only_trusted_from_proxies() {
+    let mut config = test_config();
+    config.server.trusted_proxies = vec!["10.0.0.0/8".to_string(), "192.0.2.1".to_string()];
+    let state = Data::new(AppState::new(&config).await.unwrap());
+    let client_ip = |peer_ip: &str, forwarded: Option<&str>| {
+        let mut request = test::TestRequest::default()
+            .peer_addr(peer(peer_ip))
+            .app_data(state.clone());
+        if let Some(forwarded) = forwarded {
+            request = request.insert_header(("X-Forwarded-For", forwarded));
+        }
+        ClientInfo::new(&request.to_http_request()).ip
+    };
+    let cases = [
+        // 直连的客户端伪造请求头无效
+        ("198.51.100.1", Some("1.1.1.1"), "198.51.100.1"),
+        ("198.51.100.1", None, "198.51.100.1"),
+        // 受信任的代理转发时取最右侧不受信任的地址
+        ("10.1.2.3", Some("1.1.1.1"), "1.1.1.1"),
+        ("192.0.2.1", Some("1.1.1.1, 2.2.2.2"), "2.2.2.2"),
+        ("10.1.2.3", Some("1.1.1.1, 2.2.2.2, 10.0.0.5"), "2.2.2.2"),
+        // 受信任的代理没有转发客户端地址时使用代理地址
+        ("10.1.2.3", None, "10.1.2.3"),
+        ("10.1.2.3", Some("not-an-ip"), "10.1.2.3"),
+        ("192.0.2.2", Some("1.1.1.1"), "192.0.2.2"),
+    ];
+    for (peer_ip, forwarded, expected) in cases {
+        assert_eq!(
+            client_ip(peer_ip, forwarded).as_deref(),
+            Some(expected),
+            "{peer_ip} {forwarded:?}"
+        );
+    }
+}
+
+/// 达到上限后新的邮箱不再计数，已有的记录继续计数直到被清理
+#[actix_web::test]
+async fn entries_are_capped_and_pruned() {
+    let mut config = test_config().login_guard;
+    config.max_entries = 2;
+    let guard = LoginGuard::new(&config);
+    guard.record_failure("a@example.com", None);
+    guard.record_failure("b@example.com", None);
+    for _ in 0..3 {
+        guard.record_failure("c@example.com", None);
+    }
+    let keys = |guard: &LoginGuard| {
+        let mut keys = guard
+            .list()
+            .into_iter()
+            .map(|info| info.key)
+            .collect::<Vec<_>>();
+        keys.sort();
+        keys
+    };
+    assert_eq!(keys(&guard), ["a@example.com", "b@example.com"]);
+    assert!(guard.check("c@example.com", None).is_ok());
+    for _ in 0..2 {
+        guard.record_failure("a@example.com", None);
+    }
+    assert!(guard.check("a@example.com", None).is_err());
+    // 锁定期内的记录不会被清理
+    assert_eq!(guard.prune(), 0);
+
+    // 统计窗口与锁定时长为 0 时所有记录都已过期
+    config.window = 0;
+    config.lockout = 0;
+    let guard = LoginGuard::new(&config);
+    guard.record_failure("a@example.com", Some("203.0.113.1"));
+    assert!(guard.list().is_empty());
+    assert_eq!(guard.prune(), 2);
+    assert!(!guard.clear(LockoutKind::Ip, "203.0.113.1"));
+    guard.record_failure("b@example.com", None);
+    assert_eq!(guard.prune(), 1);
+}
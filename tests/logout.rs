@@ -0,0 +1,122 @@
+//! 登出只作用于调用者自己的会话，管理员需要 `user:logout` 权限才能强制其他用户退出
+
+use actix_web::{http::StatusCode, test};
+use rust_class_web::models::audit::{AuditFilter, action};
+use rust_class_web::state::AppState;
+use serde_json::json;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+
+const ADMIN: &str = "admin@example.com";
+const ALICE: &str = "alice@example.com";
+const BOB: &str = "bob@example.com";
+
+fn sessions() -> test::TestRequest {
+    test::TestRequest::get().uri("/api/sessions")
+}
+
+fn logout(all: Option<bool>) -> test::TestRequest {
+    let request = test::TestRequest::post().uri("/api/logout");
+    match all {
+        Some(all) => request.set_json(json!({ "all": all })),
+        None => request,
+    }
+}
+
+#[actix_web::test]
+async fn logout_revokes_the_current_or_all_own_sessions() {
+    let mut config = common::config();
+    config.store.backend = "memory".to_string();
+    config.audit.flush_interval = 10;
+    let state = AppState::new(&config).await.unwrap();
+    let app = test::init_service(common::app(&state)).await;
+    sign_up!(app, "alice", ALICE);
+    sign_up!(app, "bob", BOB);
+    let bob = login!(app, BOB);
+
+    // 不传请求体与 `all: false` 一样只退出当前会话
+    for all in [None, Some(false)] {
+        let current = login!(app, ALICE);
+        let other = login!(app, ALICE);
+        let (status, body) = call!(app, logout(all), Some(&current));
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["data"], 1);
+        let (status, _) = call!(app, sessions(), Some(&current));
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = call!(app, sessions(), Some(&other));
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    // 此时 alice 还有前两轮留下的 2 个会话
+    let current = login!(app, ALICE);
+    let (status, body) = call!(app, logout(Some(true)), Some(&current));
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["data"], 3);
+    let (status, _) = call!(app, sessions(), Some(&current));
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = call!(app, sessions(), Some(&bob));
+    assert_eq!(status, StatusCode::OK);
+
+    // 登出后的 token 不能再次登出
+    let (status, _) = call!(app, logout(None), Some(&current));
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let details = state
+        .audit
+        .query(&AuditFilter {
+            action: Some(action::LOGOUT.to_string()),
+            limit: 10,
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|record| record.event.detail.unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(details, ["all", "current", "current"]);
+}
+
+#[actix_web::test]
+async fn force_logout_requires_permission() {
+    let mut config = common::config();
+    config.auth.admins = vec![ADMIN.to_string()];
+    let state = AppState::new(&config).await.unwrap();
+    let app = test::init_service(common::app(&state)).await;
+    sign_up!(app, "admin", ADMIN);
+    common::verify_email(&state.db_pool, ADMIN).await;
+    let alice = sign_up!(app, "alice", ALICE);
+    sign_up!(app, "bob", BOB);
+    let admin = login!(app, ADMIN);
+    let bob = login!(app, BOB);
+    let alice_tokens = [login!(app, ALICE), login!(app, ALICE)];
+    let uri = format!("/api/admin/users/{}/logout", alice["data"]["id"]);
+
+    let (status, body) = call!(app, test::TestRequest::post().uri(&uri), Some(&bob));
+    assert_eq!(status, StatusCode::FORBIDDEN, "{body}");
+    assert_eq!(body["errorCode"], "PERMISSION_DENIED");
+    for token in &alice_tokens {
+        let (status, _) = call!(app, sessions(), Some(token));
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let (status, body) = call!(app, test::TestRequest::post().uri(&uri), Some(&admin));
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["data"], 2);
+    for token in &alice_tokens {
+        let (status, _) = call!(app, sessions(), Some(token));
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+    // 管理员自己的会话不受影响
+    let (status, _) = call!(app, sessions(), Some(&admin));
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = call!(
+        app,
+        test::TestRequest::post().uri("/api/admin/users/9999/logout"),
+        Some(&admin)
+    );
+    assert_eq!(status, StatusCode::NOT_FOUND, "{body}");
+}
@@ -0,0 +1,233 @@
+//! OAuth2 授权码模式: 回调地址精确匹配，授权码与 PKCE 校验只有一次机会
+
+use actix_web::{http::StatusCode, test};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use reqwest::Url;
+use rust_class_web::state::AppState;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+#[macro_use]
+mod common;
+
+const ADMIN_EMAIL: &str = "admin@example.com";
+const USER_EMAIL: &str = "alice@example.com";
+const REDIRECT_URI: &str = "https://app.example.com/callback";
+const VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+
+/// PKCE S256: BASE64URL(SHA256(code_verifier))
+fn challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+/// 授权请求参数
+fn authorize_params(client_id: &str, redirect_uri: &str) -> Value {
+    json!({
+        "response_type": "code",
+        "client_id": client_id,
+        "redirect_uri": redirect_uri,
+        "scope": "profile",
+        "state": "xyz",
+        "code_challenge": challenge(VERIFIER),
+        "code_challenge_method": "S256",
+    })
+}
+
+/// 发起授权的 GET 请求
+fn authorize_get(params: &Value) -> test::TestRequest {
+    let mut url = Url::parse("http://localhost/api/oauth/authorize").unwrap();
+    for (key, value) in params.as_object().unwrap() {
+        url.query_pairs_mut()
+            .append_pair(key, value.as_str().unwrap());
+    }
+    test::TestRequest::get().uri(&format!("{}?{}", url.path(), url.query().unwrap()))
+}
+
+/// 回调地址中的查询参数
+fn redirect_param(body: &Value, name: &str) -> Option<String> {
+    let url = Url::parse(body["data"]["redirectUri"].as_str().unwrap()).unwrap();
+    url.query_pairs()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.into_owned())
+}
+
+fn exchange(
+    client_id: &str,
+    code: &str,
+    redirect_uri: &str,
+    verifier: Option<&str>,
+) -> test::TestRequest {
+    let mut form = vec![
+        ("grant_type", "authorization_code"),
+        ("client_id", client_id),
+        ("code", code),
+        ("redirect_uri", redirect_uri),
+    ];
+    if let Some(verifier) = verifier {
+        form.push(("code_verifier", verifier));
+    }
+    test::TestRequest::post()
+        .uri("/api/oauth/token")
+        .set_form(form)
+}
+
+#[actix_web::test]
+async fn authorization_codes_are_single_use_and_bound_to_the_request() {
+    let mut config = common::config();
+    config.auth.admins = vec![ADMIN_EMAIL.to_string()];
+    let state = AppState::new(&config).await.unwrap();
+    let db = state.db_pool.clone();
+    let app = test::init_service(common::app(&state)).await;
+
+    let mut tokens = Vec::new();
+    for (name, email) in [("admin", ADMIN_EMAIL), ("alice", USER_EMAIL)] {
+        sign_up!(app, name, email);
+        common::verify_email(&db, email).await;
+        tokens.push(login!(app, email));
+    }
+    let (admin_token, user_token) = (tokens[0].as_str(), tokens[1].as_str());
+
+    // 登记两个只使用 PKCE 的公开客户端
+    let mut client_ids = Vec::new();
+    for name in ["app", "other"] {
+        let (status, body) = call!(
+            app,
+            test::TestRequest::post()
+                .uri("/api/admin/oauth/clients")
+                .set_json(json!({
+                    "name": name,
+                    "redirect_uris": [REDIRECT_URI],
+                    "scopes": ["profile"],
+                    "confidential": false,
+                })),
+            Some(admin_token)
+        );
+        assert_eq!(status, StatusCode::OK, "{body}");
+        client_ids.push(
+            body["data"]["client"]["clientId"]
+                .as_str()
+                .unwrap()
+                .to_string(),
+        );
+    }
+    let (client_id, other_client_id) = (client_ids[0].as_str(), client_ids[1].as_str());
+
+    // 回调地址必须与登记的完全一致，不一致时不跳转
+    for redirect_uri in [
+        "https://app.example.com/callback/",
+        "https://app.example.com/callback?next=/",
+        "https://app.example.com/callbackx",
+        "https://app.example.com/callback/../evil",
+        "http://app.example.com/callback",
+        "https://evil.example.com/callback",
+    ] {
+        let (status, body) = call!(
+            app,
+            authorize_get(&authorize_params(client_id, redirect_uri)),
+            Some(user_token)
+        );
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{redirect_uri}: {body}");
+    }
+
+    // 公开客户端必须使用 S256 方式的 PKCE
+    let mut params = authorize_params(client_id, REDIRECT_URI);
+    params.as_object_mut().unwrap().remove("code_challenge");
+    let (_, body) = call!(app, authorize_get(&params), Some(user_token));
+    assert_eq!(
+        redirect_param(&body, "error").as_deref(),
+        Some("invalid_request")
+    );
+    params["code_challenge"] = json!(VERIFIER);
+    params["code_challenge_method"] = json!("plain");
+    let (_, body) = call!(app, authorize_get(&params), Some(user_token));
+    assert_eq!(
+        redirect_param(&body, "error").as_deref(),
+        Some("invalid_request")
+    );
+
+    // 首次授权需要用户确认，之后相同范围直接签发授权码
+    let (_, body) = call!(
+        app,
+        authorize_get(&authorize_params(client_id, REDIRECT_URI)),
+        Some(user_token)
+    );
+    assert_eq!(body["data"]["consentRequired"], true, "{body}");
+    let mut consent = authorize_params(client_id, REDIRECT_URI);
+    consent["approve"] = json!(true);
+    let (status, body) = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/oauth/authorize")
+            .set_json(consent),
+        Some(user_token)
+    );
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(redirect_param(&body, "state").as_deref(), Some("xyz"));
+    let first_code = redirect_param(&body, "code").unwrap();
+    let issue_code = || async {
+        let (_, body) = call!(
+            app,
+            authorize_get(&authorize_params(client_id, REDIRECT_URI)),
+            Some(user_token)
+        );
+        assert_eq!(body["data"]["consentRequired"], false, "{body}");
+        redirect_param(&body, "code").unwrap()
+    };
+
+    // 每次校验失败都会作废授权码，之后即使参数正确也不能再换取令牌
+    let failures = [
+        (
+            client_id,
+            REDIRECT_URI,
+            Some("wrong-verifier-wrong-verifier-wrong-verifier"),
+        ),
+        (client_id, REDIRECT_URI, None),
+        (
+            client_id,
+            "https://app.example.com/callback/",
+            Some(VERIFIER),
+        ),
+        (other_client_id, REDIRECT_URI, Some(VERIFIER)),
+    ];
+    for (index, (exchange_client, redirect_uri, verifier)) in failures.into_iter().enumerate() {
+        let code = if index == 0 {
+            first_code.clone()
+        } else {
+            issue_code().await
+        };
+        let (status, body) = call!(
+            app,
+            exchange(exchange_client, &code, redirect_uri, verifier),
+            None
+        );
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{index}: {body}");
+        assert_eq!(body["error"], "invalid_grant", "{index}: {body}");
+        let (status, body) = call!(
+            app,
+            exchange(client_id, &code, REDIRECT_URI, Some(VERIFIER)),
+            None
+        );
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{index}: {body}");
+        assert_eq!(body["error"], "invalid_grant", "{index}: {body}");
+    }
+
+    // 参数正确时换取令牌，同一个授权码不能使用第二次
+    let code = issue_code().await;
+    let (status, body) = call!(
+        app,
+        exchange(client_id, &code, REDIRECT_URI, Some(VERIFIER)),
+        None
+    );
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["token_type"], "Bearer");
+    assert_eq!(body["scope"], "profile");
+    assert!(body["access_token"].is_string());
+    assert!(body["refresh_token"].is_string());
+    let (status, body) = call!(
+        app,
+        exchange(client_id, &code, REDIRECT_URI, Some(VERIFIER)),
+        None
+    );
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"], "invalid_grant");
+}
@@ -0,0 +1,205 @@
+//! OIDC 登录: 回调必须来自发起登录的浏览器，关联未验证的本地账号时重置原有凭据，
+//! 等待回调的登录请求有数量上限，请求身份提供方有超时时间
+
+use actix_web::cookie::Cookie;
+use actix_web::{App, HttpResponse, HttpServer, http::StatusCode, rt, test, web};
+use rust_class_web::app_config::oidc::Oidc;
+use rust_class_web::entity::users;
+use rust_class_web::errors::AppError;
+use rust_class_web::models::oidc::{IdTokenClaims, OidcClient, STATE_COOKIE, sign_in};
+use rust_class_web::state::AppState;
+use sea_orm::EntityTrait;
+use serde_json::json;
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
+
+#[macro_use]
+mod common;
+
+const EMAIL: &str = "victim@example.com";
+const ISSUER: &str = "https://idp.example.com";
+
+#[actix_web::test]
+async fn oidc_login_is_bound_to_browser_and_resets_pending_accounts() {
+    let mut config = common::config();
+    config.oidc.enabled = true;
+    config.oidc.issuer = ISSUER.to_string();
+    config.oidc.client_id = "client".to_string();
+    let app_state = AppState::new(&config).await.unwrap();
+    let app = test::init_service(common::app(&app_state)).await;
+
+    // 回调缺少 Cookie 或 Cookie 与 state 不一致时拒绝，不会向身份提供方换取令牌
+    let callback = || test::TestRequest::get().uri("/api/oidc/callback?code=c&state=s");
+    for request in [
+        callback(),
+        callback().cookie(Cookie::new(STATE_COOKIE, "other")),
+    ] {
+        let (status, body) = call!(app, request, None);
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+        assert!(
+            body["message"].as_str().unwrap().contains("不匹配"),
+            "{body}"
+        );
+    }
+    // Cookie 一致时继续校验 state 本身
+    let request = callback().cookie(Cookie::new(STATE_COOKIE, "s"));
+    let (status, body) = call!(app, request, None);
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    assert!(
+        body["message"].as_str().unwrap().contains("无效或已过期"),
+        "{body}"
+    );
+
+    // 他人抢先使用受害者的邮箱注册，未验证邮箱即登录
+    let credentials = json!({ "email": EMAIL, "pass_word": "attacker-password" });
+    let mut body = credentials.clone();
+    body["name"] = json!("attacker");
+    let (status, created) = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/users/create")
+            .set_json(body),
+        None
+    );
+    assert_eq!(status, StatusCode::OK, "{created}");
+    let user_id = created["data"]["id"].as_i64().unwrap();
+    let login = || {
+        test::TestRequest::post()
+            .uri("/api/users/login")
+            .set_json(&credentials)
+    };
+    let (status, session) = call!(app, login(), None);
+    assert_eq!(status, StatusCode::OK, "{session}");
+    let token = session["data"]["token"].as_str().unwrap();
+
+    // 受害者通过身份提供方登录，关联到该账号
+    let claims: IdTokenClaims = serde_json::from_value(json!({
+        "sub": "victim",
+        "email": EMAIL,
+        "email_verified": true,
+    }))
+    .unwrap();
+    let user = sign_in(ISSUER, &claims, &app_state).await.unwrap();
+    assert_eq!(user.id, user_id);
+    assert_eq!(user.status, users::STATUS_NORMAL);
+
+    // 抢注者的密码与会话全部失效
+    let (status, body) = call!(app, login(), None);
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "{body}");
+    assert_eq!(body["errorCode"], "LOGIN_FAILED");
+    let (status, _) = call!(
+        app,
+        test::TestRequest::get().uri("/api/sessions"),
+        Some(token)
+    );
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // 已关联的账号再次登录时不再重置
+    let stored = users::Entity::find_by_id(user_id)
+        .one(&app_state.db_pool)
+        .await
+        .unwrap()
+        .unwrap();
+    sign_in(ISSUER, &claims, &app_state).await.unwrap();
+    let again = users::Entity::find_by_id(user_id)
+        .one(&app_state.db_pool)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.pass_word, again.pass_word);
+}
+
+/// 在本机启动模拟的身份提供方，返回其地址
+///
+/// `{base}` 下提供发现文档与空的 JWKS，`{base}/slow` 下的发现文档 5 秒后才响应
+fn start_provider() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let issuer = base.clone();
+    let server = HttpServer::new(move || {
+        let metadata = json!({
+            "issuer": issuer,
+            "authorization_endpoint": format!("{issuer}/authorize"),
+            "token_endpoint": format!("{issuer}/token"),
+            "jwks_uri": format!("{issuer}/jwks"),
+        });
+        App::new()
+            .route(
+                "/.well-known/openid-configuration",
+                web::get().to(move || {
+                    let metadata = metadata.clone();
+                    async move { HttpResponse::Ok().json(metadata) }
+                }),
+            )
+            .route(
+                "/jwks",
+                web::get().to(|| async { HttpResponse::Ok().json(json!({ "keys": [] })) }),
+            )
+            .route(
+                "/slow/.well-known/openid-configuration",
+                web::get().to(|| async {
+                    rt::time::sleep(Duration::from_secs(5)).await;
+                    HttpResponse::Ok().finish()
+                }),
+            )
+    })
+    .workers(1)
+    .listen(listener)
+    .unwrap()
+    .run();
+    rt::spawn(server);
+    base
+}
+
+fn client_config(issuer: &str) -> Oidc {
+    Oidc {
+        enabled: true,
+        issuer: issuer.to_string(),
+        client_id: "client".to_string(),
+        prune_interval: 0,
+        ..Oidc::default()
+    }
+}
+
+#[actix_web::test]
+async fn pending_logins_are_capped_until_pruned() {
+    let base = start_provider();
+    let client = OidcClient::new(&Oidc {
+        max_pending: 2,
+        // 立即过期，清理时全部删除
+        state_expire: 0,
+        ..client_config(&base)
+    })
+    .unwrap();
+
+    for _ in 0..2 {
+        let (url, _) = client.authorization_url().await.unwrap();
+        assert!(url.starts_with(&format!("{base}/authorize?")), "{url}");
+    }
+    let result = client.authorization_url().await;
+    assert!(
+        matches!(result, Err(AppError::TooManyRequests(_))),
+        "{result:?}"
+    );
+
+    assert_eq!(client.prune(), 2);
+    client.authorization_url().await.unwrap();
+}
+
+#[actix_web::test]
+async fn slow_provider_requests_time_out() {
+    let base = start_provider();
+    let client = OidcClient::new(&Oidc {
+        request_timeout: 1,
+        ..client_config(&format!("{base}/slow"))
+    })
+    .unwrap();
+
+    let started = Instant::now();
+    let result = client.authorization_url().await;
+    assert!(
+        matches!(result, Err(AppError::ServiceUnavailable(_))),
+        "{result:?}"
+    );
+    assert!(started.elapsed() < Duration::from_secs(3));
+}
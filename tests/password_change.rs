@@ -0,0 +1,76 @@
+//! 修改与重置密码: 新密码需要满足长度要求，原密码错误计入登录失败次数
+
+use actix_web::{http::StatusCode, test};
+use rust_class_web::state::AppState;
+use serde_json::json;
+
+#[macro_use]
+mod common;
+
+const EMAIL: &str = "alice@example.com";
+
+fn change(old: &str, new: &str) -> test::TestRequest {
+    test::TestRequest::post()
+        .uri("/api/users/password/change")
+        .set_json(json!({ "old_pass_word": old, "new_pass_word": new }))
+}
+
+#[actix_web::test]
+async fn passwords_are_validated_and_wrong_old_passwords_lock_the_account() {
+    let mut config = common::config();
+    config.login_guard.max_account_failures = 2;
+    let state = AppState::new(&config).await.unwrap();
+    let app = test::init_service(common::app(&state)).await;
+
+    // 注册、修改与重置密码都要求新密码长度，用户名不能为空
+    let (status, body) = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/users/create")
+            .set_json(json!({ "name": "", "email": EMAIL, "pass_word": "short" })),
+        None
+    );
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    assert_eq!(body["errorCode"], "VALIDATION_FAILED");
+    let mut fields: Vec<_> = body["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|error| error["field"].as_str().unwrap().to_string())
+        .collect();
+    fields.sort();
+    assert_eq!(fields, ["name", "pass_word"]);
+    sign_up!(app, "alice", EMAIL);
+    let token = login!(app, EMAIL);
+
+    let (status, body) = call!(app, change(common::PASSWORD, "short"), Some(&token));
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["errorCode"], "VALIDATION_FAILED");
+    assert_eq!(body["errors"][0]["field"], "new_pass_word");
+    let (status, body) = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/users/password/reset")
+            .set_json(json!({ "token": "invalid", "new_pass_word": "short" })),
+        None
+    );
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["errorCode"], "VALIDATION_FAILED");
+
+    // 原密码错误达到阈值后账号锁定，修改密码与登录都被拒绝
+    for _ in 0..2 {
+        let (status, _) = call!(app, change("wrong-password", "new-password"), Some(&token));
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+    let (status, body) = call!(app, change(common::PASSWORD, "new-password"), Some(&token));
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS, "{body}");
+    assert_eq!(body["errorCode"], "ACCOUNT_LOCKED");
+    let (status, body) = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/users/login")
+            .set_json(json!({ "email": EMAIL, "pass_word": common::PASSWORD })),
+        None
+    );
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS, "{body}");
+}
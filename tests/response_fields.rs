@@ -0,0 +1,342 @@
+//! 接口响应中不能出现密码哈希、令牌等敏感字段
+//!
+//! 依次调用返回用户、会话、API Key、OAuth2 客户端与审计日志的接口，递归检查响应中的字段名，
+//! 除登录签发的令牌与创建时返回一次的明文外不允许出现禁止的字段
+
+use actix_web::test;
+use chrono::Utc;
+use rust_class_web::entity::{oauth_consents, recovery_codes, user_totp};
+use rust_class_web::models::secure_token;
+use rust_class_web::state::AppState;
+use sea_orm::{ActiveModelTrait, ActiveValue::NotSet, ColumnTrait, EntityTrait, QueryFilter, Set};
+use serde_json::{Value, json};
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+
+/// 不允许出现在响应中的字段名
+const DENY_LIST: [&str; 12] = [
+    "pass_word",
+    "passWord",
+    "password",
+    "token",
+    "tokenHash",
+    "refreshFamily",
+    "refreshTokenHash",
+    "key",
+    "keyHash",
+    "clientSecret",
+    "clientSecretHash",
+    "secret",
+];
+
+const ADMIN_EMAIL: &str = "admin@example.com";
+const BOB_EMAIL: &str = "bob@example.com";
+
+/// 递归查找禁止的字段，返回字段路径，例如 `data.items[0].passWord`
+fn denied_fields(value: &Value, path: &str, found: &mut Vec<String>) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                if DENY_LIST.contains(&key.as_str()) {
+                    found.push(path.clone());
+                }
+                denied_fields(value, &path, found);
+            }
+        }
+        Value::Array(values) => {
+            for (i, value) in values.iter().enumerate() {
+                denied_fields(value, &format!("{path}[{i}]"), found);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 断言响应中除 `allowed` 中的路径外没有禁止的字段
+fn assert_clean(name: &str, body: &Value, allowed: &[&str]) {
+    let mut found = Vec::new();
+    denied_fields(body, "", &mut found);
+    found.retain(|path| !allowed.contains(&path.as_str()));
+    assert!(found.is_empty(), "{name} 返回了敏感字段 {found:?}: {body}");
+}
+
+/// 发送请求并返回响应内容，要求请求成功
+macro_rules! ok {
+    ($app:expr, $request:expr, $token:expr) => {{
+        let (status, body) = call!($app, $request, $token);
+        assert!(status.is_success(), "{status}: {body}");
+        body
+    }};
+}
+
+#[actix_web::test]
+async fn responses_do_not_expose_secrets() {
+    let mut config = common::config();
+    config.auth.admins = vec![ADMIN_EMAIL.to_string()];
+    // 审计日志写入内存中的文档存储
+    config.store.backend = "memory".to_string();
+    config.audit.flush_interval = 10;
+    let state = AppState::new(&config).await.unwrap();
+    let db = state.db_pool.clone();
+    let app = test::init_service(common::app(&state)).await;
+
+    let mut checked = Vec::new();
+    for (name, email) in [("admin", ADMIN_EMAIL), ("alice", "alice@example.com")] {
+        let body = json!({ "name": name, "email": email, "pass_word": "secret-password" });
+        let created = ok!(
+            app,
+            test::TestRequest::post()
+                .uri("/api/users/create")
+                .set_json(body),
+            None
+        );
+        assert_clean("create_user", &created, &[]);
+        checked.push(created);
+    }
+    // 配置中的管理员邮箱验证后才拥有管理员权限
+    common::verify_email(&db, ADMIN_EMAIL).await;
+    let user_id = checked[1]["data"]["id"].as_i64().unwrap();
+
+    let login = |email: &str| json!({ "email": email, "pass_word": "secret-password" });
+    let admin = ok!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/users/login")
+            .set_json(login(ADMIN_EMAIL)),
+        None
+    );
+    assert_clean("login", &admin, &["data.token"]);
+    let user = ok!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/users/login")
+            .set_json(login("alice@example.com")),
+        None
+    );
+    assert_clean("login", &user, &["data.token"]);
+    let admin_token = admin["data"]["token"].as_str().unwrap();
+    let user_token = user["data"]["token"].as_str().unwrap();
+
+    let requests = [
+        (
+            "get_user",
+            test::TestRequest::get().uri(&format!("/api/users/{user_id}")),
+            user_token,
+            None,
+        ),
+        (
+            "list_users",
+            test::TestRequest::get().uri("/api/users?size=10"),
+            admin_token,
+            None,
+        ),
+        (
+            "get_query_users",
+            test::TestRequest::post().uri("/api/users/getQueryUsers"),
+            admin_token,
+            Some(json!({ "name": "" })),
+        ),
+        (
+            "update_user",
+            test::TestRequest::put().uri(&format!("/api/users/{user_id}")),
+            user_token,
+            Some(json!({ "name": "alice", "email": "alice@example.com" })),
+        ),
+        (
+            "patch_user",
+            test::TestRequest::patch().uri(&format!("/api/users/{user_id}")),
+            admin_token,
+            Some(json!({ "name": "Alice" })),
+        ),
+        (
+            "list_sessions",
+            test::TestRequest::get().uri("/api/sessions"),
+            user_token,
+            None,
+        ),
+    ];
+    for (name, request, token, body) in requests {
+        let request = match body {
+            Some(body) => request.set_json(body),
+            None => request,
+        };
+        let body = ok!(app, request, Some(token));
+        assert_clean(name, &body, &[]);
+    }
+
+    // 两步验证登录与 OIDC 登录共用密码登录的响应，这里只检查两步验证
+    let bob = ok!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/users/create")
+            .set_json(json!({ "name": "bob", "email": BOB_EMAIL, "pass_word": "secret-password" })),
+        None
+    );
+    let bob_id = bob["data"]["id"].as_i64().unwrap();
+    let bob_login = ok!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/users/login")
+            .set_json(login(BOB_EMAIL)),
+        None
+    );
+    ok!(
+        app,
+        test::TestRequest::post().uri("/api/users/2fa/setup"),
+        bob_login["data"]["token"].as_str()
+    );
+    // 跳过验证码确认，直接启用并写入一个恢复码
+    user_totp::Entity::update_many()
+        .col_expr(user_totp::Column::Enabled, true.into())
+        .filter(user_totp::Column::UserId.eq(bob_id))
+        .exec(&db)
+        .await
+        .unwrap();
+    recovery_codes::ActiveModel {
+        id: NotSet,
+        user_id: Set(bob_id),
+        code_hash: Set(secure_token::digest("abcdefghjk")),
+        used_time: Set(None),
+        create_time: Set(Utc::now()),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+    let challenge = ok!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/users/login")
+            .set_json(login(BOB_EMAIL)),
+        None
+    );
+    assert_clean("login_challenge", &challenge, &[]);
+    let two_factor = ok!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/users/login/2fa")
+            .set_json(json!({
+                "challenge_token": challenge["data"]["challengeToken"],
+                "code": "abcde-fghjk",
+            })),
+        None
+    );
+    assert_clean("login_two_factor", &two_factor, &["data.token"]);
+    assert!(two_factor["data"]["user"]["id"].is_i64(), "{two_factor}");
+
+    ok!(
+        app,
+        test::TestRequest::delete().uri(&format!("/api/users/delete/{bob_id}")),
+        Some(admin_token)
+    );
+    let restored = ok!(
+        app,
+        test::TestRequest::post().uri(&format!("/api/admin/users/{bob_id}/restore")),
+        Some(admin_token)
+    );
+    assert_clean("restore_user", &restored, &[]);
+
+    // API Key 的明文只在创建时返回
+    let created_key = ok!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/api-keys")
+            .set_json(json!({ "name": "ci" })),
+        Some(user_token)
+    );
+    assert_clean("create_api_key", &created_key, &["data.key"]);
+    let api_keys = ok!(
+        app,
+        test::TestRequest::get().uri("/api/api-keys"),
+        Some(user_token)
+    );
+    assert_clean("list_api_keys", &api_keys, &[]);
+
+    // OAuth2 客户端的密钥只在登记时返回
+    let client = ok!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/admin/oauth/clients")
+            .set_json(json!({
+                "name": "app",
+                "redirect_uris": ["https://app.example.com/callback"],
+                "scopes": ["profile"],
+            })),
+        Some(admin_token)
+    );
+    assert_clean("create_oauth_client", &client, &["data.clientSecret"]);
+    assert!(client["data"]["clientSecret"].is_string(), "{client}");
+    let clients = ok!(
+        app,
+        test::TestRequest::get().uri("/api/admin/oauth/clients"),
+        Some(admin_token)
+    );
+    assert_clean("list_oauth_clients", &clients, &[]);
+    oauth_consents::ActiveModel {
+        user_id: Set(user_id),
+        client_id: Set(client["data"]["client"]["clientId"]
+            .as_str()
+            .unwrap()
+            .to_string()),
+        scope: Set("profile".to_string()),
+        create_time: Set(Utc::now()),
+        update_time: Set(Utc::now()),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+    let consents = ok!(
+        app,
+        test::TestRequest::get().uri("/api/oauth/consents"),
+        Some(user_token)
+    );
+    assert_eq!(consents["data"].as_array().unwrap().len(), 1, "{consents}");
+    assert_clean("list_consents", &consents, &[]);
+
+    // 等待后台任务把审计事件写入文档存储
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let audit_logs = ok!(
+        app,
+        test::TestRequest::get().uri("/api/admin/audit-logs"),
+        Some(admin_token)
+    );
+    assert!(
+        !audit_logs["data"].as_array().unwrap().is_empty(),
+        "{audit_logs}"
+    );
+    assert_clean("list_audit_logs", &audit_logs, &[]);
+
+    // 接口文档中返回给客户端的类型同样不能包含敏感字段
+    let docs = ok!(
+        app,
+        test::TestRequest::get().uri("/api-docs/openapi.json"),
+        None
+    );
+    let schemas = docs["components"]["schemas"].as_object().unwrap();
+    for (name, allowed) in [
+        ("UserDto", &[][..]),
+        ("LoginDto", &["properties.token"]),
+        ("DeviceDto", &[]),
+        ("ApiKeyDto", &[]),
+        ("CreatedKeyDto", &["properties.key"]),
+        ("OAuthClientDto", &[]),
+        ("CreatedClientDto", &["properties.clientSecret"]),
+        ("ConsentInfo", &[]),
+        ("AuditRecord", &[]),
+        // 被锁定的邮箱或 IP，不是密钥
+        ("LockoutInfo", &["properties.key"]),
+        ("RoleItem", &[]),
+    ] {
+        // 包含 `#[serde(flatten)]` 的类型字段位于 `allOf` 中，因此检查整个定义
+        let schema = schemas
+            .get(name)
+            .unwrap_or_else(|| panic!("接口文档缺少 {name}"));
+        assert_clean(name, schema, allowed);
+    }
+}
@@ -0,0 +1,139 @@
+//! 会话管理: 只能查看与操作自己的会话，撤销其他会话时保留当前会话，撤销记录写入审计日志
+
+use actix_web::{http::StatusCode, test};
+use rust_class_web::models::audit::{AuditFilter, action};
+use rust_class_web::state::AppState;
+use serde_json::json;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+
+const ALICE: &str = "alice@example.com";
+const BOB: &str = "bob@example.com";
+
+fn sessions() -> test::TestRequest {
+    test::TestRequest::get().uri("/api/sessions")
+}
+
+/// 发起请求所用会话的 ID
+macro_rules! current_session {
+    ($app:expr, $token:expr) => {{
+        let (status, body) = call!($app, sessions(), Some($token));
+        assert_eq!(status, StatusCode::OK, "{body}");
+        body["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|session| session["current"] == true)
+            .unwrap()["id"]
+            .as_i64()
+            .unwrap()
+    }};
+}
+
+#[actix_web::test]
+async fn sessions_of_other_users_are_not_found() {
+    let state = AppState::new(&common::config()).await.unwrap();
+    let app = test::init_service(common::app(&state)).await;
+    sign_up!(app, "alice", ALICE);
+    sign_up!(app, "bob", BOB);
+    let alice = login!(app, ALICE);
+    let bob = login!(app, BOB);
+    let alice_session = current_session!(app, &alice);
+    let uri = format!("/api/sessions/{alice_session}");
+
+    let (status, body) = call!(
+        app,
+        test::TestRequest::put()
+            .uri(&uri)
+            .set_json(json!({ "name": "stolen" })),
+        Some(&bob)
+    );
+    assert_eq!(status, StatusCode::NOT_FOUND, "{body}");
+    assert_eq!(body["errorCode"], "SESSION_NOT_FOUND");
+    let (status, body) = call!(app, test::TestRequest::delete().uri(&uri), Some(&bob));
+    assert_eq!(status, StatusCode::NOT_FOUND, "{body}");
+    assert_eq!(body["errorCode"], "SESSION_NOT_FOUND");
+
+    // alice 的会话不受影响，自己可以修改名称
+    let (status, body) = call!(
+        app,
+        test::TestRequest::put()
+            .uri(&uri)
+            .set_json(json!({ "name": "  laptop " })),
+        Some(&alice)
+    );
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let (status, body) = call!(app, sessions(), Some(&alice));
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["data"].as_array().unwrap().len(), 1, "{body}");
+    assert_eq!(body["data"][0]["name"], "laptop");
+}
+
+#[actix_web::test]
+async fn revoking_sessions_keeps_the_current_one_and_is_audited() {
+    let mut config = common::config();
+    config.store.backend = "memory".to_string();
+    config.audit.flush_interval = 10;
+    let state = AppState::new(&config).await.unwrap();
+    let app = test::init_service(common::app(&state)).await;
+    sign_up!(app, "alice", ALICE);
+    sign_up!(app, "bob", BOB);
+    let current = login!(app, ALICE);
+    let others = [login!(app, ALICE), login!(app, ALICE)];
+    let bob = login!(app, BOB);
+
+    let (status, body) = call!(
+        app,
+        test::TestRequest::delete().uri("/api/sessions/others"),
+        Some(&current)
+    );
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["data"], 2);
+    let (status, body) = call!(app, sessions(), Some(&current));
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["data"].as_array().unwrap().len(), 1, "{body}");
+    assert_eq!(body["data"][0]["current"], true);
+    for token in &others {
+        let (status, _) = call!(app, sessions(), Some(token));
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+    // 其他用户的会话不受影响
+    let (status, _) = call!(app, sessions(), Some(&bob));
+    assert_eq!(status, StatusCode::OK);
+
+    let phone = login!(app, ALICE);
+    let phone_session = current_session!(app, &phone);
+    let (status, body) = call!(
+        app,
+        test::TestRequest::delete().uri(&format!("/api/sessions/{phone_session}")),
+        Some(&current)
+    );
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["data"], true);
+    let (status, _) = call!(app, sessions(), Some(&phone));
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = call!(app, sessions(), Some(&current));
+    assert_eq!(status, StatusCode::OK);
+
+    // 等待后台任务把审计事件写入文档存储
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let records = state
+        .audit
+        .query(&AuditFilter {
+            action: Some(action::SESSION_REVOKE.to_string()),
+            limit: 10,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let details = records
+        .iter()
+        .map(|record| {
+            assert_eq!(record.event.actor_email.as_deref(), Some(ALICE));
+            record.event.detail.clone().unwrap()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(details, [phone_session.to_string(), "others".to_string()]);
+}
@@ -0,0 +1,103 @@
+//! 刷新令牌: 每次使用后轮换，旧令牌被再次使用时撤销整个会话
+
+use actix_web::{http::StatusCode, test};
+use chrono::{Duration, Utc};
+use rust_class_web::entity::devices;
+use rust_class_web::state::AppState;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use serde_json::{Value, json};
+
+#[macro_use]
+mod common;
+
+const EMAIL: &str = "alice@example.com";
+
+/// 刷新接口的请求
+fn refresh(refresh_token: &str) -> test::TestRequest {
+    test::TestRequest::post()
+        .uri("/api/token/refresh")
+        .set_json(json!({ "refreshToken": refresh_token }))
+}
+
+/// 取出响应中的访问令牌与刷新令牌
+fn tokens(body: &Value) -> (String, String) {
+    (
+        body["data"]["token"].as_str().unwrap().to_string(),
+        body["data"]["refreshToken"].as_str().unwrap().to_string(),
+    )
+}
+
+#[actix_web::test]
+async fn refresh_tokens_rotate_and_detect_reuse() {
+    let state = AppState::new(&common::config()).await.unwrap();
+    let db = state.db_pool.clone();
+    let app = test::init_service(common::app(&state)).await;
+
+    sign_up!(app, "alice", EMAIL);
+    let login = || {
+        test::TestRequest::post()
+            .uri("/api/users/login")
+            .set_json(json!({ "email": EMAIL, "pass_word": common::PASSWORD }))
+    };
+    let (_, body) = call!(app, login(), None);
+    let (first_token, first_refresh) = tokens(&body);
+    let (_, body) = call!(app, login(), None);
+    let (other_token, _) = tokens(&body);
+    let sessions = || test::TestRequest::get().uri("/api/sessions");
+
+    let (status, body) = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/token/refresh")
+            .set_json(json!({ "refresh_token": first_refresh })),
+        None
+    );
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["errorCode"], "INVALID_BODY");
+    let (status, _) = call!(app, refresh("not-a-token"), None);
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // 刷新后签发新的访问令牌与刷新令牌，旧访问令牌失效
+    let (status, body) = call!(app, refresh(&first_refresh), None);
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let (second_token, second_refresh) = tokens(&body);
+    assert_ne!(second_token, first_token);
+    assert_ne!(second_refresh, first_refresh);
+    assert!(body["data"]["expiresIn"].as_i64().unwrap() > 0);
+    let (status, _) = call!(app, sessions(), Some(&first_token));
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = call!(app, sessions(), Some(&second_token));
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = call!(app, refresh(&second_refresh), None);
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let (third_token, third_refresh) = tokens(&body);
+
+    // 已轮换的刷新令牌再次使用时撤销该会话，最新的令牌也随之失效
+    let (status, _) = call!(app, refresh(&first_refresh), None);
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = call!(app, refresh(&third_refresh), None);
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = call!(app, sessions(), Some(&third_token));
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // 其他设备的会话不受影响
+    let (status, body) = call!(app, sessions(), Some(&other_token));
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"].as_array().unwrap().len(), 1, "{body}");
+
+    // 过期的刷新令牌不能使用
+    let (_, body) = call!(app, login(), None);
+    let (_, expired_refresh) = tokens(&body);
+    devices::Entity::update_many()
+        .col_expr(
+            devices::Column::RefreshExpireTime,
+            Some(Utc::now() - Duration::seconds(1)).into(),
+        )
+        .filter(devices::Column::RefreshTokenHash.is_not_null())
+        .exec(&db)
+        .await
+        .unwrap();
+    let (status, _) = call!(app, refresh(&expired_refresh), None);
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
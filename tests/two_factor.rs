@@ -0,0 +1,129 @@
+//! 两步验证: TOTP 验证码不能重复使用，恢复码只能使用一次
+
+use actix_web::{http::StatusCode, test};
+use chrono::Utc;
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use rust_class_web::models::totp;
+use rust_class_web::state::AppState;
+use serde_json::json;
+use sha1::Sha1;
+
+#[macro_use]
+mod common;
+
+const EMAIL: &str = "alice@example.com";
+
+/// 按 RFC 6238 计算 `offset` 个时间步之后的验证码，模拟身份验证器 App
+fn totp_code(secret: &str, offset: i64) -> String {
+    let timestamp = Utc::now().timestamp() + offset * totp::STEP;
+    let key = BASE32_NOPAD.decode(secret.as_bytes()).unwrap();
+    let mut mac = Hmac::<Sha1>::new_from_slice(&key).unwrap();
+    mac.update(&timestamp.div_euclid(totp::STEP).to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+    let code = format!("{:06}", binary % 10u32.pow(totp::DIGITS));
+    assert!(totp::verify(secret, &code, timestamp, 0).is_some());
+    code
+}
+
+#[actix_web::test]
+async fn totp_codes_and_recovery_codes_are_single_use() {
+    let state = AppState::new(&common::config()).await.unwrap();
+    let app = test::init_service(common::app(&state)).await;
+
+    sign_up!(app, "alice", EMAIL);
+    let token = login!(app, EMAIL);
+    let login = || {
+        test::TestRequest::post()
+            .uri("/api/users/login")
+            .set_json(json!({ "email": EMAIL, "pass_word": common::PASSWORD }))
+    };
+
+    let (status, body) = call!(
+        app,
+        test::TestRequest::post().uri("/api/users/2fa/setup"),
+        Some(&token)
+    );
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let secret = body["data"]["secret"].as_str().unwrap().to_string();
+    let confirm = |code: &str| {
+        test::TestRequest::post()
+            .uri("/api/users/2fa/confirm")
+            .set_json(json!({ "code": code }))
+    };
+    let (status, body) = call!(app, confirm("000000x"), Some(&token));
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["errorCode"], "INVALID_TWO_FACTOR_CODE");
+    let confirm_code = totp_code(&secret, 0);
+    let (status, body) = call!(app, confirm(&confirm_code), Some(&token));
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let recovery_codes: Vec<String> = serde_json::from_value(body["data"].clone()).unwrap();
+    assert_eq!(recovery_codes.len(), state.config.two_factor.recovery_codes);
+
+    // 启用后密码登录只返回挑战令牌
+    let challenge = || async {
+        let (status, body) = call!(app, login(), None);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["twoFactorRequired"], true, "{body}");
+        assert!(body["data"].get("token").is_none(), "{body}");
+        body["data"]["challengeToken"].as_str().unwrap().to_string()
+    };
+    let submit = |challenge: &str, code: &str| {
+        test::TestRequest::post()
+            .uri("/api/users/login/2fa")
+            .set_json(json!({ "challenge_token": challenge, "code": code }))
+    };
+
+    // 确认启用时用过的验证码不能再用于登录，挑战令牌在失败后仍然有效
+    let challenge_token = challenge().await;
+    let (status, body) = call!(app, submit(&challenge_token, &confirm_code), None);
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["errorCode"], "INVALID_TWO_FACTOR_CODE");
+    let next_code = totp_code(&secret, 1);
+    let (status, body) = call!(app, submit(&challenge_token, &next_code), None);
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert!(body["data"]["token"].is_string());
+
+    // 同一个验证码不能登录第二次
+    let challenge_token = challenge().await;
+    let (status, body) = call!(app, submit(&challenge_token, &next_code), None);
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["errorCode"], "INVALID_TWO_FACTOR_CODE");
+
+    // 恢复码忽略大小写与连字符，使用后失效
+    let (status, body) = call!(app, submit(&challenge_token, &recovery_codes[0]), None);
+    assert_eq!(status, StatusCode::OK, "{body}");
+    // 挑战令牌只能使用一次
+    let (status, _) = call!(app, submit(&challenge_token, &recovery_codes[1]), None);
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let challenge_token = challenge().await;
+    let (status, body) = call!(app, submit(&challenge_token, &recovery_codes[0]), None);
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["errorCode"], "INVALID_TWO_FACTOR_CODE");
+    let reformatted = recovery_codes[1].replace('-', "").to_uppercase();
+    let (status, body) = call!(app, submit(&challenge_token, &reformatted), None);
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    // 关闭两步验证同样不接受用过的验证码与恢复码
+    let disable = |code: &str| {
+        test::TestRequest::post()
+            .uri("/api/users/2fa/disable")
+            .set_json(json!({ "code": code }))
+    };
+    for code in [&next_code, &recovery_codes[0], &recovery_codes[1]] {
+        let (status, body) = call!(app, disable(code), Some(&token));
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{code}");
+        assert_eq!(body["errorCode"], "INVALID_TWO_FACTOR_CODE");
+    }
+    let (status, body) = call!(app, disable(&recovery_codes[2]), Some(&token));
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let (_, body) = call!(app, login(), None);
+    assert!(body["data"]["token"].is_string(), "{body}");
+}
@@ -0,0 +1,152 @@
+//! 删除用户后标记为已删除，保留期内可以恢复，过期后不能恢复
+
+use actix_web::{http::StatusCode, test};
+use chrono::{Duration, Utc};
+use rust_class_web::entity::users;
+use rust_class_web::state::AppState;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use serde_json::{Value, json};
+
+#[macro_use]
+mod common;
+
+const ADMIN_EMAIL: &str = "admin@example.com";
+const USER_EMAIL: &str = "alice@example.com";
+
+fn credentials(email: &str) -> Value {
+    json!({ "email": email, "pass_word": common::PASSWORD })
+}
+
+#[actix_web::test]
+async fn deleted_users_can_be_restored_within_retention() {
+    let mut config = common::config();
+    config.auth.admins = vec![ADMIN_EMAIL.to_string()];
+    let state = AppState::new(&config).await.unwrap();
+    let db = state.db_pool.clone();
+    let app = test::init_service(common::app(&state)).await;
+
+    for (name, email) in [("admin", ADMIN_EMAIL), ("alice", USER_EMAIL)] {
+        sign_up!(app, name, email);
+    }
+    // 配置中的管理员邮箱验证后才拥有管理员权限
+    common::verify_email(&db, ADMIN_EMAIL).await;
+    let login = |email| {
+        test::TestRequest::post()
+            .uri("/api/users/login")
+            .set_json(credentials(email))
+    };
+    let (_, admin) = call!(app, login(ADMIN_EMAIL), None);
+    let admin_token = admin["data"]["token"].as_str().unwrap();
+    let (_, user) = call!(app, login(USER_EMAIL), None);
+    let user_token = user["data"]["token"].as_str().unwrap();
+    let user_id = user["data"]["user"]["id"].as_i64().unwrap();
+
+    let (status, body) = call!(
+        app,
+        test::TestRequest::delete().uri(&format!("/api/users/delete/{user_id}")),
+        Some(admin_token)
+    );
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let user = users::Entity::find_by_id(user_id)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(user.status, users::STATUS_DELETED);
+    assert!(user.deleted_at.is_some());
+    assert_eq!(
+        user.status_before_delete.as_deref(),
+        Some(users::STATUS_PENDING)
+    );
+
+    // 会话已撤销，不能再登录
+    let (status, _) = call!(
+        app,
+        test::TestRequest::get().uri("/api/sessions"),
+        Some(user_token)
+    );
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, body) = call!(app, login(USER_EMAIL), None);
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["errorCode"], "LOGIN_FAILED");
+
+    // 默认查询不包含已删除的用户
+    let (status, body) = call!(
+        app,
+        test::TestRequest::get().uri(&format!("/api/users/{user_id}")),
+        Some(admin_token)
+    );
+    assert_eq!(status, StatusCode::NOT_FOUND, "{body}");
+    let (_, body) = call!(
+        app,
+        test::TestRequest::get().uri("/api/users"),
+        Some(admin_token)
+    );
+    assert_eq!(body["data"]["total"], 1, "{body}");
+    let (_, body) = call!(
+        app,
+        test::TestRequest::get().uri("/api/users?status=deleted"),
+        Some(admin_token)
+    );
+    assert_eq!(body["data"]["items"][0]["id"], user_id, "{body}");
+    assert!(body["data"]["items"][0]["deletedAt"].is_string(), "{body}");
+    let (status, _) = call!(
+        app,
+        test::TestRequest::delete().uri(&format!("/api/users/delete/{user_id}")),
+        Some(admin_token)
+    );
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // 保留期内邮箱仍被占用
+    let mut body = credentials(USER_EMAIL);
+    body["name"] = json!("other");
+    let (status, body) = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/users/create")
+            .set_json(body),
+        None
+    );
+    assert_eq!(status, StatusCode::CONFLICT, "{body}");
+
+    let restore = || test::TestRequest::post().uri(&format!("/api/admin/users/{user_id}/restore"));
+    let (status, body) = call!(app, restore(), Some(admin_token));
+    assert_eq!(status, StatusCode::OK, "{body}");
+    // 删除前未验证邮箱，恢复后仍为待验证状态
+    assert_eq!(body["data"]["status"], users::STATUS_PENDING);
+    assert!(body["data"].get("deletedAt").is_none(), "{body}");
+    let user = users::Entity::find_by_id(user_id)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(user.status_before_delete.is_none());
+    let (status, user) = call!(app, login(USER_EMAIL), None);
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = call!(app, restore(), Some(admin_token));
+    assert_eq!(status, StatusCode::CONFLICT);
+    // 普通用户不能恢复
+    let user_token = user["data"]["token"].as_str().unwrap();
+    let (status, body) = call!(app, restore(), Some(user_token));
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["errorCode"], "PERMISSION_DENIED");
+
+    // 超过保留期后不能恢复
+    call!(
+        app,
+        test::TestRequest::delete().uri(&format!("/api/users/delete/{user_id}")),
+        Some(admin_token)
+    );
+    users::Entity::update_many()
+        .col_expr(
+            users::Column::DeletedAt,
+            (Utc::now() - Duration::days(config.user_deletion.retention_days as i64 + 1)).into(),
+        )
+        .filter(users::Column::Id.eq(user_id))
+        .exec(&db)
+        .await
+        .unwrap();
+    let (status, body) = call!(app, restore(), Some(admin_token));
+    assert_eq!(status, StatusCode::CONFLICT, "{body}");
+    assert_eq!(body["errorCode"], "RESTORE_EXPIRED");
+}
@@ -0,0 +1,87 @@
+//! 已废弃的 `POST /users/getQueryUsers` 返回分页信息，调用方可以继续获取后续的用户
+
+use actix_web::http::StatusCode;
+use actix_web::test;
+use chrono::Utc;
+use rust_class_web::entity::users;
+use rust_class_web::state::AppState;
+use sea_orm::{ActiveModelTrait, ActiveValue::NotSet, Set};
+use serde_json::json;
+
+#[macro_use]
+mod common;
+
+const ADMIN_EMAIL: &str = "admin@example.com";
+/// 多于默认每页条数
+const MEMBERS: usize = 25;
+
+#[actix_web::test]
+async fn get_query_users_pages_through_all_matches() {
+    let mut config = common::config();
+    config.auth.admins = vec![ADMIN_EMAIL.to_string()];
+    let state = AppState::new(&config).await.unwrap();
+    let app = test::init_service(common::app(&state)).await;
+
+    sign_up!(app, "admin", ADMIN_EMAIL);
+    common::verify_email(&state.db_pool, ADMIN_EMAIL).await;
+    let token = login!(app, ADMIN_EMAIL);
+    for i in 0..MEMBERS {
+        users::ActiveModel {
+            id: NotSet,
+            name: Set(format!("member-{i}")),
+            email: Set(format!("member-{i}@example.com")),
+            pass_word: Set(String::new()),
+            status: Set(users::STATUS_NORMAL.to_string()),
+            create_time: Set(Utc::now()),
+            update_time: Set(Utc::now()),
+            deleted_at: NotSet,
+            status_before_delete: NotSet,
+            pending_email: NotSet,
+        }
+        .insert(&state.db_pool)
+        .await
+        .unwrap();
+    }
+
+    macro_rules! query {
+        ($body:expr) => {{
+            let (status, body) = call!(
+                app,
+                test::TestRequest::post()
+                    .uri("/api/users/getQueryUsers")
+                    .set_json($body),
+                Some(&token)
+            );
+            assert_eq!(status, StatusCode::OK, "{body}");
+            body["data"].clone()
+        }};
+    }
+
+    // 默认返回第一页，总数与游标告诉调用方还有更多
+    let first = query!(json!({ "name": "member" }));
+    assert_eq!(first["total"], MEMBERS);
+    assert_eq!(first["items"].as_array().unwrap().len(), 20);
+    let cursor = first["nextCursor"].as_str().unwrap();
+
+    let rest = query!(json!({ "name": "member", "cursor": cursor }));
+    assert_eq!(rest["items"].as_array().unwrap().len(), MEMBERS - 20);
+    assert!(rest["nextCursor"].is_null(), "{rest}");
+
+    let last = query!(json!({ "name": "member", "page": 3, "size": 10 }));
+    let names = last["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|user| user["name"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "member-20",
+            "member-21",
+            "member-22",
+            "member-23",
+            "member-24"
+        ]
+    );
+}
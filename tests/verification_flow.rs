@@ -0,0 +1,52 @@
+//! 注册后需要验证邮箱才能登录，验证链接只能使用一次
+
+use actix_web::{http::StatusCode, test};
+use rust_class_web::entity::users;
+use rust_class_web::state::AppState;
+use serde_json::json;
+
+#[macro_use]
+mod common;
+
+const EMAIL: &str = "alice@example.com";
+
+#[actix_web::test]
+async fn login_requires_verified_email() {
+    let mut config = common::config();
+    config.verification.allow_unverified_login = false;
+    let mut state = AppState::new(&config).await.unwrap();
+    let mailer = common::capture_mail(&mut state);
+    let app = test::init_service(common::app(&state)).await;
+    let login = || {
+        test::TestRequest::post()
+            .uri("/api/users/login")
+            .set_json(json!({ "email": EMAIL, "pass_word": common::PASSWORD }))
+    };
+    let verify =
+        |token: &str| test::TestRequest::get().uri(&format!("/api/users/verify?token={token}"));
+
+    let created = sign_up!(app, "alice", EMAIL);
+    assert_eq!(created["data"]["status"], users::STATUS_PENDING);
+    let link = common::mailed_token(&mailer, EMAIL);
+
+    let (status, body) = call!(app, login(), None);
+    assert_eq!(status, StatusCode::FORBIDDEN, "{body}");
+    assert_eq!(body["errorCode"], "EMAIL_NOT_VERIFIED");
+
+    let (status, body) = call!(app, verify(&link), None);
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let token = login!(app, EMAIL);
+    let (status, body) = call!(
+        app,
+        test::TestRequest::get().uri(&format!("/api/users/{}", created["data"]["id"])),
+        Some(&token)
+    );
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["data"]["status"], users::STATUS_NORMAL);
+
+    // 验证链接只能使用一次
+    let (status, body) = call!(app, verify(&link), None);
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    let (status, body) = call!(app, verify("not-a-token"), None);
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+}